@@ -39,10 +39,12 @@ fn main() {
     });
 
     // computation runs until guards are joined or dropped.
-    if let Ok(guards) = guards {
-        for guard in guards.join() {
-            println!("result: {:?}", guard);
-        }
+    match guards {
+        Ok(guards) => {
+            for guard in guards.join() {
+                println!("result: {:?}", guard);
+            }
+        },
+        Err(error) => println!("error in computation: {}", error),
     }
-    else { println!("error in computation"); }
 }
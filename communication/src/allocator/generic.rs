@@ -3,9 +3,13 @@
 //! This type is useful in settings where it is difficult to write code generic in `A: Allocate`,
 //! for example closures whose type arguments must be specified.
 
+use std::ops::Range;
+use std::time::Duration;
+
 use allocator::{Allocate, AllocateBuilder, Message, Thread, Process};
 use allocator::zero_copy::allocator_process::{ProcessBuilder, ProcessAllocator};
 use allocator::zero_copy::allocator::{TcpBuilder, TcpAllocator};
+use allocator::zero_copy::shared_memory::{SharedMemoryBuilder, SharedMemoryAllocator};
 
 use {Push, Pull, Data};
 
@@ -20,6 +24,8 @@ pub enum Generic {
     ProcessBinary(ProcessAllocator),
     /// Inter-process allocator.
     ZeroCopy(TcpAllocator<Process>),
+    /// Inter-process allocator, for processes on one host.
+    SharedMemory(SharedMemoryAllocator),
 }
 
 impl Generic {
@@ -30,6 +36,7 @@ impl Generic {
             &Generic::Process(ref p) => p.index(),
             &Generic::ProcessBinary(ref pb) => pb.index(),
             &Generic::ZeroCopy(ref z) => z.index(),
+            &Generic::SharedMemory(ref s) => s.index(),
         }
     }
     /// The number of workers.
@@ -39,6 +46,17 @@ impl Generic {
             &Generic::Process(ref p) => p.peers(),
             &Generic::ProcessBinary(ref pb) => pb.peers(),
             &Generic::ZeroCopy(ref z) => z.peers(),
+            &Generic::SharedMemory(ref s) => s.peers(),
+        }
+    }
+    /// The indices of the workers of each process, in order of process.
+    pub fn processes(&self) -> Vec<Range<usize>> {
+        match self {
+            &Generic::Thread(ref t) => t.processes(),
+            &Generic::Process(ref p) => p.processes(),
+            &Generic::ProcessBinary(ref pb) => pb.processes(),
+            &Generic::ZeroCopy(ref z) => z.processes(),
+            &Generic::SharedMemory(ref s) => s.processes(),
         }
     }
     /// Constructs several send endpoints and one receive endpoint.
@@ -48,6 +66,7 @@ impl Generic {
             &mut Generic::Process(ref mut p) => p.allocate(identifier),
             &mut Generic::ProcessBinary(ref mut pb) => pb.allocate(identifier),
             &mut Generic::ZeroCopy(ref mut z) => z.allocate(identifier),
+            &mut Generic::SharedMemory(ref mut s) => s.allocate(identifier),
         }
     }
     /// Perform work before scheduling operators.
@@ -57,6 +76,7 @@ impl Generic {
             &mut Generic::Process(ref mut p) => p.pre_work(),
             &mut Generic::ProcessBinary(ref mut pb) => pb.pre_work(),
             &mut Generic::ZeroCopy(ref mut z) => z.pre_work(),
+            &mut Generic::SharedMemory(ref mut s) => s.pre_work(),
         }
     }
     /// Perform work after scheduling operators.
@@ -66,6 +86,47 @@ impl Generic {
             &mut Generic::Process(ref mut p) => p.post_work(),
             &mut Generic::ProcessBinary(ref mut pb) => pb.post_work(),
             &mut Generic::ZeroCopy(ref mut z) => z.post_work(),
+            &mut Generic::SharedMemory(ref mut s) => s.post_work(),
+        }
+    }
+    /// Indicates that outgoing data has exceeded its budget.
+    pub fn backpressured(&self) -> bool {
+        match self {
+            &Generic::Thread(ref t) => t.backpressured(),
+            &Generic::Process(ref p) => p.backpressured(),
+            &Generic::ProcessBinary(ref pb) => pb.backpressured(),
+            &Generic::ZeroCopy(ref z) => z.backpressured(),
+            &Generic::SharedMemory(ref s) => s.backpressured(),
+        }
+    }
+    /// Releases the resources of a channel.
+    pub fn release(&mut self, identifier: usize) {
+        match self {
+            &mut Generic::Thread(ref mut t) => t.release(identifier),
+            &mut Generic::Process(ref mut p) => p.release(identifier),
+            &mut Generic::ProcessBinary(ref mut pb) => pb.release(identifier),
+            &mut Generic::ZeroCopy(ref mut z) => z.release(identifier),
+            &mut Generic::SharedMemory(ref mut s) => s.release(identifier),
+        }
+    }
+    /// Appends the identifiers of channels that received data.
+    pub fn drain_events(&mut self, channels: &mut Vec<usize>) {
+        match self {
+            &mut Generic::Thread(ref mut t) => t.drain_events(channels),
+            &mut Generic::Process(ref mut p) => p.drain_events(channels),
+            &mut Generic::ProcessBinary(ref mut pb) => pb.drain_events(channels),
+            &mut Generic::ZeroCopy(ref mut z) => z.drain_events(channels),
+            &mut Generic::SharedMemory(ref mut s) => s.drain_events(channels),
+        }
+    }
+    /// Blocks until data may have arrived, or `duration` elapses.
+    pub fn await_events(&self, duration: Option<Duration>) {
+        match self {
+            &Generic::Thread(ref t) => t.await_events(duration),
+            &Generic::Process(ref p) => p.await_events(duration),
+            &Generic::ProcessBinary(ref pb) => pb.await_events(duration),
+            &Generic::ZeroCopy(ref z) => z.await_events(duration),
+            &Generic::SharedMemory(ref s) => s.await_events(duration),
         }
     }
 }
@@ -73,12 +134,17 @@ impl Generic {
 impl Allocate for Generic {
     fn index(&self) -> usize { self.index() }
     fn peers(&self) -> usize { self.peers() }
+    fn processes(&self) -> Vec<Range<usize>> { self.processes() }
     fn allocate<T: Data>(&mut self, identifier: usize) -> (Vec<Box<Push<Message<T>>>>, Box<Pull<Message<T>>>) {
         self.allocate(identifier)
     }
 
     fn pre_work(&mut self) { self.pre_work(); }
     fn post_work(&mut self) { self.post_work(); }
+    fn backpressured(&self) -> bool { self.backpressured() }
+    fn release(&mut self, identifier: usize) { self.release(identifier); }
+    fn drain_events(&mut self, channels: &mut Vec<usize>) { self.drain_events(channels); }
+    fn await_events(&self, duration: Option<Duration>) { self.await_events(duration); }
 }
 
 
@@ -96,6 +162,8 @@ pub enum GenericBuilder {
     ProcessBinary(ProcessBuilder),
     /// Builder for `ZeroCopy` allocator.
     ZeroCopy(TcpBuilder<Process>),
+    /// Builder for `SharedMemory` allocator.
+    SharedMemory(SharedMemoryBuilder),
 }
 
 impl AllocateBuilder for GenericBuilder {
@@ -106,6 +174,7 @@ impl AllocateBuilder for GenericBuilder {
             GenericBuilder::Process(p) => Generic::Process(p),
             GenericBuilder::ProcessBinary(pb) => Generic::ProcessBinary(pb.build()),
             GenericBuilder::ZeroCopy(z) => Generic::ZeroCopy(z.build()),
+            GenericBuilder::SharedMemory(s) => Generic::SharedMemory(s.build()),
         }
     }
 }
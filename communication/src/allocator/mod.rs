@@ -12,6 +12,9 @@ pub mod generic;
 
 pub mod zero_copy;
 
+use std::ops::Range;
+use std::time::Duration;
+
 use {Data, Push, Pull, Message};
 
 /// A proto-allocator, which implements `Send` and can be completed with `build`.
@@ -35,10 +38,42 @@ pub trait Allocate {
     fn index(&self) -> usize;
     /// The number of workers.
     fn peers(&self) -> usize;
+    /// The indices of the workers of each process, in order of process.
+    ///
+    /// The ranges partition `(0..self.peers())`. Allocators that do not communicate between
+    /// processes report all workers as members of one process.
+    fn processes(&self) -> Vec<Range<usize>> { vec![0 .. self.peers()] }
     /// Constructs several send endpoints and one receive endpoint.
     fn allocate<T: Data>(&mut self, identifier: usize) -> (Vec<Box<Push<Message<T>>>>, Box<Pull<Message<T>>>);
     /// Work performed before scheduling dataflows.
     fn pre_work(&mut self) { }
     /// Work performed after scheduling dataflows.
     fn post_work(&mut self) { }
+    /// Indicates that outgoing data has exceeded its budget.
+    ///
+    /// Workers should avoid producing more data until this returns false, though they should
+    /// continue to call `pre_work` and `post_work` so that data keeps moving.
+    fn backpressured(&self) -> bool { false }
+    /// Releases the resources of the channel `identifier`, which will not be allocated again.
+    ///
+    /// Workers release the identifiers of a dataflow once it completes. Data subsequently
+    /// received for a released channel are discarded.
+    fn release(&mut self, _identifier: usize) { }
+    /// Appends to `channels` the identifiers of channels that received data since the last call.
+    ///
+    /// Workers use the identifiers to schedule the operators reading from these channels. Data
+    /// that a worker sends to itself through an allocator without shared state, like `Thread`,
+    /// are not reported, and the sender is expected to schedule the recipient directly.
+    fn drain_events(&mut self, _channels: &mut Vec<usize>) { }
+    /// Blocks the worker's thread until data may have arrived for it, or `duration` elapses.
+    ///
+    /// Wake-ups may be spurious, and callers should check for work before calling again. The
+    /// thread is also woken by calls to `unpark` on its handle, which other threads may use to
+    /// indicate that they have work for the worker.
+    fn await_events(&self, duration: Option<Duration>) {
+        match duration {
+            Some(duration) => ::std::thread::park_timeout(duration),
+            None => ::std::thread::park(),
+        }
+    }
 }
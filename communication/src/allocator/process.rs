@@ -1,9 +1,12 @@
 //! Typed inter-thread, intra-process channels.
 
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::any::Any;
 use std::sync::mpsc::{Sender, Receiver, channel};
 use std::collections::HashMap;
+use std::thread::Thread as WorkerThread;
+use std::time::Duration;
 
 use allocator::{Allocate, AllocateBuilder, Message, Thread};
 use {Push, Pull};
@@ -15,6 +18,10 @@ pub struct Process {
     peers: usize,
     // below: `Box<Any+Send>` is a `Box<Vec<Option<(Vec<Sender<T>>, Receiver<T>)>>>`
     channels: Arc<Mutex<HashMap<usize, Box<Any+Send>>>>,
+    // threads of the workers, registered as they allocate or await events.
+    threads: Arc<Mutex<Vec<Option<WorkerThread>>>>,
+    // identifiers of channels that received data, for each worker, with their `Buzzer` flags.
+    events: Vec<Arc<Mutex<Vec<(usize, Arc<AtomicBool>)>>>>,
 }
 
 impl Process {
@@ -23,13 +30,25 @@ impl Process {
     /// Allocate a list of connected intra-process allocators.
     pub fn new_vector(count: usize) -> Vec<Process> {
         let channels = Arc::new(Mutex::new(HashMap::new()));
+        let threads = Arc::new(Mutex::new(vec![None; count]));
+        let events = (0 .. count).map(|_| Arc::new(Mutex::new(Vec::new()))).collect::<Vec<_>>();
         (0 .. count).map(|index| Process {
             inner:      Thread,
             index:      index,
             peers:      count,
             channels:   channels.clone(),
+            threads:    threads.clone(),
+            events:     events.clone(),
         }).collect()
     }
+
+    /// Records the current thread as that of this worker, so that other workers can wake it.
+    fn register(&self) {
+        let mut threads = self.threads.lock().ok().expect("mutex error?");
+        if threads[self.index].is_none() {
+            threads[self.index] = Some(::std::thread::current());
+        }
+    }
 }
 
 impl Allocate for Process {
@@ -37,6 +56,9 @@ impl Allocate for Process {
     fn peers(&self) -> usize { self.peers }
     fn allocate<T: Any+Send+'static>(&mut self, identifier: usize) -> (Vec<Box<Push<Message<T>>>>, Box<Pull<Message<T>>>) {
 
+        // workers that allocate channels may await data on them.
+        self.register();
+
         // ensure exclusive access to shared list of channels
         let mut channels = self.channels.lock().ok().expect("mutex error?");
 
@@ -47,9 +69,17 @@ impl Allocate for Process {
 
                 let mut pushers = Vec::new();
                 let mut pullers = Vec::new();
-                for _ in 0..self.peers {
+                for index in 0..self.peers {
                     let (s, r): (Sender<Message<T>>, Receiver<Message<T>>) = channel();
-                    pushers.push(Pusher { target: s });
+                    let buzzer = Buzzer {
+                        threads: self.threads.clone(),
+                        index,
+                        thread: None,
+                        channel: identifier,
+                        pending: Arc::new(AtomicBool::new(false)),
+                        events: self.events[index].clone(),
+                    };
+                    pushers.push(Pusher { target: s, buzzer });
                     pullers.push(Puller { source: r, current: None });
                 }
 
@@ -82,6 +112,31 @@ impl Allocate for Process {
         for s in send.into_iter() { temp.push(Box::new(s) as Box<Push<Message<T>>>); }
         (temp, Box::new(recv) as Box<Pull<super::Message<T>>>)
     }
+
+    fn await_events(&self, duration: Option<Duration>) {
+        self.register();
+        // Events recorded before the thread was registered did not wake it.
+        if !self.events[self.index].lock().ok().expect("mutex error?").is_empty() {
+            return;
+        }
+        match duration {
+            Some(duration) => ::std::thread::park_timeout(duration),
+            None => ::std::thread::park(),
+        }
+    }
+
+    fn drain_events(&mut self, channels: &mut Vec<usize>) {
+        for (channel, pending) in self.events[self.index].lock().ok().expect("mutex error?").drain(..) {
+            // Cleared before the channel is read, so that data sent afterwards is reported again.
+            pending.store(false, Ordering::SeqCst);
+            channels.push(channel);
+        }
+    }
+
+    fn release(&mut self, identifier: usize) {
+        // Channels not yet taken by every worker would otherwise remain in the shared map.
+        self.channels.lock().ok().expect("mutex error?").remove(&identifier);
+    }
 }
 
 impl AllocateBuilder for Process {
@@ -89,21 +144,54 @@ impl AllocateBuilder for Process {
     fn build(self) -> Self { self }
 }
 
+/// Reports data sent on a channel to the receiving worker, and wakes it once it has registered its thread.
+#[derive(Clone)]
+struct Buzzer {
+    threads: Arc<Mutex<Vec<Option<WorkerThread>>>>,
+    index: usize,
+    thread: Option<WorkerThread>,
+    channel: usize,
+    // set while the channel is recorded in `events` and not yet drained.
+    pending: Arc<AtomicBool>,
+    events: Arc<Mutex<Vec<(usize, Arc<AtomicBool>)>>>,
+}
+
+impl Buzzer {
+    fn buzz(&mut self) {
+        // Each channel is recorded, and its worker woken, at most once between drains; the worker
+        // reads the channel after the drain, and so sees any data sent before it.
+        if !self.pending.swap(true, Ordering::SeqCst) {
+            self.events.lock().ok().expect("mutex error?").push((self.channel, self.pending.clone()));
+            if self.thread.is_none() {
+                self.thread = self.threads.lock().ok().expect("mutex error?")[self.index].clone();
+            }
+            // An unregistered worker has not yet allocated channels or awaited events.
+            if let Some(ref thread) = self.thread {
+                thread.unpark();
+            }
+        }
+    }
+}
+
 /// The push half of an intra-process channel.
 struct Pusher<T> {
     target: Sender<T>,
+    buzzer: Buzzer,
 }
 
 impl<T> Clone for Pusher<T> {
     fn clone(&self) -> Self {
-        Pusher { target: self.target.clone() }
+        Pusher { target: self.target.clone(), buzzer: self.buzzer.clone() }
     }
 }
 
 impl<T> Push<T> for Pusher<T> {
     #[inline] fn push(&mut self, element: &mut Option<T>) {
         if let Some(element) = element.take() {
-            self.target.send(element).unwrap();
+            // A receiver that has released the channel discards data sent to it.
+            if self.target.send(element).is_ok() {
+                self.buzzer.buzz();
+            }
         }
     }
 }
@@ -121,3 +209,32 @@ impl<T> Pull<T> for Puller<T> {
         &mut self.current
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use allocator::{Allocate, Message};
+    use {Push, Pull};
+    use super::Process;
+
+    #[test]
+    fn events_recorded_once() {
+        let mut workers = Process::new_vector(2);
+        let (mut pushers, _puller0) = workers[0].allocate::<u64>(7);
+        let (_pushers1, mut puller1) = workers[1].allocate::<u64>(7);
+
+        for round in 0 .. 3 {
+            pushers[1].send(Message::from_typed(round));
+        }
+        let mut events = Vec::new();
+        workers[1].drain_events(&mut events);
+        assert_eq!(events, vec![7]);
+
+        // Data sent after a drain is reported again.
+        while puller1.recv().is_some() { }
+        pushers[1].send(Message::from_typed(3));
+        events.clear();
+        workers[1].drain_events(&mut events);
+        assert_eq!(events, vec![7]);
+    }
+}
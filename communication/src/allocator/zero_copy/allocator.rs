@@ -1,7 +1,9 @@
 //! Zero-copy allocator based on TCP.
 use std::rc::Rc;
-use std::cell::RefCell;
-use std::collections::{VecDeque, HashMap};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::time::Duration;
 // use std::sync::mpsc::{channel, Sender, Receiver};
 
 use bytes::arc::Bytes;
@@ -12,7 +14,9 @@ use {Allocate, Data, Push, Pull};
 use allocator::{Message, Process};
 
 use super::bytes_exchange::{BytesPull, SendEndpoint, MergeQueue, Signal};
-use super::push_pull::{Pusher, PullerInner};
+use super::push_pull::{Pusher, PullerInner, Inbox};
+use super::failure::Failures;
+use super::fingerprint::{self, Fingerprints, FINGERPRINT};
 
 /// Builds an instance of a TcpAllocator.
 ///
@@ -26,25 +30,56 @@ pub struct TcpBuilder<A: Allocate> {
     peers:      usize,              // number of peer allocators.
     sends:      Vec<MergeQueue>,    // for pushing bytes at remote processes.
     recvs:      Vec<MergeQueue>,    // for pulling bytes from remote processes.
+    budget:     usize,              // bytes queued for or from each remote process.
     signal:     Signal,
+    failures:   Failures,           // failures reported by communication threads.
+    shift:      usize,              // log2 of the size of serialization buffers.
+    offsets:    Vec<usize>,         // offsets[p] is the index of the first worker of process p.
+    type_checks: bool,              // announce the types of allocated channels.
 }
 
 /// Creates a vector of builders, sharing appropriate state.
+///
+/// Process `p` has `workers[p]` worker threads, whose global indices follow those of the
+/// workers of processes before it. Each queue between a worker and a communication thread is
+/// full once it holds `budget` bytes, each worker stops taking data from a remote process while
+/// `budget` bytes it has taken are not yet consumed, and workers serialize data into buffers of
+/// `1 << shift` bytes. If `type_checks` is set, workers announce the types of the channels they
+/// allocate.
+///
+/// The queues for each send thread are paired with the signal workers ping as they add data, and
+/// the queues for each receive thread with the signal workers ping as they drain data.
 pub fn new_vector(
     my_process: usize,
-    threads: usize,
-    processes: usize)
+    workers: &[usize],
+    budget: usize,
+    shift: usize,
+    type_checks: bool,
+    failures: Failures)
 // -> (Vec<TcpBuilder<Process>>, Vec<Receiver<Bytes>>, Vec<Sender<Bytes>>) {
--> (Vec<TcpBuilder<Process>>, Vec<(Vec<MergeQueue>, Signal)>, Vec<Vec<MergeQueue>>) {
+-> (Vec<TcpBuilder<Process>>, Vec<(Vec<MergeQueue>, Signal)>, Vec<(Vec<MergeQueue>, Signal)>) {
 
     // The results are a vector of builders, as well as the necessary shared state to build each
     // of the send and receive communication threads, respectively.
 
+    let threads = workers[my_process];
+    let processes = workers.len();
+
+    let mut offsets = Vec::with_capacity(processes + 1);
+    offsets.push(0);
+    for &count in workers.iter() {
+        let last = offsets[offsets.len() - 1];
+        offsets.push(last + count);
+    }
+
     let worker_signals: Vec<Signal> = (0 .. threads).map(|_| Signal::new()).collect();
     let network_signals: Vec<Signal> = (0 .. processes-1).map(|_| Signal::new()).collect();
 
-    let worker_to_network: Vec<Vec<_>> = (0 .. threads).map(|_| (0 .. processes-1).map(|p| MergeQueue::new(network_signals[p].clone())).collect()).collect();
-    let network_to_worker: Vec<Vec<_>> = (0 .. processes-1).map(|_| (0 .. threads).map(|t| MergeQueue::new(worker_signals[t].clone())).collect()).collect();
+    // Receive threads wait for workers to drain their queues, and so need their own signals.
+    let space_signals: Vec<Signal> = (0 .. processes-1).map(|_| Signal::new()).collect();
+
+    let worker_to_network: Vec<Vec<_>> = (0 .. threads).map(|_| (0 .. processes-1).map(|p| MergeQueue::bounded(network_signals[p].clone(), budget, Signal::new())).collect()).collect();
+    let network_to_worker: Vec<Vec<_>> = (0 .. processes-1).map(|p| (0 .. threads).map(|t| MergeQueue::bounded(worker_signals[t].clone(), budget, space_signals[p].clone())).collect()).collect();
 
     let worker_from_network: Vec<Vec<_>> = (0 .. threads).map(|t| (0 .. processes-1).map(|p| network_to_worker[p][t].clone()).collect()).collect();
     let network_from_worker: Vec<Vec<_>> = (0 .. processes-1).map(|p| (0 .. threads).map(|t| worker_to_network[t][p].clone()).collect()).collect();
@@ -59,17 +94,23 @@ pub fn new_vector(
         .map(|(index, (((inner, signal), sends), recvs))| {
             TcpBuilder {
                 inner,
-                index: my_process * threads + index,
-                peers: threads * processes,
+                index: offsets[my_process] + index,
+                peers: offsets[processes],
                 sends,
                 recvs,
+                budget,
                 signal,
+                failures: failures.clone(),
+                shift,
+                offsets: offsets.clone(),
+                type_checks,
             }})
         .collect();
 
     let sends = network_from_worker.into_iter().zip(network_signals).collect();
+    let recvs = network_to_worker.into_iter().zip(space_signals).collect();
 
-    (builders, sends, network_to_worker)
+    (builders, sends, recvs)
 }
 
 impl<A: Allocate> TcpBuilder<A> {
@@ -79,20 +120,30 @@ impl<A: Allocate> TcpBuilder<A> {
 
         let mut sends = Vec::new();
         for send in self.sends.into_iter() {
-            let sendpoint = SendEndpoint::new(send);
+            let sendpoint = SendEndpoint::new(send, self.shift);
             sends.push(Rc::new(RefCell::new(sendpoint)));
         }
 
+        let queued = Rc::new(self.recvs.iter().map(|_| Cell::new(0)).collect());
+
         TcpAllocator {
             inner: self.inner,
             index: self.index,
             peers: self.peers,
             // allocated: 0,
-            _signal: self.signal,
+            signal: self.signal,
             staged: Vec::new(),
             sends,
             recvs: self.recvs,
+            budget: self.budget,
+            queued,
             to_local: HashMap::new(),
+            failures: self.failures,
+            offsets: self.offsets,
+            type_checks: self.type_checks,
+            fingerprints: Fingerprints::new(self.index),
+            released: Released::new(),
+            events: Vec::new(),
         }
     }
 }
@@ -106,34 +157,61 @@ pub struct TcpAllocator<A: Allocate> {
     peers:      usize,                              // number of peer allocators (for typed channel allocation).
     // allocated:  usize,                              // indicates how many channels have been allocated (locally).
 
-    _signal:     Signal,
+    signal:     Signal,                             // pinged when data arrive for this worker.
 
     staged:     Vec<Bytes>,
 
     // sending, receiving, and responding to binary buffers.
     sends:      Vec<Rc<RefCell<SendEndpoint<MergeQueue>>>>,     // sends[x] -> goes to process x.
     recvs:      Vec<MergeQueue>,                                // recvs[x] <- from process x?.
-    to_local:   HashMap<usize, Rc<RefCell<VecDeque<Bytes>>>>,   // to worker-local typed pullers.
+    budget:     usize,                                          // bytes queued for or from each remote process.
+    queued:     Rc<Vec<Cell<usize>>>,                           // queued[x] bytes from recvs[x] not yet pulled.
+    to_local:   HashMap<usize, Rc<RefCell<Inbox>>>,             // to worker-local typed pullers.
+    failures:   Failures,                                       // failures reported by communication threads.
+    offsets:    Vec<usize>,                                     // offsets[p] is the index of the first worker of process p.
+    type_checks: bool,                                          // announce the types of allocated channels.
+    fingerprints: Fingerprints,                                 // types of channels, local and announced.
+    released:   Released,                                       // identifiers of released channels.
+    events:     Vec<usize>,                                     // channels that received data.
+}
+
+impl<A: Allocate> TcpAllocator<A> {
+    /// The index of the process hosting the worker with index `worker`.
+    fn process_of(&self, worker: usize) -> usize {
+        // Processes have at least one worker, so offsets are strictly increasing.
+        match self.offsets.binary_search(&worker) {
+            Ok(process) => process,
+            Err(process) => process - 1,
+        }
+    }
+    /// Indicates that data taken from some remote process await consumption beyond its budget.
+    fn backlogged(&self) -> bool {
+        self.queued.iter().any(|queued| queued.get() >= self.budget)
+    }
 }
 
 impl<A: Allocate> Allocate for TcpAllocator<A> {
     fn index(&self) -> usize { self.index }
     fn peers(&self) -> usize { self.peers }
+    fn processes(&self) -> Vec<Range<usize>> {
+        self.offsets.windows(2).map(|bounds| bounds[0] .. bounds[1]).collect()
+    }
     fn allocate<T: Data>(&mut self, identifier: usize) -> (Vec<Box<Push<Message<T>>>>, Box<Pull<Message<T>>>) {
 
         // Result list of boxed pushers.
         let mut pushes = Vec::<Box<Push<Message<T>>>>::new();
 
         // Inner exchange allocations.
-        let inner_peers = self.inner.peers();
         let (mut inner_sends, inner_recv) = self.inner.allocate(identifier);
 
+        let description = fingerprint::describe::<T>();
+
+        let my_process = self.process_of(self.index);
         for target_index in 0 .. self.peers() {
 
-            // TODO: crappy place to hardcode this rule.
-            let mut process_id = target_index / inner_peers;
+            let mut process_id = self.process_of(target_index);
 
-            if process_id == self.index / inner_peers {
+            if process_id == my_process {
                 pushes.push(inner_sends.remove(0));
             }
             else {
@@ -144,17 +222,28 @@ impl<A: Allocate> Allocate for TcpAllocator<A> {
                     target:     target_index,
                     length:     0,
                     seqno:      0,
+                    flags:      0,
                 };
 
                 // create, box, and stash new process_binary pusher.
-                if process_id > self.index / inner_peers { process_id -= 1; }
+                if process_id > my_process { process_id -= 1; }
+                if self.type_checks {
+                    fingerprint::announce(&header, &description, &mut self.sends[process_id].borrow_mut());
+                }
                 pushes.push(Box::new(Pusher::new(header, self.sends[process_id].clone())));
             }
         }
 
-        self.to_local.insert(identifier, Rc::new(RefCell::new(VecDeque::new())));
+        // Check the type against any announced before this allocation.
+        self.fingerprints.allocate(identifier, description);
 
-        let puller = Box::new(PullerInner::new(inner_recv, self.to_local[&identifier].clone()));
+        // Data may have arrived before this allocation, and must not be discarded. From now on they
+        // can be consumed, and count against the budgets of the processes they came from.
+        let queued = &self.queued;
+        let queue = self.to_local.entry(identifier).or_insert_with(|| Rc::new(RefCell::new(Inbox::new(queued.clone())))).clone();
+        queue.borrow_mut().bind();
+
+        let puller = Box::new(PullerInner::new(inner_recv, queue));
 
         (pushes, puller, )
     }
@@ -163,37 +252,106 @@ impl<A: Allocate> Allocate for TcpAllocator<A> {
     #[inline(never)]
     fn pre_work(&mut self) {
 
-        for recv in self.recvs.iter_mut() {
-            recv.drain_into(&mut self.staged);
+        // Surface failed connections, rather than wait on data that will not arrive.
+        if let Some(failure) = self.failures.first() {
+            ::std::panic::resume_unwind(Box::new(failure));
         }
 
-        for mut bytes in self.staged.drain(..) {
+        for (index, recv) in self.recvs.iter_mut().enumerate() {
+            if recv.is_poisoned() { panic!("MergeQueue poisoned."); }
 
-            // We expect that `bytes` contains an integral number of messages.
-            // No splitting occurs across allocations.
-            while bytes.len() > 0 {
+            // Leave data with the receive thread while data already taken from the same process
+            // for allocated channels are not consumed; the receive thread then stops reading, and
+            // the remote process experiences backpressure. Data for channels not yet allocated do
+            // not count, as receiving other data (e.g. progress updates) may be what the worker
+            // needs in order to go on to allocate them.
+            if self.queued[index].get() >= self.budget { continue; }
 
-                if let Some(header) = MessageHeader::try_read(&mut bytes[..]) {
-
-                    // Get the header and payload, ditch the header.
-                    let mut peel = bytes.extract_to(header.required_bytes());
-                    let _ = peel.extract_to(40);
+            recv.drain_into(&mut self.staged);
 
-                    // Ensure that a queue exists.
-                    // We may receive data before allocating, and shouldn't block.
-                    self.to_local
-                        .entry(header.channel)
-                        .or_insert_with(|| Rc::new(RefCell::new(VecDeque::new())))
-                        .borrow_mut()
-                        .push_back(peel);
+            for mut bytes in self.staged.drain(..) {
+
+                // We expect that `bytes` contains an integral number of messages.
+                // No splitting occurs across allocations.
+                while bytes.len() > 0 {
+
+                    if let Some(header) = MessageHeader::try_read(&mut bytes[..]) {
+
+                        // Get the header and payload, ditch the header.
+                        let mut peel = bytes.extract_to(header.required_bytes());
+                        let _ = peel.extract_to(::std::mem::size_of::<MessageHeader>());
+
+                        // Data for released channels are no longer needed.
+                        if self.released.contains(header.channel) {
+                            continue;
+                        }
+
+                        // Announcements of channel types are checked, rather than delivered.
+                        if header.flags & FINGERPRINT != 0 {
+                            self.fingerprints.announced(header.channel, header.source, &peel[..]);
+                            continue;
+                        }
+
+                        // Ensure that a queue exists.
+                        // We may receive data before allocating, and shouldn't block.
+                        let queued = &self.queued;
+                        self.to_local
+                            .entry(header.channel)
+                            .or_insert_with(|| Rc::new(RefCell::new(Inbox::new(queued.clone()))))
+                            .borrow_mut()
+                            .push(index, peel);
+
+                        self.events.push(header.channel);
+                    }
+                    else {
+                        println!("failed to read full header!");
+                    }
                 }
-                else {
-                    println!("failed to read full header!");
+            }
+        }
+
+        // Keep the consumers of queued data scheduled until receipt resumes.
+        if self.backlogged() {
+            for (channel, inbox) in self.to_local.iter() {
+                let inbox = inbox.borrow();
+                if inbox.is_bound() && !inbox.is_empty() {
+                    self.events.push(*channel);
                 }
             }
         }
     }
 
+    // Channels that received data from other processes, or from process-local peers.
+    fn drain_events(&mut self, channels: &mut Vec<usize>) {
+        self.inner.drain_events(channels);
+        channels.extend(self.events.drain(..));
+    }
+
+    // Indicates that data for some remote process exceeds its budget.
+    //
+    // Operators must still run to consume data already received, or processes that each wait
+    // for the other to receive would deadlock, and so a backlog of received data takes priority.
+    fn backpressured(&self) -> bool {
+        !self.backlogged() && self.sends.iter().any(|send| send.borrow().get_ref().is_full())
+    }
+
+    // Data from other processes ping `signal`, and the inner allocator unparks this thread.
+    fn await_events(&self, duration: Option<Duration>) {
+        match duration {
+            Some(duration) => self.signal.wait_timeout(duration),
+            None => self.signal.wait(),
+        }
+    }
+
+    fn release(&mut self, identifier: usize) {
+        self.inner.release(identifier);
+        if let Some(inbox) = self.to_local.remove(&identifier) {
+            inbox.borrow_mut().clear();
+        }
+        self.fingerprints.release(identifier);
+        self.released.insert(identifier);
+    }
+
     // Perform postparatory work, most likely sending un-full binary buffers.
     fn post_work(&mut self) {
         // Publish outgoing byte ledgers.
@@ -210,4 +368,154 @@ impl<A: Allocate> Allocate for TcpAllocator<A> {
         //     }
         // }
     }
-}
\ No newline at end of file
+}
+/// A set of released channel identifiers.
+///
+/// Workers release the identifiers of each dataflow as a contiguous range, so the set is stored
+/// as sorted, disjoint, and non-adjacent ranges, whose number is bounded by the number of
+/// dataflows that are still running rather than by the number that have ever completed.
+pub(crate) struct Released {
+    ranges: Vec<(usize, usize)>,    // half-open ranges of released identifiers.
+}
+
+impl Released {
+    /// Allocates an empty set.
+    pub(crate) fn new() -> Self {
+        Released { ranges: Vec::new() }
+    }
+    /// Indicates that `identifier` has been released.
+    pub(crate) fn contains(&self, identifier: usize) -> bool {
+        let position = self.position(identifier);
+        position > 0 && identifier < self.ranges[position - 1].1
+    }
+    /// Records that `identifier` has been released.
+    pub(crate) fn insert(&mut self, identifier: usize) {
+        if self.contains(identifier) { return; }
+        let position = self.position(identifier);
+        let extends_lower = position > 0 && self.ranges[position - 1].1 == identifier;
+        let extends_upper = position < self.ranges.len() && self.ranges[position].0 == identifier + 1;
+        match (extends_lower, extends_upper) {
+            (true, true) => {
+                self.ranges[position - 1].1 = self.ranges[position].1;
+                self.ranges.remove(position);
+            },
+            (true, false) => { self.ranges[position - 1].1 += 1; },
+            (false, true) => { self.ranges[position].0 -= 1; },
+            (false, false) => { self.ranges.insert(position, (identifier, identifier + 1)); },
+        }
+    }
+    /// The number of ranges starting at or before `identifier`.
+    fn position(&self, identifier: usize) -> usize {
+        match self.ranges.binary_search_by(|&(lower, _)| lower.cmp(&identifier)) {
+            Ok(position) => position + 1,
+            Err(position) => position,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use bytes::arc::Bytes;
+
+    use {Allocate, Pull};
+    use allocator::Message;
+    use networking::MessageHeader;
+    use allocator::zero_copy::bytes_exchange::BytesPush;
+    use allocator::zero_copy::failure::Failures;
+    use super::new_vector;
+
+    // A message for worker 0 on channel 0 from worker 1, as a receive thread would provide it.
+    fn message(seqno: usize, payload: Vec<u8>) -> Bytes {
+        let message = Message::from_typed(payload);
+        let header = MessageHeader { channel: 0, source: 1, target: 0, length: message.length_in_bytes(), seqno, flags: 0 };
+        let mut bytes = Vec::new();
+        header.write_to(&mut bytes).unwrap();
+        message.into_bytes(&mut bytes);
+        Bytes::from(bytes)
+    }
+
+    #[test]
+    fn process_of_uneven() {
+        // Processes with 2, 1, and 3 workers, from the perspective of the second.
+        let (builders, _sends, _recvs) = new_vector(1, &[2, 1, 3], 1 << 20, 16, false, Failures::new());
+        let allocator = builders.into_iter().next().unwrap().build();
+        assert_eq!(allocator.index(), 2);
+        assert_eq!(allocator.peers(), 6);
+        let processes: Vec<usize> = (0 .. 6).map(|worker| allocator.process_of(worker)).collect();
+        assert_eq!(processes, vec![0, 0, 1, 2, 2, 2]);
+        assert_eq!(allocator.processes(), vec![0 .. 2, 2 .. 3, 3 .. 6]);
+    }
+
+    #[test]
+    fn slow_receiver_bounded() {
+
+        let budget = 1 << 14;
+        let (mut builders, _sends, mut recvs) = new_vector(0, &[1, 1], budget, 16, false, Failures::new());
+        let allocator = Rc::new(RefCell::new(builders.remove(0).build()));
+        let (_pushers, mut puller) = allocator.borrow_mut().allocate::<Vec<u8>>(0);
+        let mut queue = (recvs.remove(0).0).remove(0);
+
+        // A receive thread that only stops when its queue is full, and a worker that never pulls.
+        let mut sent = 0;
+        for _ in 0 .. 1000 {
+            if !queue.is_full() {
+                queue.extend(Some(message(sent, vec![sent as u8; 1000])));
+                sent += 1;
+            }
+            allocator.borrow_mut().pre_work();
+            let queued = allocator.borrow().queued[0].get();
+            assert!(queued < 2 * budget + 1100, "{} bytes queued", queued);
+        }
+        assert!(queue.is_full());
+        assert!(sent < 100);
+
+        // Consumers of queued data remain scheduled.
+        let mut events = Vec::new();
+        allocator.borrow_mut().drain_events(&mut events);
+        assert!(events.contains(&0));
+
+        // Once the worker pulls, data are received again, in order.
+        let mut received = 0;
+        while received < sent {
+            while let Some(message) = puller.pull() {
+                assert_eq!(&message[..], &vec![received as u8; 1000][..]);
+                received += 1;
+            }
+            allocator.borrow_mut().pre_work();
+        }
+        assert_eq!(allocator.borrow().queued[0].get(), 0);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn unallocated_not_counted() {
+
+        let budget = 1 << 14;
+        let (mut builders, _sends, mut recvs) = new_vector(0, &[1, 1], budget, 16, false, Failures::new());
+        let allocator = Rc::new(RefCell::new(builders.remove(0).build()));
+        let mut queue = (recvs.remove(0).0).remove(0);
+
+        // Data for a channel not yet allocated are taken without limit, leaving room for others.
+        for sent in 0 .. 100 {
+            queue.extend(Some(message(sent, vec![sent as u8; 1000])));
+            allocator.borrow_mut().pre_work();
+            assert!(queue.is_empty());
+        }
+        assert_eq!(allocator.borrow().queued[0].get(), 0);
+
+        // Once allocated, they count against the budget until pulled.
+        let (_pushers, mut puller) = allocator.borrow_mut().allocate::<Vec<u8>>(0);
+        assert!(allocator.borrow().queued[0].get() >= budget);
+        let mut received = 0;
+        while let Some(message) = puller.pull() {
+            assert_eq!(&message[..], &vec![received as u8; 1000][..]);
+            received += 1;
+        }
+        assert_eq!(received, 100);
+        assert_eq!(allocator.borrow().queued[0].get(), 0);
+    }
+}
@@ -3,6 +3,7 @@
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::collections::{VecDeque, HashMap};
+use std::time::Duration;
 
 use bytes::arc::Bytes;
 
@@ -14,6 +15,7 @@ use allocator::Message;
 use super::bytes_exchange::{BytesPull, SendEndpoint, MergeQueue, Signal};
 
 use super::push_pull::{Pusher, Puller};
+use super::allocator::Released;
 
 /// Builds an instance of a ProcessAllocator.
 ///
@@ -27,13 +29,15 @@ pub struct ProcessBuilder {
     sends:      Vec<MergeQueue>,    // for pushing bytes at remote processes.
     recvs:      Vec<MergeQueue>,    // for pulling bytes from remote processes.
     signal:     Signal,
+    shift:      usize,              // log2 of the size of serialization buffers.
 }
 
 impl ProcessBuilder {
     /// Creates a vector of builders, sharing appropriate state.
     ///
-    /// This method requires access to a byte exchanger, from which it mints channels.
-    pub fn new_vector(count: usize) -> Vec<ProcessBuilder> {
+    /// This method requires access to a byte exchanger, from which it mints channels. Workers
+    /// serialize data into buffers of `1 << shift` bytes, as with `ConnectionOptions::buffer_shift`.
+    pub fn new_vector(count: usize, shift: usize) -> Vec<ProcessBuilder> {
 
         let signals: Vec<Signal> = (0 .. count).map(|_| Signal::new()).collect();
 
@@ -62,6 +66,7 @@ impl ProcessBuilder {
                     sends,
                     recvs,
                     signal,
+                    shift,
                 }
              )
              .collect()
@@ -72,7 +77,7 @@ impl ProcessBuilder {
 
         let mut sends = Vec::new();
         for send in self.sends.into_iter() {
-            let sendpoint = SendEndpoint::new(send);
+            let sendpoint = SendEndpoint::new(send, self.shift);
             sends.push(Rc::new(RefCell::new(sendpoint)));
         }
 
@@ -83,7 +88,9 @@ impl ProcessBuilder {
             sends,
             recvs: self.recvs,
             to_local: HashMap::new(),
-            _signal: self.signal,
+            released: Released::new(),
+            signal: self.signal,
+            events: Vec::new(),
         }
     }
 }
@@ -103,12 +110,14 @@ pub struct ProcessAllocator {
     index:      usize,                              // number out of peers
     peers:      usize,                              // number of peer allocators (for typed channel allocation).
 
-    _signal:     Signal,
+    signal:     Signal,                             // pinged when data arrive for this worker.
     // sending, receiving, and responding to binary buffers.
     staged:     Vec<Bytes>,
     sends:      Vec<Rc<RefCell<SendEndpoint<MergeQueue>>>>, // sends[x] -> goes to process x.
     recvs:      Vec<MergeQueue>,                            // recvs[x] <- from process x?.
     to_local:   HashMap<usize, Rc<RefCell<VecDeque<Bytes>>>>,          // to worker-local typed pullers.
+    released:   Released,                                               // identifiers of released channels.
+    events:     Vec<usize>,                                             // channels that received data.
 }
 
 impl Allocate for ProcessAllocator {
@@ -127,15 +136,17 @@ impl Allocate for ProcessAllocator {
                 target:     target_index,
                 length:     0,
                 seqno:      0,
+                flags:      0,
             };
 
             // create, box, and stash new process_binary pusher.
             pushes.push(Box::new(Pusher::new(header, self.sends[target_index].clone())));
         }
 
-        self.to_local.insert(identifier, Rc::new(RefCell::new(VecDeque::new())));
+        // Data may have arrived before this allocation, and must not be discarded.
+        let queue = self.to_local.entry(identifier).or_insert_with(|| Rc::new(RefCell::new(VecDeque::new()))).clone();
 
-        let puller = Box::new(Puller::new(self.to_local[&identifier].clone()));
+        let puller = Box::new(Puller::new(queue));
 
         (pushes, puller)
     }
@@ -145,6 +156,7 @@ impl Allocate for ProcessAllocator {
     fn pre_work(&mut self) {
 
         for recv in self.recvs.iter_mut() {
+            if recv.is_poisoned() { panic!("MergeQueue poisoned."); }
             recv.drain_into(&mut self.staged);
         }
 
@@ -158,7 +170,12 @@ impl Allocate for ProcessAllocator {
 
                     // Get the header and payload, ditch the header.
                     let mut peel = bytes.extract_to(header.required_bytes());
-                    let _ = peel.extract_to(40);
+                    let _ = peel.extract_to(::std::mem::size_of::<MessageHeader>());
+
+                    // Data for released channels are no longer needed.
+                    if self.released.contains(header.channel) {
+                        continue;
+                    }
 
                     // Ensure that a queue exists.
                     // We may receive data before allocating, and shouldn't block.
@@ -167,6 +184,8 @@ impl Allocate for ProcessAllocator {
                         .or_insert_with(|| Rc::new(RefCell::new(VecDeque::new())))
                         .borrow_mut()
                         .push_back(peel);
+
+                    self.events.push(header.channel);
                 }
                 else {
                     println!("failed to read full header!");
@@ -175,6 +194,23 @@ impl Allocate for ProcessAllocator {
         }
     }
 
+    fn drain_events(&mut self, channels: &mut Vec<usize>) {
+        channels.extend(self.events.drain(..));
+    }
+
+    // Data from other workers ping `signal`.
+    fn await_events(&self, duration: Option<Duration>) {
+        match duration {
+            Some(duration) => self.signal.wait_timeout(duration),
+            None => self.signal.wait(),
+        }
+    }
+
+    fn release(&mut self, identifier: usize) {
+        self.to_local.remove(&identifier);
+        self.released.insert(identifier);
+    }
+
     // Perform postparatory work, most likely sending un-full binary buffers.
     fn post_work(&mut self) {
         // Publish outgoing byte ledgers.
@@ -49,6 +49,17 @@ impl Signal {
             ::std::thread::park();
         }
     }
+    /// Blocks unless or until ping is called, or `timeout` elapses.
+    ///
+    /// Like `wait`, the first call registers the calling thread and does not block.
+    pub fn wait_timeout(&self, timeout: ::std::time::Duration) {
+        if self.thread.read().expect("failed to read thread").is_none() {
+            *self.thread.write().expect("failed to set thread") = Some(::std::thread::current())
+        }
+        else {
+            ::std::thread::park_timeout(timeout);
+        }
+    }
     /// Unblocks the current or next call to wait.
     pub fn ping(&self) {
         if let Some(thread) = self.thread.read().expect("failed to read thread").as_ref() {
@@ -57,29 +68,69 @@ impl Signal {
     }
 }
 
-use std::sync::atomic::{AtomicBool, Ordering};
-/// Who knows.
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+/// A queue of `Bytes` shared between one producer and one consumer.
+///
+/// A queue may have a byte budget, beyond which it reports itself as full. Pushing into a
+/// full queue does not block; instead, producers are expected to consult `is_full`, and
+/// either wait for the consumer with `wait_for_space` or stop producing data.
 #[derive(Clone)]
 pub struct MergeQueue {
     queue: Arc<Mutex<VecDeque<Bytes>>>, // queue of bytes.
     dirty: Signal,                      // indicates whether there may be data present.
     panic: Arc<AtomicBool>,
+    bytes: Arc<AtomicUsize>,            // number of bytes in the queue.
+    budget: usize,                      // number of bytes at which the queue is full.
+    space: Signal,                      // indicates that the queue may have drained.
 }
 
 impl MergeQueue {
     /// Allocates a new queue with an associated signal.
     pub fn new(signal: Signal) -> Self {
+        MergeQueue::bounded(signal, usize::max_value(), Signal::new())
+    }
+    /// Allocates a new queue which is full once it holds `budget` bytes.
+    ///
+    /// The `space` signal is pinged whenever the consumer drains the queue.
+    pub fn bounded(signal: Signal, budget: usize, space: Signal) -> Self {
         MergeQueue {
             queue: Arc::new(Mutex::new(VecDeque::new())),
             dirty: signal,
             panic: Arc::new(AtomicBool::new(false)),
+            bytes: Arc::new(AtomicUsize::new(0)),
+            budget,
+            space,
+        }
+    }
+    /// The number of bytes currently in the queue.
+    pub fn len(&self) -> usize {
+        self.bytes.load(Ordering::SeqCst)
+    }
+    /// Indicates that the queue holds at least its budget of bytes.
+    pub fn is_full(&self) -> bool {
+        self.len() >= self.budget
+    }
+    /// Blocks while the queue is full, unless or until the consumer goes away.
+    ///
+    /// This method should only be called by the producer, which must be the only thread that
+    /// waits on the queue's `space` signal.
+    pub fn wait_for_space(&self) {
+        while self.is_full() && !self.is_complete() && !self.is_poisoned() {
+            self.space.wait();
         }
     }
     /// Indicates that all input handles to the queue have dropped.
     pub fn is_complete(&self) -> bool {
-        if self.panic.load(Ordering::SeqCst) { panic!("MergeQueue poisoned."); }
         Arc::strong_count(&self.queue) == 1
     }
+    /// Indicates that the queue currently holds no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.queue.lock().expect("Failed to lock queue").is_empty()
+    }
+    /// Indicates that some handle to the queue was dropped while panicking.
+    pub fn is_poisoned(&self) -> bool {
+        self.panic.load(Ordering::SeqCst)
+    }
 }
 
 impl BytesPush for MergeQueue {
@@ -91,6 +142,7 @@ impl BytesPush for MergeQueue {
         let mut queue = self.queue.lock().expect("Failed to lock queue");
         let mut iterator = iterator.into_iter();
         if let Some(bytes) = iterator.next() {
+            self.bytes.fetch_add(bytes.len(), Ordering::SeqCst);
             let mut tail = if let Some(mut tail) = queue.pop_back() {
                 if let Err(bytes) = tail.try_merge(bytes) {
                     queue.push_back(::std::mem::replace(&mut tail, bytes));
@@ -103,6 +155,7 @@ impl BytesPush for MergeQueue {
             };
 
             for bytes in iterator {
+                self.bytes.fetch_add(bytes.len(), Ordering::SeqCst);
                 if let Err(bytes) = tail.try_merge(bytes) {
                     queue.push_back(::std::mem::replace(&mut tail, bytes));
                 }
@@ -114,9 +167,13 @@ impl BytesPush for MergeQueue {
 
 impl BytesPull for MergeQueue {
     fn drain_into(&mut self, vec: &mut Vec<Bytes>) {
-        if self.panic.load(Ordering::SeqCst) { panic!("MergeQueue poisoned."); }
         let mut queue = self.queue.lock().expect("unable to lock mutex");
-        vec.extend(queue.drain(..));
+        if !queue.is_empty() {
+            let drained: usize = queue.iter().map(|bytes| bytes.len()).sum();
+            self.bytes.fetch_sub(drained, Ordering::SeqCst);
+            vec.extend(queue.drain(..));
+            self.space.ping();
+        }
     }
 }
 
@@ -135,6 +192,7 @@ impl Drop for MergeQueue {
         // Drop the queue before pinging.
         self.queue = Arc::new(Mutex::new(VecDeque::new()));
         self.dirty.ping();
+        self.space.ping();
     }
 }
 
@@ -155,11 +213,11 @@ impl<P: BytesPush> SendEndpoint<P> {
         }
     }
 
-    /// Allocates a new `BytesSendEndpoint` from a shared queue.
-    pub fn new(queue: P) -> Self {
+    /// Allocates a new `BytesSendEndpoint` from a shared queue, with buffers of `1 << shift` bytes.
+    pub fn new(queue: P, shift: usize) -> Self {
         SendEndpoint {
             send: queue,
-            buffer: BytesSlab::new(20),
+            buffer: BytesSlab::new(shift),
         }
     }
     /// Makes the next `bytes` bytes valid.
@@ -184,6 +242,10 @@ impl<P: BytesPush> SendEndpoint<P> {
     pub fn publish(&mut self) {
         self.send_buffer();
     }
+    /// A reference to the target of the endpoint.
+    pub fn get_ref(&self) -> &P {
+        &self.send
+    }
 }
 
 impl<P: BytesPush> Drop for SendEndpoint<P> {
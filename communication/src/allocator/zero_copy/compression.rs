@@ -0,0 +1,185 @@
+//! Compression of message payloads sent between processes.
+//!
+//! Compression is applied by send threads to individual messages, and recorded in the message
+//! header with the `COMPRESSED` flag. A compressed payload is the eight byte length of the
+//! uncompressed payload followed by the deflated payload. Receive threads decompress flagged
+//! messages before handing them to workers, who never observe compressed data.
+
+use std::io::{Read, Write, Result, Error, ErrorKind};
+
+use abomonation::{encode, decode};
+use flate2;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+
+use bytes::arc::Bytes;
+use networking::MessageHeader;
+
+/// Header flag indicating that the payload is compressed.
+pub const COMPRESSED: usize = 1;
+
+/// Configuration for compressing data sent to other processes.
+#[derive(Copy, Clone, Debug)]
+pub struct Compression {
+    /// Compression level, from 0 (fastest) to 9 (smallest).
+    pub level: u32,
+    /// Payloads with fewer bytes than this are sent uncompressed.
+    pub threshold: usize,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression {
+            level: 1,
+            threshold: 1 << 10,
+        }
+    }
+}
+
+impl Compression {
+    /// Attempts to compress `payload` into `buffer`, returning the header to send with it.
+    ///
+    /// Returns `None` if the payload is too small to bother with, or if compression does not
+    /// reduce its size; in either case the original message should be sent instead.
+    pub fn compress(&self, header: &MessageHeader, payload: &[u8], buffer: &mut Vec<u8>) -> Option<MessageHeader> {
+        if payload.len() < self.threshold { return None; }
+        buffer.clear();
+        unsafe { encode(&(payload.len() as u64), buffer) }.expect("compression failed");
+        let mut encoder = DeflateEncoder::new(::std::mem::replace(buffer, Vec::new()), flate2::Compression::new(self.level));
+        // Writes to a `Vec` do not fail.
+        encoder.write_all(payload).expect("compression failed");
+        *buffer = encoder.finish().expect("compression failed");
+        if buffer.len() < payload.len() {
+            let mut compressed = *header;
+            compressed.length = buffer.len();
+            compressed.flags |= COMPRESSED;
+            Some(compressed)
+        }
+        else {
+            None
+        }
+    }
+}
+
+/// The largest ratio of uncompressed to compressed bytes that deflate can achieve.
+const MAX_RATIO: usize = 1032;
+
+/// Reconstructs the uncompressed message from a complete compressed message.
+///
+/// The resulting `Bytes` contain a header without the `COMPRESSED` flag followed by the
+/// uncompressed payload, just as if the message had been sent uncompressed. The declared length
+/// of the uncompressed payload is checked against `limit`, and against what the compressed
+/// payload could possibly hold, before anything is allocated, and the payload must decompress to
+/// exactly the declared length.
+pub fn decompress(header: &MessageHeader, message: &[u8], limit: usize) -> Result<Bytes> {
+    let payload = &message[::std::mem::size_of::<MessageHeader>() .. header.required_bytes()];
+    if payload.len() < 8 {
+        return Err(Error::new(ErrorKind::InvalidData, "compressed payload too short"));
+    }
+    let mut length = [0u8; 8];
+    length.copy_from_slice(&payload[.. 8]);
+    let length = unsafe { decode::<u64>(&mut length) }.map(|(length, _)| *length).unwrap_or(0);
+    let deflated = payload.len() - 8;
+    if length > limit as u64 || length > (deflated as u64).saturating_mul(MAX_RATIO as u64) {
+        return Err(Error::new(ErrorKind::InvalidData, format!("compressed payload declares {} bytes, more than allowed", length)));
+    }
+
+    let mut uncompressed = *header;
+    uncompressed.length = length as usize;
+    uncompressed.flags &= !COMPRESSED;
+
+    let mut result = Vec::with_capacity(uncompressed.required_bytes());
+    uncompressed.write_to(&mut result)?;
+    // Read at most one byte more than declared, which is enough to detect a longer payload.
+    DeflateDecoder::new(&payload[8 ..]).take(length + 1).read_to_end(&mut result)?;
+    if result.len() != uncompressed.required_bytes() {
+        return Err(Error::new(ErrorKind::InvalidData, "decompressed payload has incorrect length"));
+    }
+    Ok(Bytes::from(result))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::ErrorKind;
+
+    use abomonation::encode;
+    use networking::MessageHeader;
+    use super::{Compression, decompress, COMPRESSED};
+
+    fn header(length: usize) -> MessageHeader {
+        MessageHeader { channel: 3, source: 1, target: 2, length, seqno: 7, flags: 0 }
+    }
+
+    // Compresses `payload`, returning the header and complete message to decompress.
+    fn compressed(payload: &[u8]) -> (MessageHeader, Vec<u8>) {
+        let mut buffer = Vec::new();
+        let compressed = Compression::default().compress(&header(payload.len()), payload, &mut buffer).expect("payload did not compress");
+        let mut message = Vec::new();
+        compressed.write_to(&mut message).unwrap();
+        message.extend_from_slice(&buffer[..]);
+        (compressed, message)
+    }
+
+    // Replaces the declared uncompressed length of a compressed `message`.
+    fn declare(message: &mut Vec<u8>, length: u64) {
+        let offset = ::std::mem::size_of::<MessageHeader>();
+        let mut bytes = Vec::new();
+        unsafe { encode(&length, &mut bytes).unwrap(); }
+        message[offset .. offset + 8].copy_from_slice(&bytes[..]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let payload: Vec<u8> = (0 .. 10_000).map(|i| (i % 7) as u8).collect();
+        let (header, message) = compressed(&payload[..]);
+        assert!(header.flags & COMPRESSED != 0);
+        assert!(header.length < payload.len());
+
+        let mut bytes = decompress(&header, &message[..], 1 << 20).unwrap();
+        let result = MessageHeader::try_read(&mut bytes[..]).unwrap();
+        assert_eq!(result.flags & COMPRESSED, 0);
+        assert_eq!(result.length, payload.len());
+        assert_eq!((result.channel, result.source, result.target, result.seqno), (3, 1, 2, 7));
+        assert_eq!(&bytes[::std::mem::size_of::<MessageHeader>() ..], &payload[..]);
+    }
+
+    #[test]
+    fn truncated() {
+        let payload: Vec<u8> = (0 .. 10_000).map(|i| (i % 7) as u8).collect();
+        let (mut header, mut message) = compressed(&payload[..]);
+        // Keep the length prefix and half of the deflated payload.
+        header.length = 8 + (header.length - 8) / 2;
+        message.truncate(::std::mem::size_of::<MessageHeader>() + header.length);
+        assert!(decompress(&header, &message[..], 1 << 20).is_err());
+    }
+
+    #[test]
+    fn oversize() {
+        let payload: Vec<u8> = (0 .. 10_000).map(|i| (i % 7) as u8).collect();
+        let (header, mut message) = compressed(&payload[..]);
+
+        // Beyond the configured limit.
+        let error = decompress(&header, &message[..], 1000).err().expect("oversize payload accepted");
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        // Beyond what the compressed payload could hold, with no limit.
+        declare(&mut message, 1 << 60);
+        let error = decompress(&header, &message[..], usize::max_value()).err().expect("oversize payload accepted");
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn mismatched_length() {
+        let payload: Vec<u8> = (0 .. 10_000).map(|i| (i % 7) as u8).collect();
+        let (header, mut message) = compressed(&payload[..]);
+
+        // Declared shorter than the real payload.
+        declare(&mut message, 5_000);
+        assert!(decompress(&header, &message[..], 1 << 20).is_err());
+
+        // Declared longer than the real payload.
+        declare(&mut message, 20_000);
+        assert!(decompress(&header, &message[..], 1 << 20).is_err());
+    }
+}
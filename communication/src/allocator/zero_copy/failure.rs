@@ -0,0 +1,70 @@
+//! Reporting of failed connections to remote processes.
+
+use std::fmt::{Display, Formatter, Error};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A failed connection to a remote process, as observed by a send or receive thread.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PeerFailure {
+    /// The index of the process observing the failure.
+    pub process: usize,
+    /// The index of the remote process whose connection failed.
+    pub remote: usize,
+    /// True if observed by the send thread, false if by the receive thread.
+    pub sender: bool,
+    /// A description of the failure.
+    pub error: String,
+}
+
+impl Display for PeerFailure {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let thread = if self.sender { "send" } else { "recv" };
+        write!(f, "process {} lost connection to process {} ({} thread): {}", self.process, self.remote, thread, self.error)
+    }
+}
+
+/// Failures observed by the communication threads of a process.
+///
+/// Send and receive threads report failures here rather than panicking, and workers poll the
+/// report (cheaply) to learn that they should stop waiting on data that will not arrive.
+#[derive(Clone)]
+pub struct Failures {
+    failed: Arc<AtomicBool>,
+    reports: Arc<Mutex<Vec<PeerFailure>>>,
+}
+
+impl Failures {
+    /// Allocates a new empty failure report.
+    pub fn new() -> Self {
+        Failures {
+            failed: Arc::new(AtomicBool::new(false)),
+            reports: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+    /// Records a failure.
+    pub fn report(&self, failure: PeerFailure) {
+        // A poisoned lock indicates a panic while reporting; the flag is still worth setting.
+        if let Ok(mut reports) = self.reports.lock() {
+            reports.push(failure);
+        }
+        self.failed.store(true, Ordering::SeqCst);
+    }
+    /// The first reported failure, if any.
+    ///
+    /// Later failures are often consequences of the first, for example remote processes shutting
+    /// down in response to it, and so the first is the most informative.
+    #[inline]
+    pub fn first(&self) -> Option<PeerFailure> {
+        if self.failed.load(Ordering::SeqCst) {
+            self.reports.lock().ok().and_then(|reports| reports.first().cloned())
+        }
+        else {
+            None
+        }
+    }
+    /// All reported failures, in the order they were reported.
+    pub fn all(&self) -> Vec<PeerFailure> {
+        self.reports.lock().map(|reports| reports.clone()).unwrap_or(Vec::new())
+    }
+}
@@ -0,0 +1,99 @@
+//! Checks that workers agree on the types of the channels they allocate.
+//!
+//! Workers must allocate channels with the same type for the same identifier; otherwise data
+//! are decoded as the wrong type. When type checks are enabled, each worker announces the type
+//! of each channel it allocates to each remote worker, with a message flagged `FINGERPRINT`
+//! whose payload describes the type, padded with zeros to keep subsequent headers aligned.
+//! The message precedes any data on the channel, and the
+//! receiving worker compares the description with the type it allocates, reporting both types
+//! if they differ.
+
+use std::io::Write;
+use std::collections::HashMap;
+
+use networking::MessageHeader;
+
+use super::bytes_exchange::{BytesPush, SendEndpoint};
+
+/// Header flag indicating that the payload describes the type of the channel, rather than data.
+pub const FINGERPRINT: usize = 2;
+
+/// A description of the type `T`, by name and size.
+pub fn describe<T: 'static>() -> String {
+    format!("{} ({} bytes)", ::std::any::type_name::<T>(), ::std::mem::size_of::<T>())
+}
+
+/// Writes a message announcing `description` as the type of the channel of `header`.
+pub fn announce<P: BytesPush>(header: &MessageHeader, description: &str, endpoint: &mut SendEndpoint<P>) {
+    let mut header = *header;
+    header.length = (description.len() + 7) & !7;
+    header.flags |= FINGERPRINT;
+    {
+        let mut bytes = endpoint.reserve(header.required_bytes());
+        let writer = &mut bytes;
+        header.write_to(writer).expect("failed to write header!");
+        writer.write_all(description.as_bytes()).expect("failed to write fingerprint!");
+        writer.write_all(&[0u8; 7][.. header.length - description.len()]).expect("failed to write fingerprint!");
+    }
+    endpoint.make_valid(header.required_bytes());
+}
+
+/// The types of channels, as allocated locally and as announced by remote workers.
+pub struct Fingerprints {
+    index: usize,
+    local: HashMap<usize, String>,
+    remote: HashMap<usize, Vec<(usize, String)>>,
+}
+
+impl Fingerprints {
+    /// Allocates an empty record for the worker with index `index`.
+    pub fn new(index: usize) -> Self {
+        Fingerprints {
+            index,
+            local: HashMap::new(),
+            remote: HashMap::new(),
+        }
+    }
+
+    /// Records the type of a locally allocated channel, checking earlier announcements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a remote worker announced a different type for the channel.
+    pub fn allocate(&mut self, channel: usize, description: String) {
+        if let Some(announced) = self.remote.remove(&channel) {
+            for (source, remote) in announced {
+                check(channel, self.index, &description, source, &remote);
+            }
+        }
+        self.local.insert(channel, description);
+    }
+
+    /// Records the type a remote worker announced for a channel, checking any local allocation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the channel was allocated locally with a different type.
+    pub fn announced(&mut self, channel: usize, source: usize, payload: &[u8]) {
+        let length = payload.iter().rposition(|&byte| byte != 0).map(|position| position + 1).unwrap_or(0);
+        let remote = String::from_utf8_lossy(&payload[.. length]).into_owned();
+        if let Some(local) = self.local.get(&channel) {
+            check(channel, self.index, local, source, &remote);
+            return;
+        }
+        self.remote.entry(channel).or_insert_with(Vec::new).push((source, remote));
+    }
+
+    /// Forgets the type of a released channel.
+    pub fn release(&mut self, channel: usize) {
+        self.local.remove(&channel);
+        self.remote.remove(&channel);
+    }
+}
+
+/// Panics with both types if `local` and `remote` differ.
+fn check(channel: usize, index: usize, local: &str, source: usize, remote: &str) {
+    if local != remote {
+        panic!("type mismatch on channel {}: worker {} allocated {}, but worker {} allocated {}", channel, index, local, source, remote);
+    }
+}
@@ -0,0 +1,163 @@
+//! Validated framing of messages sent between processes.
+//!
+//! In validated mode, send threads precede each message with a `Frame`, which holds magic
+//! bytes identifying the protocol and checksums of the message header and payload. Receive
+//! threads check the frame before trusting the header, so that a corrupted stream or a stray
+//! connection is reported as an error, rather than decoded into nonsense.
+
+use std::io::{Write, Result, Error, ErrorKind};
+
+use abomonation::{encode, decode};
+use flate2::Crc;
+
+use networking::{MessageHeader, PROTOCOL_VERSION};
+
+/// Magic bytes that begin each frame, ending with the protocol version.
+pub const MAGIC: u64 = 0x54494d454c590000 | PROTOCOL_VERSION;
+
+/// Framing data preceding each message in validated mode.
+#[derive(Copy, Clone, Debug, Abomonation)]
+pub struct Frame {
+    /// The constant `MAGIC`.
+    pub magic: u64,
+    /// Checksum of the encoded message header.
+    pub header: u64,
+    /// Checksum of the message payload.
+    pub payload: u64,
+}
+
+impl Frame {
+    /// Writes `header` and `payload` preceded by their frame.
+    pub fn write_to<W: Write>(writer: &mut W, header: &MessageHeader, payload: &[u8]) -> Result<()> {
+        let mut encoded = Vec::with_capacity(::std::mem::size_of::<MessageHeader>());
+        header.write_to(&mut encoded)?;
+        let frame = Frame {
+            magic: MAGIC,
+            header: checksum(&encoded[..]),
+            payload: checksum(payload),
+        };
+        unsafe { encode(&frame, writer)?; }
+        writer.write_all(&encoded[..])?;
+        writer.write_all(payload)
+    }
+
+    /// Returns the header of a framed message, when `bytes` contain all of it.
+    ///
+    /// The header is validated as soon as it is available, so that a corrupted length does not
+    /// leave the caller waiting for data that will never arrive. An error is returned if the
+    /// magic bytes or either checksum is incorrect, or if the header declares a payload of more
+    /// than `max_message` bytes.
+    pub fn try_read(bytes: &mut [u8], max_message: usize) -> Result<Option<MessageHeader>> {
+        let frame_bytes = ::std::mem::size_of::<Frame>();
+        let header_bytes = ::std::mem::size_of::<MessageHeader>();
+        if bytes.len() < frame_bytes + header_bytes {
+            return Ok(None);
+        }
+        let frame = unsafe { decode::<Frame>(&mut bytes[.. frame_bytes]) }.map(|(frame, _)| *frame).expect("frame too short");
+        if frame.magic != MAGIC {
+            return Err(Error::new(ErrorKind::InvalidData, format!("received invalid magic bytes {:#x}; expected {:#x}", frame.magic, MAGIC)));
+        }
+        if checksum(&bytes[frame_bytes .. frame_bytes + header_bytes]) != frame.header {
+            return Err(Error::new(ErrorKind::InvalidData, "received corrupted message header"));
+        }
+        let header = unsafe { decode::<MessageHeader>(&mut bytes[frame_bytes .. frame_bytes + header_bytes]) }.map(|(header, _)| *header).expect("header too short");
+        check_length(&header, max_message)?;
+        if bytes.len() < frame_bytes + header.required_bytes() {
+            return Ok(None);
+        }
+        if checksum(&bytes[frame_bytes + header_bytes .. frame_bytes + header.required_bytes()]) != frame.payload {
+            return Err(Error::new(ErrorKind::InvalidData, format!("received corrupted payload for channel {}", header.channel)));
+        }
+        Ok(Some(header))
+    }
+}
+
+/// Returns an error if `header` declares a payload of more than `max_message` bytes.
+pub fn check_length(header: &MessageHeader, max_message: usize) -> Result<()> {
+    if header.length > max_message {
+        return Err(Error::new(ErrorKind::InvalidData, format!("received message of {} bytes for channel {}, more than the maximum of {}", header.length, header.channel, max_message)));
+    }
+    Ok(())
+}
+
+/// A checksum of `bytes`.
+fn checksum(bytes: &[u8]) -> u64 {
+    let mut crc = Crc::new();
+    crc.update(bytes);
+    crc.sum() as u64
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::{ErrorKind, Result};
+
+    use networking::MessageHeader;
+    use super::{Frame, MAGIC};
+
+    // A framed message on channel 5 with `payload`.
+    fn framed(payload: &[u8]) -> Vec<u8> {
+        let header = MessageHeader { channel: 5, source: 0, target: 1, length: payload.len(), seqno: 0, flags: 0 };
+        let mut bytes = Vec::new();
+        Frame::write_to(&mut bytes, &header, payload).unwrap();
+        bytes
+    }
+
+    fn assert_invalid(result: Result<Option<MessageHeader>>, message: &str) {
+        match result {
+            Err(error) => {
+                assert_eq!(error.kind(), ErrorKind::InvalidData, "{}", error);
+                assert!(error.to_string().contains(message), "{}", error);
+            },
+            Ok(_) => panic!("frame accepted, expecting: {}", message),
+        }
+    }
+
+    #[test]
+    fn valid() {
+        let mut bytes = framed(b"payload!");
+        let header = Frame::try_read(&mut bytes[..], 1 << 20).unwrap().expect("complete frame not read");
+        assert_eq!((header.channel, header.length), (5, 8));
+
+        // Incomplete frames are awaited.
+        let length = bytes.len();
+        assert!(Frame::try_read(&mut bytes[.. length - 1], 1 << 20).unwrap().is_none());
+        assert!(Frame::try_read(&mut bytes[.. 10], 1 << 20).unwrap().is_none());
+    }
+
+    #[test]
+    fn bad_magic() {
+        let mut bytes = framed(b"payload!");
+        bytes[0] ^= 1;
+        assert_invalid(Frame::try_read(&mut bytes[..], 1 << 20), &format!("expected {:#x}", MAGIC));
+    }
+
+    #[test]
+    fn bad_header_checksum() {
+        let mut bytes = framed(b"payload!");
+        // Corrupt the channel of the header, which follows the frame.
+        bytes[::std::mem::size_of::<Frame>()] ^= 1;
+        assert_invalid(Frame::try_read(&mut bytes[..], 1 << 20), "corrupted message header");
+    }
+
+    #[test]
+    fn bad_payload_checksum() {
+        let mut bytes = framed(b"payload!");
+        let length = bytes.len();
+        bytes[length - 1] ^= 1;
+        assert_invalid(Frame::try_read(&mut bytes[..], 1 << 20), "corrupted payload for channel 5");
+    }
+
+    #[test]
+    fn oversize_length() {
+        // Rejected from the header alone, before the payload arrives.
+        let mut bytes = framed(&[0u8; 100][..]);
+        let partial = ::std::mem::size_of::<Frame>() + ::std::mem::size_of::<MessageHeader>();
+        assert_invalid(Frame::try_read(&mut bytes[.. partial], 99), "received message of 100 bytes for channel 5, more than the maximum of 99");
+
+        let header = MessageHeader { channel: 5, source: 0, target: 1, length: usize::max_value(), seqno: 0, flags: 0 };
+        let mut bytes = Vec::new();
+        Frame::write_to(&mut bytes, &header, &[]).unwrap();
+        assert_invalid(Frame::try_read(&mut bytes[..], usize::max_value() - 1), "more than the maximum");
+    }
+}
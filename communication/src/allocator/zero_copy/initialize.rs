@@ -2,9 +2,12 @@
 
 use std::sync::Arc;
 use allocator::Process;
-use networking::create_sockets;
+use std::path::PathBuf;
+use networking::{create_sockets, Connection, ConnectionOptions, UNIX_PREFIX};
+use super::shared_memory::{self, connect_rings, doorbell_loop, SharedMemoryBuilder};
 use super::tcp::{send_loop, recv_loop};
 use super::allocator::{TcpBuilder, new_vector};
+use super::failure::{Failures, PeerFailure};
 
 /// Join handles for send and receive threads.
 ///
@@ -13,41 +16,175 @@ use super::allocator::{TcpBuilder, new_vector};
 pub struct CommsGuard {
     send_guards: Vec<::std::thread::JoinHandle<()>>,
     recv_guards: Vec<::std::thread::JoinHandle<()>>,
+    failures: Failures,
 }
 
-impl Drop for CommsGuard {
-    fn drop(&mut self) {
+impl CommsGuard {
+    /// Failures reported by the communication threads so far.
+    pub fn failures(&self) -> Vec<PeerFailure> {
+        self.failures.all()
+    }
+    /// Joins with the communication threads, and reports the first connection failure, if any.
+    pub fn join(mut self) -> Result<(), PeerFailure> {
+        self.join_threads();
+        match self.failures.first() {
+            Some(failure) => Err(failure),
+            None => Ok(()),
+        }
+    }
+    fn join_threads(&mut self) {
+        let failed = self.failures.first().is_some();
         for handle in self.send_guards.drain(..) {
-            handle.join().expect("Send thread panic");
+            // Threads only panic once connections have failed, for example from poisoned queues.
+            if handle.join().is_err() && !failed {
+                panic!("Send thread panic");
+            }
         }
         // println!("SEND THREADS JOINED");
         for handle in self.recv_guards.drain(..) {
-            handle.join().expect("Recv thread panic");
+            if handle.join().is_err() && !failed {
+                panic!("Recv thread panic");
+            }
         }
         // println!("RECV THREADS JOINED");
     }
 }
 
+impl Drop for CommsGuard {
+    fn drop(&mut self) {
+        self.join_threads();
+    }
+}
+
 use ::logging::{CommunicationSetup, CommunicationEvent};
 use logging_core::Logger;
 
 /// Initializes network connections
+///
+/// The number of worker threads in each process is listed in `workers`.
 pub fn initialize_networking(
     addresses: Vec<String>,
     my_index: usize,
-    threads: usize,
+    workers: Vec<usize>,
     noisy: bool,
+    options: ConnectionOptions,
     log_sender: Box<Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync>)
 -> ::std::io::Result<(Vec<TcpBuilder<Process>>, CommsGuard)>
 // where
 //     F: Fn(CommunicationSetup)->Option<Logger<CommunicationEvent>>+Send+Sync+'static,
 {
+    let connections = create_sockets(addresses, my_index, &workers[..], noisy, options.clone())?;
+    initialize_networking_from(connections, my_index, workers, options, log_sender)
+}
+
+/// Initializes shared-memory connections between processes on the same host.
+///
+/// Processes rendezvous through Unix domain sockets in `directory`, which must be shared by
+/// all processes, and then exchange data through memory-mapped rings of `capacity` bytes
+/// created in the same directory. The number of worker threads in each process is listed in
+/// `workers`. The queue budget and buffer size of `options` apply, but compression and frame
+/// validation, which are meant for networks, do not.
+pub fn initialize_shared_memory(
+    directory: PathBuf,
+    my_index: usize,
+    workers: Vec<usize>,
+    noisy: bool,
+    options: ConnectionOptions,
+    capacity: usize,
+    log_sender: Box<Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync>)
+-> ::std::io::Result<(Vec<SharedMemoryBuilder>, CommsGuard)>
+{
+    let addresses = (0 .. workers.len()).map(|index| format!("{}{}", UNIX_PREFIX, directory.join(format!("process-{}.sock", index)).display())).collect();
+    let connections = create_sockets(addresses, my_index, &workers[..], noisy, options.clone())?;
+    let mut rings = connect_rings(connections, &directory, my_index, capacity)?;
+
     let log_sender = Arc::new(log_sender);
-    let processes = addresses.len();
+    let worker_offset: usize = workers[.. my_index].iter().sum();
+    let budget = options.queue_budget.unwrap_or(usize::max_value());
+    let shift = options.buffer_shift;
+
+    let failures = Failures::new();
+    let (builders, remote_recvs, remote_sends) = new_vector(my_index, &workers[..], budget, shift, options.type_checks, failures.clone());
+    let mut remote_recv_iter = remote_recvs.into_iter();
+    let mut remote_send_iter = remote_sends.into_iter();
+
+    let mut send_guards = Vec::new();
+    let mut recv_guards = Vec::new();
+
+    for index in 0 .. rings.len() {
+
+        if let Some((rings, control)) = rings[index].take() {
+
+            let (remote_recv, send_signal) = remote_recv_iter.next().unwrap();
+            let (remote_send, recv_signal) = remote_send_iter.next().unwrap();
+
+            {
+                let rings = rings.clone();
+                let control = control.try_clone()?;
+                let (send_signal, recv_signal) = (send_signal.clone(), recv_signal.clone());
+                // Receives doorbells, and so is joined with the receive threads.
+                recv_guards.push(::std::thread::Builder::new()
+                    .name(format!("doorbell thread {}", index))
+                    .spawn(move || doorbell_loop(rings, control, send_signal, recv_signal))?);
+            }
+
+            {
+                let rings = rings.clone();
+                let control = control.try_clone()?;
+                let log_sender = log_sender.clone();
+                let failures = failures.clone();
+                send_guards.push(::std::thread::Builder::new()
+                    .name(format!("send thread {}", index))
+                    .spawn(move || {
+                        let logger = log_sender(CommunicationSetup { process: my_index, sender: true, remote: Some(index) });
+                        shared_memory::send_loop(rings, control, remote_recv, send_signal, my_index, index, failures, logger);
+                    })?);
+            }
+
+            {
+                let log_sender = log_sender.clone();
+                let failures = failures.clone();
+                recv_guards.push(::std::thread::Builder::new()
+                    .name(format!("recv thread {}", index))
+                    .spawn(move || {
+                        let logger = log_sender(CommunicationSetup { process: my_index, sender: false, remote: Some(index) });
+                        shared_memory::recv_loop(rings, control, remote_send, recv_signal, worker_offset, my_index, index, failures, shift, logger);
+                    })?);
+            }
+        }
+    }
+
+    let builders = builders.into_iter().map(SharedMemoryBuilder::new).collect();
+    Ok((builders, CommsGuard { send_guards, recv_guards, failures }))
+}
+
+/// Starts communication threads for established connections.
+///
+/// The connections are indexed by process, with `None` at `my_index`, and `workers` lists the
+/// number of worker threads in each process. The compression, queue budget, buffer size, frame
+/// validation, and message size limit of `options` apply to the communication threads.
+pub fn initialize_networking_from(
+    mut results: Vec<Option<Connection>>,
+    my_index: usize,
+    workers: Vec<usize>,
+    options: ConnectionOptions,
+    log_sender: Box<Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync>)
+-> ::std::io::Result<(Vec<TcpBuilder<Process>>, CommsGuard)>
+{
+    let log_sender = Arc::new(log_sender);
+    assert_eq!(results.len(), workers.len());
+
+    // Workers of this process have global indices starting from `worker_offset`.
+    let worker_offset: usize = workers[.. my_index].iter().sum();
 
-    let mut results = create_sockets(addresses, my_index, noisy)?;
+    let compression = options.compression;
+    let budget = options.queue_budget.unwrap_or(usize::max_value());
+    let shift = options.buffer_shift;
+    let validate = options.validate_frames;
+    let max_message = options.max_message;
 
-    let (builders, remote_recvs, remote_sends) = new_vector(my_index, threads, processes);
+    let failures = Failures::new();
+    let (builders, remote_recvs, remote_sends) = new_vector(my_index, &workers[..], budget, shift, options.type_checks, failures.clone());
     let mut remote_recv_iter = remote_recvs.into_iter();
     let mut remote_send_iter = remote_sends.into_iter();
 
@@ -63,6 +200,7 @@ pub fn initialize_networking(
 
             {
                 let log_sender = log_sender.clone();
+                let failures = failures.clone();
                 let stream = stream.try_clone()?;
                 let join_guard =
                 ::std::thread::Builder::new()
@@ -75,17 +213,18 @@ pub fn initialize_networking(
                             remote: Some(index),
                         });
 
-                        send_loop(stream, remote_recv, signal, my_index, index, logger);
+                        send_loop(stream, remote_recv, signal, my_index, index, failures, compression, validate, logger);
                     })?;
 
                 send_guards.push(join_guard);
             }
 
-            let remote_send = remote_send_iter.next().unwrap();
+            let (remote_send, _) = remote_send_iter.next().unwrap();
 
             {
                 // let remote_sends = remote_sends.clone();
                 let log_sender = log_sender.clone();
+                let failures = failures.clone();
                 let stream = stream.try_clone()?;
                 let join_guard =
                 ::std::thread::Builder::new()
@@ -96,7 +235,7 @@ pub fn initialize_networking(
                             sender: false,
                             remote: Some(index),
                         });
-                        recv_loop(stream, remote_send, threads * my_index, my_index, index, logger);
+                        recv_loop(stream, remote_send, worker_offset, my_index, index, failures, shift, validate, max_message, logger);
                     })?;
 
                 recv_guards.push(join_guard);
@@ -105,5 +244,5 @@ pub fn initialize_networking(
         }
     }
 
-    Ok((builders, CommsGuard { send_guards, recv_guards }))
+    Ok((builders, CommsGuard { send_guards, recv_guards, failures }))
 }
\ No newline at end of file
@@ -10,4 +10,9 @@ pub mod tcp;
 pub mod allocator;
 pub mod allocator_process;
 pub mod initialize;
+pub mod failure;
+pub mod shared_memory;
+pub mod compression;
+pub mod fingerprint;
+pub mod frame;
 pub mod push_pull;
\ No newline at end of file
@@ -1,7 +1,7 @@
 //! Push and Pull implementations wrapping serialized data.
 
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::VecDeque;
 
 use bytes::arc::Bytes;
@@ -93,21 +93,88 @@ impl<T:Data> Pull<Message<T>> for Puller<T> {
     }
 }
 
+/// Serialized messages received from other processes for one channel, awaiting a puller.
+///
+/// Each message is recorded with the index of the connection it arrived on. Once the channel is
+/// allocated, its bytes are counted against that connection in `queued` until it is pulled, so that
+/// the allocator can stop receiving from connections whose messages are not being consumed. The
+/// messages of channels not yet allocated are not counted, as nothing could consume them until
+/// the worker gets further, which may require receiving other messages from the same connection.
+pub struct Inbox {
+    messages: VecDeque<(usize, Bytes)>,     // messages, and the connections they arrived on.
+    queued: Rc<Vec<Cell<usize>>>,           // bytes queued in inboxes, for each connection.
+    bound: bool,                            // the channel is allocated, and its bytes counted.
+}
+
+impl Inbox {
+    /// Allocates an empty inbox for a channel not yet allocated, counting queued bytes in `queued`
+    /// once bound.
+    pub fn new(queued: Rc<Vec<Cell<usize>>>) -> Self {
+        Inbox { messages: VecDeque::new(), queued, bound: false }
+    }
+    /// Indicates that the channel is allocated, and starts counting its queued bytes.
+    pub fn bind(&mut self) {
+        if !self.bound {
+            self.bound = true;
+            for &(connection, ref bytes) in self.messages.iter() {
+                let queued = &self.queued[connection];
+                queued.set(queued.get() + bytes.len());
+            }
+        }
+    }
+    /// Indicates that the channel is allocated.
+    pub fn is_bound(&self) -> bool {
+        self.bound
+    }
+    /// Adds a message that arrived on connection `connection`.
+    pub fn push(&mut self, connection: usize, bytes: Bytes) {
+        if self.bound {
+            let queued = &self.queued[connection];
+            queued.set(queued.get() + bytes.len());
+        }
+        self.messages.push_back((connection, bytes));
+    }
+    /// Removes the oldest message.
+    pub fn pop(&mut self) -> Option<Bytes> {
+        let bound = self.bound;
+        let queued = &self.queued;
+        self.messages.pop_front().map(|(connection, bytes)| {
+            if bound {
+                let queued = &queued[connection];
+                queued.set(queued.get() - bytes.len());
+            }
+            bytes
+        })
+    }
+    /// Indicates that no messages are queued.
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+    /// Discards all messages.
+    pub fn clear(&mut self) {
+        while self.pop().is_some() { }
+    }
+}
+
+impl Drop for Inbox {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
 /// An adapter from which one can pull elements of type `T`.
 ///
-/// This type is very simple, and just consumes owned `Vec<u8>` allocations. It is
-/// not the most efficient thing possible, which would probably instead be something
-/// like the `bytes` crate (../bytes/) which provides an exclusive view of a shared
-/// allocation.
+/// Messages from process-local workers are pulled from `inner`, and serialized messages from
+/// other processes are pulled from a shared `Inbox`.
 pub struct PullerInner<T> {
     inner: Box<Pull<Message<T>>>,            // inner pullable (e.g. intra-process typed queue)
     current: Option<Message<T>>,
-    receiver: Rc<RefCell<VecDeque<Bytes>>>,    // source of serialized buffers
+    receiver: Rc<RefCell<Inbox>>,            // source of serialized buffers
 }
 
 impl<T:Data> PullerInner<T> {
-    /// Creates a new `PullerInner` instance from a shared queue.
-    pub fn new(inner: Box<Pull<Message<T>>>, receiver: Rc<RefCell<VecDeque<Bytes>>>) -> Self {
+    /// Creates a new `PullerInner` instance from a shared inbox.
+    pub fn new(inner: Box<Pull<Message<T>>>, receiver: Rc<RefCell<Inbox>>) -> Self {
         PullerInner {
             inner,
             current: None,
@@ -128,10 +195,10 @@ impl<T:Data> Pull<Message<T>> for PullerInner<T> {
             self.current =
             self.receiver
                 .borrow_mut()
-                .pop_front()
+                .pop()
                 .map(|bytes| unsafe { Message::from_bytes(bytes) });
 
             &mut self.current
         }
     }
-}
\ No newline at end of file
+}
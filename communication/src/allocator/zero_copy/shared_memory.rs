@@ -0,0 +1,645 @@
+//! Shared-memory allocator for processes on the same host.
+//!
+//! Processes first connect as in `networking`, through Unix domain sockets, and then exchange the
+//! names of memory-mapped files, each holding a single-producer single-consumer ring of bytes in
+//! one direction. Workers use the same `MergeQueue`s and `SendEndpoint`s as the `TcpAllocator`,
+//! and for each other process a send thread copies their `MessageHeader`-framed data into the
+//! ring to that process, whose receive thread copies them out into queues for its workers. No
+//! system calls are made while data flow.
+//!
+//! A thread that finds its ring empty (the receive thread) or full (the send thread) records
+//! that it is waiting in the ring, and the thread on the other side then rings a doorbell: it
+//! writes a byte to the socket connecting the processes, which a third thread reads in order to
+//! wake the waiting thread. The socket also reveals the failure of the other process, as the
+//! operating system closes it.
+
+use std::fs::{File, OpenOptions, remove_file};
+use std::io::{Read, Write, Result, Error, ErrorKind};
+use std::net::Shutdown;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use abomonation::{encode, decode};
+use memmap::MmapMut;
+
+use logging_core::Logger;
+
+use networking::{Connection, MessageHeader};
+use {Allocate, Data, Push, Pull};
+use allocator::{Message, Process};
+use ::logging::{CommunicationEvent, CommunicationSetup, MessageEvent, StateEvent, QueueEvent};
+
+use super::allocator::{TcpBuilder, TcpAllocator};
+use super::bytes_exchange::{BytesPush, BytesPull, MergeQueue, Signal};
+use super::bytes_slab::BytesSlab;
+use super::failure::{Failures, PeerFailure};
+
+/// Default capacity in bytes of each ring.
+pub const DEFAULT_RING_CAPACITY: usize = 1 << 24;
+
+// Layout of the ring header; counters are on separate cache lines.
+const HEAD: usize = 0;              // total bytes written.
+const TAIL: usize = 64;             // total bytes read.
+const WRITER_CLOSED: usize = 128;   // non-zero once the writer will write no more.
+const READER_WAITING: usize = 192;  // non-zero while the reader waits for data.
+const WRITER_WAITING: usize = 256;  // non-zero while the writer waits for space.
+const HEADER_BYTES: usize = 320;
+
+// Bytes written to the connection between processes.
+const DATA: u8 = 1;     // the ring to the recipient holds data, or is closed.
+const SPACE: u8 = 2;    // the ring from the recipient has space.
+const READY: u8 = 3;    // the recipient's ring has been mapped.
+
+/// A ring of bytes in a memory-mapped file.
+///
+/// All accesses to the counters are sequentially consistent, so that a thread recording that it
+/// waits and then checking the ring cannot miss an update made by the other thread before it
+/// checks whether anyone waits.
+struct Ring {
+    map: MmapMut,
+    capacity: usize,
+}
+
+// The ring is only accessed through atomic counters and the disjoint regions they describe, by
+// at most one reader and one writer.
+unsafe impl Sync for Ring { }
+
+impl Ring {
+    /// Creates a new file at `path` holding an empty ring of `capacity` bytes.
+    ///
+    /// The file is removed when the returned `RingFile` is dropped, including on errors.
+    fn create(path: &Path, capacity: usize) -> Result<(Ring, RingFile)> {
+        let file = OpenOptions::new().read(true).write(true).create_new(true).open(path)?;
+        let guard = RingFile { path: path.to_path_buf() };
+        file.set_len((HEADER_BYTES + capacity) as u64)?;
+        Ok((Ring::map(&file)?, guard))
+    }
+    /// Opens the ring in an existing file.
+    fn open(path: &Path) -> Result<Ring> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        Ring::map(&file)
+    }
+    fn map(file: &File) -> Result<Ring> {
+        let map = unsafe { MmapMut::map_mut(file)? };
+        if map.len() <= HEADER_BYTES {
+            return Err(Error::new(ErrorKind::InvalidData, "shared memory ring too small"));
+        }
+        let capacity = map.len() - HEADER_BYTES;
+        Ok(Ring { map, capacity })
+    }
+    fn counter(&self, offset: usize) -> &AtomicUsize {
+        unsafe { &*(self.map.as_ptr().offset(offset as isize) as *const AtomicUsize) }
+    }
+    fn data(&self) -> *mut u8 {
+        unsafe { (self.map.as_ptr() as *mut u8).offset(HEADER_BYTES as isize) }
+    }
+
+    /// The number of bytes written but not yet read.
+    fn len(&self) -> usize {
+        self.counter(HEAD).load(Ordering::SeqCst) - self.counter(TAIL).load(Ordering::SeqCst)
+    }
+    /// Copies bytes into the ring, returning the number copied (possibly zero).
+    fn write(&self, buf: &[u8]) -> usize {
+        let head = self.counter(HEAD).load(Ordering::SeqCst);
+        let tail = self.counter(TAIL).load(Ordering::SeqCst);
+        let count = ::std::cmp::min(buf.len(), self.capacity - (head - tail));
+        let offset = head % self.capacity;
+        let first = ::std::cmp::min(count, self.capacity - offset);
+        unsafe {
+            ::std::ptr::copy_nonoverlapping(buf.as_ptr(), self.data().offset(offset as isize), first);
+            ::std::ptr::copy_nonoverlapping(buf[first..].as_ptr(), self.data(), count - first);
+        }
+        self.counter(HEAD).store(head + count, Ordering::SeqCst);
+        count
+    }
+    /// Copies bytes out of the ring, returning the number copied (possibly zero).
+    fn read(&self, buf: &mut [u8]) -> usize {
+        let head = self.counter(HEAD).load(Ordering::SeqCst);
+        let tail = self.counter(TAIL).load(Ordering::SeqCst);
+        let count = ::std::cmp::min(buf.len(), head - tail);
+        let offset = tail % self.capacity;
+        let first = ::std::cmp::min(count, self.capacity - offset);
+        unsafe {
+            ::std::ptr::copy_nonoverlapping(self.data().offset(offset as isize), buf.as_mut_ptr(), first);
+            ::std::ptr::copy_nonoverlapping(self.data(), buf[first..].as_mut_ptr(), count - first);
+        }
+        self.counter(TAIL).store(tail + count, Ordering::SeqCst);
+        count
+    }
+    fn is_set(&self, flag: usize) -> bool {
+        self.counter(flag).load(Ordering::SeqCst) != 0
+    }
+    fn set(&self, flag: usize) {
+        self.counter(flag).store(1, Ordering::SeqCst);
+    }
+    /// Clears `flag`, returning whether it was set.
+    fn take(&self, flag: usize) -> bool {
+        self.counter(flag).swap(0, Ordering::SeqCst) != 0
+    }
+}
+
+/// A ring file created by this process, removed when dropped.
+///
+/// Each process removes the files it created once the other process has mapped them, or as soon
+/// as connecting fails.
+struct RingFile {
+    path: PathBuf,
+}
+
+impl Drop for RingFile {
+    fn drop(&mut self) {
+        let _ = remove_file(&self.path);
+    }
+}
+
+/// The rings between this process and one other, and the state shared by the threads using them.
+pub struct Rings {
+    incoming: Ring,
+    outgoing: Ring,
+    failed: AtomicBool,     // set once the other process is known to have failed.
+    finished: AtomicUsize,  // the number of this process's threads finished with the rings.
+}
+
+impl Rings {
+    /// An error if the other process is known to have failed.
+    fn check(&self) -> Result<()> {
+        if self.failed.load(Ordering::SeqCst) {
+            Err(Error::new(ErrorKind::ConnectionAborted, "process exited without closing shared memory"))
+        }
+        else {
+            Ok(())
+        }
+    }
+    /// Records that one of this process's threads has cleanly finished with the rings.
+    ///
+    /// Once both the send and receive threads have finished, neither will ring doorbells, and the
+    /// connection is shut down for writing, which the other process observes as a clean shutdown.
+    fn finish(&self, control: &Connection) {
+        if self.finished.fetch_add(1, Ordering::SeqCst) == 1 {
+            let _ = control.shutdown(Shutdown::Write);
+        }
+    }
+}
+
+/// Rings `bell` at the other process if `flag` was set in `ring`, clearing it.
+fn notify(ring: &Ring, flag: usize, control: &mut Connection, bell: u8) -> Result<()> {
+    if ring.take(flag) {
+        control.write_all(&[bell])?;
+    }
+    Ok(())
+}
+
+/// Establishes rings of `capacity` bytes with each connected process, through files in `directory`.
+///
+/// The connections are returned alongside the rings, and should then only be used for doorbells.
+/// All files created are removed before returning, whether or not connecting succeeds.
+pub fn connect_rings(
+    connections: Vec<Option<Connection>>,
+    directory: &Path,
+    my_index: usize,
+    capacity: usize) -> Result<Vec<Option<(Arc<Rings>, Connection)>>>
+{
+    // Create and announce the rings to each process.
+    let mut outgoing = Vec::with_capacity(connections.len());
+    let mut files = Vec::new();
+    for (index, connection) in connections.iter().enumerate() {
+        if let Some(connection) = connection.as_ref() {
+            let path = directory.join(format!("timely-{}-{}-to-{}.ring", ::std::process::id(), my_index, index));
+            let (ring, file) = Ring::create(&path, capacity)?;
+            files.push(file);
+            write_path(&mut connection.try_clone()?, &path)?;
+            outgoing.push(Some(ring));
+        }
+        else {
+            outgoing.push(None);
+        }
+    }
+
+    // Map the rings announced by each process, and confirm this to them.
+    let mut results = Vec::with_capacity(connections.len());
+    for (connection, outgoing) in connections.into_iter().zip(outgoing.into_iter()) {
+        if let (Some(mut control), Some(outgoing)) = (connection, outgoing) {
+            let incoming = Ring::open(&read_path(&mut control)?)?;
+            control.write_all(&[READY])?;
+            results.push(Some((Arc::new(Rings {
+                incoming,
+                outgoing,
+                failed: AtomicBool::new(false),
+                finished: AtomicUsize::new(0),
+            }), control)));
+        }
+        else {
+            results.push(None);
+        }
+    }
+
+    // Once each process has mapped our rings, their files are no longer needed.
+    for result in results.iter_mut() {
+        if let Some((_, ref mut control)) = *result {
+            let mut ready = [0u8; 1];
+            control.read_exact(&mut ready)?;
+            if ready[0] != READY {
+                return Err(Error::new(ErrorKind::InvalidData, "unexpected data while connecting shared memory"));
+            }
+        }
+    }
+    drop(files);
+
+    Ok(results)
+}
+
+fn write_path<W: Write>(connection: &mut W, path: &Path) -> Result<()> {
+    let name = path.to_str().ok_or_else(|| Error::new(ErrorKind::InvalidInput, "shared memory path is not valid UTF-8"))?;
+    unsafe { encode(&(name.len() as u64), connection)?; }
+    connection.write_all(name.as_bytes())
+}
+
+// Longest path accepted from the other process, as `PATH_MAX` on Linux.
+const MAX_PATH: u64 = 4096;
+
+fn read_path<R: Read>(connection: &mut R) -> Result<PathBuf> {
+    let mut buffer = [0u8; 8];
+    connection.read_exact(&mut buffer)?;
+    let length = unsafe { decode::<u64>(&mut buffer) }.map(|(length, _)| *length).unwrap_or(0);
+    // The length is checked before allocating, as a corrupt length could otherwise exhaust memory.
+    if length > MAX_PATH {
+        return Err(Error::new(ErrorKind::InvalidData, format!("shared memory path of {} bytes exceeds {} bytes", length, MAX_PATH)));
+    }
+    let mut name = vec![0u8; length as usize];
+    connection.read_exact(&mut name[..])?;
+    String::from_utf8(name)
+        .map(PathBuf::from)
+        .map_err(|_| Error::new(ErrorKind::InvalidData, "shared memory path is not valid UTF-8"))
+}
+
+/// Reads doorbells from the other process, waking the send and receive threads.
+///
+/// The loop ends when the other process shuts down the connection, which it does cleanly only
+/// once both rings are closed; otherwise the other process has failed, which is recorded in the
+/// rings before waking both threads.
+pub fn doorbell_loop(rings: Arc<Rings>, mut control: Connection, send: Signal, recv: Signal) {
+    let mut bell = [0u8; 1];
+    loop {
+        match control.read(&mut bell) {
+            Ok(1) if bell[0] == DATA => recv.ping(),
+            Ok(1) if bell[0] == SPACE => send.ping(),
+            Err(ref error) if error.kind() == ErrorKind::Interrupted => { },
+            _ => break,
+        }
+    }
+    if !rings.incoming.is_set(WRITER_CLOSED) || !rings.outgoing.is_set(WRITER_CLOSED) {
+        rings.failed.store(true, Ordering::SeqCst);
+    }
+    send.ping();
+    recv.ping();
+}
+
+/// Repeatedly copies data from workers into the ring to another process.
+///
+/// The thread waits on `signal` both for data from workers and for space in the ring. If a local
+/// worker fails, or the other process does, the connection is shut down without closing the
+/// ring, so that the other process observes the failure.
+pub fn send_loop(
+    rings: Arc<Rings>,
+    mut control: Connection,
+    mut sources: Vec<MergeQueue>,
+    signal: Signal,
+    process: usize,
+    remote: usize,
+    failures: Failures,
+    mut logger: Option<Logger<CommunicationEvent, CommunicationSetup>>)
+{
+    logger.as_mut().map(|l| l.log(StateEvent { send: true, process, remote, start: true, }));
+
+    match send_messages(&rings, &mut control, &mut sources, &signal, &mut logger) {
+        Ok(true) => rings.finish(&control),
+        Ok(false) => {
+            // A local worker has failed; it reports its own error.
+            let _ = control.shutdown(Shutdown::Both);
+        },
+        Err(error) => {
+            failures.report(PeerFailure { process, remote, sender: true, error: error.to_string() });
+            let _ = control.shutdown(Shutdown::Both);
+        },
+    }
+
+    logger.as_mut().map(|l| l.log(StateEvent { send: true, process, remote, start: false, }));
+}
+
+/// Writes data from `sources` until they are complete, and then closes the ring.
+///
+/// Returns `Ok(false)` without closing the ring if any source is poisoned.
+fn send_messages(
+    rings: &Rings,
+    control: &mut Connection,
+    sources: &mut Vec<MergeQueue>,
+    signal: &Signal,
+    logger: &mut Option<Logger<CommunicationEvent, CommunicationSetup>>) -> Result<bool>
+{
+    let mut stash = Vec::new();
+
+    while !sources.is_empty() {
+
+        // Poisoned sources indicate a local worker panicked; we must not report a clean shutdown.
+        if sources.iter().any(|source| source.is_poisoned()) {
+            return Ok(false);
+        }
+
+        logger.as_mut().map(|logger| {
+            let queued = sources.iter().map(|source| source.len()).sum();
+            if queued > 0 {
+                let full = sources.iter().any(|source| source.is_full());
+                logger.log(QueueEvent { is_send: true, queued, full });
+            }
+        });
+
+        for source in sources.iter_mut() {
+            source.drain_into(&mut stash);
+        }
+
+        if stash.is_empty() {
+            signal.wait();
+            rings.check()?;
+            // A source may complete immediately after publishing data; retire it only once drained.
+            sources.retain(|source| source.is_poisoned() || !source.is_complete() || !source.is_empty());
+        }
+        else {
+            for mut bytes in stash.drain(..) {
+                logger.as_mut().map(|logger| {
+                    let mut offset = 0;
+                    while let Some(header) = MessageHeader::try_read(&mut bytes[offset..]) {
+                        logger.log(MessageEvent { is_send: true, header, });
+                        offset += header.required_bytes();
+                    }
+                });
+                write_all(rings, control, signal, &bytes[..])?;
+            }
+        }
+    }
+
+    // The receive thread may not be waiting, but must learn that no more data will arrive.
+    rings.outgoing.set(WRITER_CLOSED);
+    rings.outgoing.take(READER_WAITING);
+    control.write_all(&[DATA])?;
+
+    Ok(true)
+}
+
+/// Copies all of `buf` into the outgoing ring, waiting on `signal` while the ring is full.
+fn write_all(rings: &Rings, control: &mut Connection, signal: &Signal, mut buf: &[u8]) -> Result<()> {
+    while !buf.is_empty() {
+        let written = rings.outgoing.write(buf);
+        if written > 0 {
+            buf = &buf[written..];
+            notify(&rings.outgoing, READER_WAITING, control, DATA)?;
+        }
+        else {
+            // Record that we wait before checking again, so that the reader cannot miss it.
+            rings.outgoing.set(WRITER_WAITING);
+            if rings.outgoing.len() == rings.outgoing.capacity {
+                signal.wait();
+                rings.check()?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Repeatedly copies data from the ring from another process into queues for workers.
+///
+/// The thread waits on `signal` both for data in the ring and for space in the queues, and so
+/// `signal` must be the signal workers ping as they drain `targets`. Data are copied into buffers
+/// of `1 << shift` bytes, which are shared with workers.
+pub fn recv_loop(
+    rings: Arc<Rings>,
+    mut control: Connection,
+    mut targets: Vec<MergeQueue>,
+    signal: Signal,
+    worker_offset: usize,
+    process: usize,
+    remote: usize,
+    failures: Failures,
+    shift: usize,
+    mut logger: Option<Logger<CommunicationEvent, CommunicationSetup>>)
+{
+    logger.as_mut().map(|l| l.log(StateEvent { send: false, process, remote, start: true }));
+
+    match recv_messages(&rings, &mut control, &mut targets, &signal, worker_offset, shift, &mut logger) {
+        Ok(()) => rings.finish(&control),
+        Err(error) => {
+            failures.report(PeerFailure { process, remote, sender: false, error: error.to_string() });
+            let _ = control.shutdown(Shutdown::Both);
+        },
+    }
+
+    logger.as_mut().map(|l| l.log(StateEvent { send: false, process, remote, start: false, }));
+}
+
+/// Reads messages into `targets` until the ring is closed, or an error.
+fn recv_messages(
+    rings: &Rings,
+    control: &mut Connection,
+    targets: &mut Vec<MergeQueue>,
+    signal: &Signal,
+    worker_offset: usize,
+    shift: usize,
+    logger: &mut Option<Logger<CommunicationEvent, CommunicationSetup>>) -> Result<()>
+{
+    let mut buffer = BytesSlab::new(shift);
+    let mut stageds: Vec<Vec<_>> = targets.iter().map(|_| Vec::new()).collect();
+
+    loop {
+
+        // The writer closes the ring only after its last write, so check for closing first.
+        let closed = rings.incoming.is_set(WRITER_CLOSED);
+        buffer.ensure_capacity(1);
+        let read = rings.incoming.read(buffer.empty());
+
+        if read == 0 {
+            if closed {
+                if !buffer.valid().is_empty() {
+                    return Err(Error::new(ErrorKind::InvalidData, "shared memory closed within a message"));
+                }
+                return Ok(());
+            }
+            // Record that we wait before checking again, so that the writer cannot miss it.
+            rings.incoming.set(READER_WAITING);
+            if rings.incoming.len() == 0 && !rings.incoming.is_set(WRITER_CLOSED) {
+                signal.wait();
+                rings.check()?;
+            }
+            continue;
+        }
+
+        notify(&rings.incoming, WRITER_WAITING, control, SPACE)?;
+        buffer.make_valid(read);
+
+        while let Some(header) = MessageHeader::try_read(buffer.valid()) {
+            let bytes = buffer.extract(header.required_bytes());
+            logger.as_mut().map(|logger| logger.log(MessageEvent { is_send: false, header, }));
+            let target = header.target.wrapping_sub(worker_offset);
+            if target >= stageds.len() {
+                return Err(Error::new(ErrorKind::InvalidData, format!("received message for worker {}, which is not in this process", header.target)));
+            }
+            stageds[target].push(bytes);
+        }
+
+        for (target, staged) in targets.iter_mut().zip(stageds.iter_mut()) {
+            target.extend(staged.drain(..));
+        }
+
+        // Stop reading while any worker is too far behind; the ring then fills, and the other
+        // process experiences backpressure.
+        let full = targets.iter().any(|target| target.is_full());
+        logger.as_mut().map(|logger| {
+            let queued = targets.iter().map(|target| target.len()).sum();
+            if queued > 0 {
+                logger.log(QueueEvent { is_send: false, queued, full });
+            }
+        });
+        if full {
+            for target in targets.iter() {
+                target.wait_for_space();
+            }
+        }
+    }
+}
+
+/// Builds an instance of a `SharedMemoryAllocator`.
+pub struct SharedMemoryBuilder {
+    inner: TcpBuilder<Process>,
+}
+
+impl SharedMemoryBuilder {
+    /// Wraps a builder whose queues are served by shared-memory communication threads.
+    pub fn new(inner: TcpBuilder<Process>) -> Self {
+        SharedMemoryBuilder { inner }
+    }
+    /// Builds a `SharedMemoryAllocator`, instantiating `Rc<RefCell<_>>` elements.
+    pub fn build(self) -> SharedMemoryAllocator {
+        SharedMemoryAllocator { inner: self.inner.build() }
+    }
+}
+
+/// An allocator for processes on one host, which exchange data through shared memory.
+///
+/// Workers exchange data with communication threads through queues exactly as they do in a
+/// `TcpAllocator`, which this allocator wraps; the communication threads differ, in that they
+/// move data through shared-memory rings rather than sockets.
+pub struct SharedMemoryAllocator {
+    inner: TcpAllocator<Process>,
+}
+
+impl Allocate for SharedMemoryAllocator {
+    fn index(&self) -> usize { self.inner.index() }
+    fn peers(&self) -> usize { self.inner.peers() }
+    fn processes(&self) -> Vec<Range<usize>> { self.inner.processes() }
+    fn allocate<T: Data>(&mut self, identifier: usize) -> (Vec<Box<Push<Message<T>>>>, Box<Pull<Message<T>>>) {
+        self.inner.allocate(identifier)
+    }
+    fn pre_work(&mut self) { self.inner.pre_work(); }
+    fn post_work(&mut self) { self.inner.post_work(); }
+    fn backpressured(&self) -> bool { self.inner.backpressured() }
+    fn release(&mut self, identifier: usize) { self.inner.release(identifier); }
+    fn drain_events(&mut self, channels: &mut Vec<usize>) { self.inner.drain_events(channels); }
+    fn await_events(&self, duration: Option<Duration>) { self.inner.await_events(duration); }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::path::PathBuf;
+    use std::sync::atomic::Ordering;
+
+    use std::io::{Cursor, ErrorKind};
+
+    use super::{Ring, RingFile, HEAD, TAIL};
+
+    fn ring(name: &str, capacity: usize) -> (Ring, RingFile) {
+        let path = ::std::env::temp_dir().join(format!("timely-test-{}-{}.ring", name, ::std::process::id()));
+        let _ = ::std::fs::remove_file(&path);
+        Ring::create(&path, capacity).unwrap()
+    }
+
+    #[test]
+    fn wraparound() {
+        let (ring, _file) = ring("wraparound", 16);
+        let mut buffer = [0u8; 16];
+        // Writes of 5 bytes cross the end of the ring at varying offsets.
+        for round in 0 .. 100u8 {
+            let data = [round, round + 1, round + 2, round + 3, round + 4];
+            assert_eq!(ring.write(&data), 5);
+            assert_eq!(ring.len(), 5);
+            assert_eq!(ring.read(&mut buffer[..3]), 3);
+            assert_eq!(ring.read(&mut buffer[3..]), 2);
+            assert_eq!(&buffer[..5], &data);
+            assert_eq!(ring.len(), 0);
+        }
+        assert_eq!(ring.counter(HEAD).load(Ordering::SeqCst), 500);
+        assert_eq!(ring.counter(TAIL).load(Ordering::SeqCst), 500);
+    }
+
+    #[test]
+    fn full_and_empty() {
+        let (ring, _file) = ring("full", 16);
+        let data = (0 .. 20).collect::<Vec<u8>>();
+        let mut buffer = [0u8; 20];
+        assert_eq!(ring.read(&mut buffer), 0);
+        // Offset the ring, so that filling it wraps around.
+        assert_eq!(ring.write(&data[.. 10]), 10);
+        assert_eq!(ring.read(&mut buffer[.. 10]), 10);
+        assert_eq!(ring.write(&data), 16);
+        assert_eq!(ring.write(&data), 0);
+        assert_eq!(ring.len(), 16);
+        assert_eq!(ring.read(&mut buffer[.. 4]), 4);
+        assert_eq!(ring.write(&data[16 ..]), 4);
+        assert_eq!(ring.read(&mut buffer[4 ..]), 16);
+        assert_eq!(&buffer[..], &data[..]);
+        assert_eq!(ring.read(&mut buffer), 0);
+    }
+
+    #[test]
+    fn shared_between_maps() {
+        let (writer, file) = ring("shared", 8);
+        let reader = Ring::open(&file.path).unwrap();
+        let data = (0 .. 100).collect::<Vec<u8>>();
+        let mut received = Vec::new();
+        let mut written = 0;
+        while received.len() < data.len() {
+            written += writer.write(&data[written ..]);
+            let mut buffer = [0u8; 3];
+            let read = reader.read(&mut buffer);
+            received.extend_from_slice(&buffer[.. read]);
+        }
+        assert_eq!(received, data);
+    }
+
+    #[test]
+    fn file_removed() {
+        let path: PathBuf = {
+            let (_ring, file) = ring("removed", 8);
+            assert!(file.path.exists());
+            file.path.clone()
+        };
+        assert!(!path.exists());
+        // Failing to create a ring removes its file.
+        assert!(Ring::create(&path, 0).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn path_length_bounded() {
+        let mut bytes = Vec::new();
+        super::write_path(&mut bytes, &PathBuf::from("/tmp/ring")).unwrap();
+        assert_eq!(super::read_path(&mut Cursor::new(bytes)).unwrap(), PathBuf::from("/tmp/ring"));
+
+        // A corrupt length is reported rather than allocated.
+        let mut bytes = Vec::new();
+        unsafe { ::abomonation::encode(&u64::max_value(), &mut bytes).unwrap(); }
+        let error = super::read_path(&mut Cursor::new(bytes)).err().expect("oversize path accepted");
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -1,35 +1,71 @@
 //!
 
-use std::io::{Read, Write};
-use std::net::TcpStream;
+use std::io::{Read, Write, Result, Error, ErrorKind, BufWriter};
+use std::net::Shutdown;
 
-use networking::MessageHeader;
+use abomonation::decode;
+
+use networking::{MessageHeader, Connection};
 
 use super::bytes_slab::BytesSlab;
 use super::bytes_exchange::{MergeQueue, Signal};
+use super::failure::{Failures, PeerFailure};
+use super::compression::{Compression, COMPRESSED, decompress};
+use super::frame::{Frame, check_length};
 
 use logging_core::Logger;
 
-use ::logging::{CommunicationEvent, CommunicationSetup, MessageEvent, StateEvent};
+use ::logging::{CommunicationEvent, CommunicationSetup, MessageEvent, StateEvent, CompressionEvent, QueueEvent};
 
-/// Repeatedly reads from a TcpStream and carves out messages.
+/// Repeatedly reads from a connection and carves out messages.
 ///
 /// The intended communication pattern is a sequence of (header, message)^* for valid
 /// messages, followed by a header for a zero length message indicating the end of stream.
-/// If the stream ends without being shut down, the receive thread panics in an attempt to
-/// take down the computation and cause the failures to cascade.
+/// If the stream ends without being shut down, or cannot be read, the failure is recorded
+/// in `failures` and the thread exits; workers observe the failure and stop waiting on
+/// data that will not arrive, causing the failure to cascade without a panic.
+///
+/// Data are read into buffers of `1 << shift` bytes, which are shared with workers. If
+/// `validate` is set, each message is expected to be preceded by a `Frame`, and invalid frames
+/// are reported as failures, as are messages of more than `max_message` bytes, either as sent or
+/// once decompressed. Lengths are checked as soon as a header arrives, before buffering the rest
+/// of the message.
 pub fn recv_loop(
-    mut reader: TcpStream,
+    mut reader: Connection,
     mut targets: Vec<MergeQueue>,
     worker_offset: usize,
     process: usize,
     remote: usize,
+    failures: Failures,
+    shift: usize,
+    validate: bool,
+    max_message: usize,
     mut logger: Option<Logger<CommunicationEvent, CommunicationSetup>>)
 {
     // Log the receive thread's start.
     logger.as_mut().map(|l| l.log(StateEvent { send: false, process, remote, start: true }));
 
-    let mut buffer = BytesSlab::new(20);
+    if let Err(error) = recv_messages(&mut reader, &mut targets, worker_offset, shift, validate, max_message, &mut logger) {
+        failures.report(PeerFailure { process, remote, sender: false, error: error.to_string() });
+        // Stop the remote process from writing to a connection no one is reading.
+        let _ = reader.shutdown(Shutdown::Both);
+    }
+
+    // Log the receive thread's stop.
+    logger.as_mut().map(|l| l.log(StateEvent { send: false, process, remote, start: false, }));
+}
+
+/// Reads messages into `targets` until a clean shutdown, or an error.
+fn recv_messages(
+    reader: &mut Connection,
+    targets: &mut Vec<MergeQueue>,
+    worker_offset: usize,
+    shift: usize,
+    validate: bool,
+    max_message: usize,
+    logger: &mut Option<Logger<CommunicationEvent, CommunicationSetup>>) -> Result<()>
+{
+    let mut buffer = BytesSlab::new(shift);
 
     // Where we stash Bytes before handing them off.
     let mut stageds = Vec::with_capacity(targets.len());
@@ -53,80 +89,176 @@ pub fn recv_loop(
 
         // Attempt to read some more bytes into self.buffer.
         let read = match reader.read(&mut buffer.empty()) {
+            Ok(0) => return Err(Error::new(ErrorKind::UnexpectedEof, "connection closed without clean shutdown")),
             Ok(n) => n,
-            Err(x) => {
-                // We don't expect this, as socket closure results in Ok(0) reads.
-                println!("Error: {:?}", x);
-                0
-            },
+            Err(ref error) if error.kind() == ErrorKind::Interrupted => 0,
+            Err(error) => return Err(error),
         };
 
-        assert!(read > 0);
         buffer.make_valid(read);
 
         // Consume complete messages from the front of self.buffer.
-        while let Some(header) = MessageHeader::try_read(buffer.valid()) {
+        loop {
+
+            let header = if validate {
+                match Frame::try_read(buffer.valid(), max_message)? {
+                    Some(header) => {
+                        // Discard the frame, which workers do not expect.
+                        let _ = buffer.extract(::std::mem::size_of::<Frame>());
+                        header
+                    },
+                    None => break,
+                }
+            }
+            else {
+                // Check the length as soon as the header arrives, rather than once the payload has.
+                match unsafe { decode::<MessageHeader>(buffer.valid()) } {
+                    Some((header, remaining)) => {
+                        check_length(header, max_message)?;
+                        if remaining.len() < header.length { break; }
+                        *header
+                    },
+                    None => break,
+                }
+            };
 
             // TODO: Consolidate message sequences sent to the same worker?
             let peeled_bytes = header.required_bytes();
-            let bytes = buffer.extract(peeled_bytes);
+            let mut bytes = buffer.extract(peeled_bytes);
 
             // Record message receipt.
             logger.as_mut().map(|logger| {
                 logger.log(MessageEvent { is_send: false, header, });
             });
 
+            // Workers only ever see uncompressed messages.
+            if header.flags & COMPRESSED != 0 {
+                bytes = decompress(&header, &bytes[..], max_message)?;
+                logger.as_mut().map(|logger| {
+                    let uncompressed = MessageHeader::try_read(&mut bytes[..]).expect("decompressed header missing");
+                    logger.log(CompressionEvent { is_send: false, header: uncompressed, compressed: header.length });
+                });
+            }
+
             if header.length > 0 {
-                stageds[header.target - worker_offset].push(bytes);
+                let target = header.target.wrapping_sub(worker_offset);
+                if target >= stageds.len() {
+                    return Err(Error::new(ErrorKind::InvalidData, format!("received message for worker {}, which is not in this process", header.target)));
+                }
+                stageds[target].push(bytes);
             }
             else {
                 // Shutting down; confirm absence of subsequent data.
                 active = false;
                 if !buffer.valid().is_empty() {
-                    panic!("Clean shutdown followed by data.");
+                    return Err(Error::new(ErrorKind::InvalidData, "clean shutdown followed by data"));
                 }
                 buffer.ensure_capacity(1);
-                if reader.read(&mut buffer.empty()).expect("read failure") > 0 {
-                    panic!("Clean shutdown followed by data.");
+                if reader.read(&mut buffer.empty())? > 0 {
+                    return Err(Error::new(ErrorKind::InvalidData, "clean shutdown followed by data"));
                 }
             }
         }
 
         // Pass bytes along to targets.
         for (index, staged) in stageds.iter_mut().enumerate() {
-
-
             use allocator::zero_copy::bytes_exchange::BytesPush;
             targets[index].extend(staged.drain(..));
         }
+
+        // Record queue depths, and stop reading while any worker is too far behind; the remote
+        // process then experiences backpressure through the connection.
+        let full = targets.iter().any(|target| target.is_full());
+        logger.as_mut().map(|logger| {
+            let queued = targets.iter().map(|target| target.len()).sum();
+            if queued > 0 {
+                logger.log(QueueEvent { is_send: false, queued, full });
+            }
+        });
+        if full {
+            for target in targets.iter() {
+                target.wait_for_space();
+            }
+        }
     }
 
-    // Log the receive thread's start.
-    logger.as_mut().map(|l| l.log(StateEvent { send: false, process, remote, start: false, }));
+    Ok(())
 }
 
-/// Repeatedly sends messages into a TcpStream.
+/// Repeatedly sends messages into a connection.
 ///
 /// The intended communication pattern is a sequence of (header, message)^* for valid
 /// messages, followed by a header for a zero length message indicating the end of stream.
+/// If the stream cannot be written, the failure is recorded in `failures` and the thread
+/// exits. If a local worker fails, the stream is shut down without the final header, so
+/// that the remote process observes the failure.
+///
+/// If `compression` is set, message payloads are compressed where this reduces their size. If
+/// `validate` is set, each message is preceded by a `Frame`.
 pub fn send_loop(
     // TODO: Maybe we don't need BufWriter with consolidation in writes.
-    writer: TcpStream,
+    writer: Connection,
     mut sources: Vec<MergeQueue>,
     signal: Signal,
     process: usize,
     remote: usize,
+    failures: Failures,
+    compression: Option<Compression>,
+    validate: bool,
     mut logger: Option<Logger<CommunicationEvent, CommunicationSetup>>)
 {
 
-    // Log the receive thread's start.
+    // Log the send thread's start.
     logger.as_mut().map(|l| l.log(StateEvent { send: true, process, remote, start: true, }));
 
-    let mut writer = ::std::io::BufWriter::with_capacity(1 << 16, writer);
+    let mut writer = BufWriter::with_capacity(1 << 16, writer);
+
+    match send_messages(&mut writer, &mut sources, &signal, compression, validate, &mut logger) {
+        Ok(true) => { },
+        Ok(false) => {
+            // A local worker has failed; it reports its own error.
+            let _ = writer.get_mut().shutdown(Shutdown::Both);
+        },
+        Err(error) => {
+            failures.report(PeerFailure { process, remote, sender: true, error: error.to_string() });
+            let _ = writer.get_mut().shutdown(Shutdown::Both);
+        },
+    }
+
+    // Log the send thread's stop.
+    logger.as_mut().map(|l| l.log(StateEvent { send: true, process, remote, start: false, }));
+}
+
+/// Writes messages from `sources` until they are complete, and then the final header.
+///
+/// Returns `Ok(false)` without writing the final header if any source is poisoned.
+fn send_messages(
+    writer: &mut BufWriter<Connection>,
+    sources: &mut Vec<MergeQueue>,
+    signal: &Signal,
+    compression: Option<Compression>,
+    validate: bool,
+    logger: &mut Option<Logger<CommunicationEvent, CommunicationSetup>>) -> Result<bool>
+{
     let mut stash = Vec::new();
+    let mut compressed = Vec::new();
 
     while !sources.is_empty() {
 
+        // Poisoned sources indicate a local worker panicked; we must not report a clean shutdown.
+        if sources.iter().any(|source| source.is_poisoned()) {
+            return Ok(false);
+        }
+
+        // Record queue depths before draining them.
+        logger.as_mut().map(|logger| {
+            let queued = sources.iter().map(|source| source.len()).sum();
+            if queued > 0 {
+                let full = sources.iter().any(|source| source.is_full());
+                logger.log(QueueEvent { is_send: true, queued, full });
+            }
+        });
+
         // TODO: Round-robin better, to release resources fairly when overloaded.
         for source in sources.iter_mut() {
             use allocator::zero_copy::bytes_exchange::BytesPull;
@@ -137,24 +269,55 @@ pub fn send_loop(
             // No evidence of records to read, but sources not yet empty (at start of loop).
             // We are going to flush our writer (to move buffered data) and wait on a signal.
             // We could get awoken by more data, a channel closing, or spuriously perhaps.
-            writer.flush().expect("Failed to flush writer.");
-            signal.wait();
-            sources.retain(|source| !source.is_complete());
+            // A source may complete immediately after publishing data; retire it only once drained,
+            // and before waiting, as its completion may already have been signalled.
+            sources.retain(|source| source.is_poisoned() || !source.is_complete() || !source.is_empty());
+            if !sources.is_empty() {
+                writer.flush()?;
+                signal.wait();
+            }
         }
         else {
             // TODO: Could do scatter/gather write here.
             for mut bytes in stash.drain(..) {
 
-                // Record message sends.
-                logger.as_mut().map(|logger| {
+                if compression.is_some() || validate {
+                    // Compress and frame messages individually, as each may have a different target.
                     let mut offset = 0;
                     while let Some(header) = MessageHeader::try_read(&mut bytes[offset..]) {
-                        logger.log(MessageEvent { is_send: true, header, });
+                        let message = &bytes[offset .. offset + header.required_bytes()];
+                        let payload = &message[::std::mem::size_of::<MessageHeader>() ..];
+                        let compressed_header = compression.as_ref().and_then(|compression| compression.compress(&header, payload, &mut compressed));
+                        let (sent, sent_payload) = match compressed_header {
+                            Some(sent) => {
+                                logger.as_mut().map(|logger| logger.log(CompressionEvent { is_send: true, header, compressed: sent.length }));
+                                (sent, &compressed[..])
+                            },
+                            None => (header, payload),
+                        };
+                        if validate {
+                            Frame::write_to(writer, &sent, sent_payload)?;
+                        }
+                        else {
+                            sent.write_to(writer)?;
+                            writer.write_all(sent_payload)?;
+                        }
+                        logger.as_mut().map(|logger| logger.log(MessageEvent { is_send: true, header: sent, }));
                         offset += header.required_bytes();
                     }
-                });
+                }
+                else {
+                    // Record message sends.
+                    logger.as_mut().map(|logger| {
+                        let mut offset = 0;
+                        while let Some(header) = MessageHeader::try_read(&mut bytes[offset..]) {
+                            logger.log(MessageEvent { is_send: true, header, });
+                            offset += header.required_bytes();
+                        }
+                    });
 
-                writer.write_all(&bytes[..]).expect("Write failure in send_loop.");
+                    writer.write_all(&bytes[..])?;
+                }
             }
         }
     }
@@ -168,12 +331,102 @@ pub fn send_loop(
         target:     0,
         length:     0,
         seqno:      0,
+        flags:      0,
     };
-    header.write_to(&mut writer).expect("Failed to write header!");
-    writer.flush().expect("Failed to flush writer.");
-    writer.get_mut().shutdown(::std::net::Shutdown::Write).expect("Write shutdown failed");
+    if validate {
+        Frame::write_to(writer, &header, &[])?;
+    }
+    else {
+        header.write_to(writer)?;
+    }
+    writer.flush()?;
+    writer.get_mut().shutdown(Shutdown::Write)?;
     logger.as_mut().map(|logger| logger.log(MessageEvent { is_send: true, header }));
 
-    // Log the receive thread's start.
-    logger.as_mut().map(|l| l.log(StateEvent { send: true, process, remote, start: false, }));
-}
\ No newline at end of file
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::{Write, ErrorKind, Result};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    use networking::{MessageHeader, Connection};
+    use allocator::zero_copy::bytes_exchange::{MergeQueue, Signal};
+    use allocator::zero_copy::frame::Frame;
+    use super::recv_messages;
+
+    // Receives `bytes` into a single worker with index `1`, as a receive thread would.
+    fn receive(bytes: Vec<u8>, validate: bool, max_message: usize) -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let sender = thread::spawn(move || {
+            let mut stream = TcpStream::connect(address).unwrap();
+            // The receiver may stop reading early, and close the connection.
+            let _ = stream.write_all(&bytes[..]);
+        });
+        let mut reader = Connection::Tcp(listener.accept().unwrap().0);
+        let mut targets = vec![MergeQueue::new(Signal::new())];
+        let result = recv_messages(&mut reader, &mut targets, 1, 12, validate, max_message, &mut None);
+        sender.join().unwrap();
+        result
+    }
+
+    // A message for `target` with a payload of `length` bytes, optionally framed.
+    fn message(target: usize, length: usize, validate: bool) -> Vec<u8> {
+        let header = MessageHeader { channel: 0, source: 0, target, length, seqno: 0, flags: 0 };
+        let payload = vec![7u8; length];
+        let mut bytes = Vec::new();
+        if validate {
+            Frame::write_to(&mut bytes, &header, &payload[..]).unwrap();
+        }
+        else {
+            header.write_to(&mut bytes).unwrap();
+            bytes.extend_from_slice(&payload[..]);
+        }
+        bytes
+    }
+
+    fn assert_invalid(result: Result<()>, message: &str) {
+        match result {
+            Err(error) => {
+                assert_eq!(error.kind(), ErrorKind::InvalidData, "{}", error);
+                assert!(error.to_string().contains(message), "{}", error);
+            },
+            Ok(()) => panic!("received without error, expecting: {}", message),
+        }
+    }
+
+    #[test]
+    fn clean_shutdown() {
+        for &validate in &[false, true] {
+            let mut bytes = message(1, 16, validate);
+            bytes.extend(message(1, 0, validate));
+            receive(bytes, validate, 1 << 20).unwrap();
+        }
+    }
+
+    #[test]
+    fn target_out_of_range() {
+        for &validate in &[false, true] {
+            assert_invalid(receive(message(2, 16, validate), validate, 1 << 20), "received message for worker 2, which is not in this process");
+            assert_invalid(receive(message(0, 16, validate), validate, 1 << 20), "received message for worker 0, which is not in this process");
+        }
+    }
+
+    #[test]
+    fn oversize_length() {
+        for &validate in &[false, true] {
+            // Only the header arrives, and the length is rejected without awaiting the payload.
+            let header = MessageHeader { channel: 0, source: 0, target: 1, length: usize::max_value(), seqno: 0, flags: 0 };
+            let mut bytes = Vec::new();
+            if validate { Frame::write_to(&mut bytes, &header, &[]).unwrap(); }
+            else { header.write_to(&mut bytes).unwrap(); }
+            assert_invalid(receive(bytes, validate, 1 << 20), "more than the maximum of 1048576");
+
+            assert_invalid(receive(message(1, 2000, validate), validate, 1000), "received message of 2000 bytes");
+        }
+    }
+}
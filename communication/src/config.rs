@@ -0,0 +1,578 @@
+//! Structured configuration of the communication infrastructure.
+//!
+//! A `Config` describes the workers of this process, how to reach other processes, and how to
+//! move data between them. It can be assembled with builder methods, or loaded from text in
+//! three places, each of which uses the same keys:
+//!
+//! * command line arguments, as `--key value` (see `Config::from_args`),
+//! * environment variables, as `TIMELY_KEY=value` with dashes replaced by underscores,
+//! * configuration files, as lines `key = value`, with `#` starting a comment.
+//!
+//! The recognized keys are:
+//!
+//! * `threads`: number of worker threads in this process.
+//! * `process`: index of this process.
+//! * `processes`: number of processes.
+//! * `hostfile`: file whose lines are the addresses of processes (`host:port`, or `unix:path`),
+//!   each optionally followed by the number of worker threads in that process.
+//! * `addresses`: comma-separated addresses of processes, instead of `hostfile`.
+//! * `worker-counts`: comma-separated numbers of worker threads in each process.
+//! * `shared-memory`: directory through which processes on one host share memory.
+//! * `ring-capacity`: bytes in each shared-memory ring.
+//! * `buffer-shift`: log2 of the size in bytes of serialization buffers.
+//! * `connect-timeout`: seconds after which connecting to other processes is abandoned.
+//! * `compression`: compression level (0-9) of data sent to other processes.
+//! * `compression-threshold`: bytes below which messages are not compressed.
+//! * `queue-budget`: bytes queued for each remote process before applying backpressure.
+//! * `type-checks`: `true` to check that workers allocate channels with the same types.
+//! * `validate-frames`: `true` to check messages between processes with magic bytes and checksums.
+//! * `max-message`: bytes in the largest message accepted from other processes.
+//! * `report`: `true` to report connection progress.
+
+use std::any::Any;
+use std::io::{BufRead, BufReader};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[cfg(feature = "arg_parse")]
+use getopts;
+
+use allocator::{Thread, Process, GenericBuilder};
+use allocator::zero_copy::initialize::{initialize_networking, initialize_shared_memory};
+use allocator::zero_copy::shared_memory::DEFAULT_RING_CAPACITY;
+use allocator::zero_copy::compression::Compression;
+use networking::ConnectionOptions;
+use initialize::Configuration;
+
+use ::logging::{CommunicationSetup, CommunicationEvent};
+use logging_core::Logger;
+
+/// Keys understood by `Config::set`, with descriptions and value hints for command line help.
+const KEYS: &'static [(&'static str, &'static str, &'static str)] = &[
+    ("threads", "number of per-process worker threads", "NUM"),
+    ("process", "identity of this process", "IDX"),
+    ("processes", "number of processes", "NUM"),
+    ("hostfile", "text file whose lines are process addresses (host:port, or unix:path), optionally followed by worker counts", "FILE"),
+    ("addresses", "comma-separated process addresses, instead of a hostfile", "ADDRS"),
+    ("worker-counts", "comma-separated numbers of worker threads in each process", "NUMS"),
+    ("shared-memory", "directory through which processes on one host share memory, instead of a hostfile", "DIR"),
+    ("ring-capacity", "bytes in each shared memory ring", "BYTES"),
+    ("buffer-shift", "log2 of the size of serialization buffers", "NUM"),
+    ("connect-timeout", "seconds to wait for connections to other processes", "SECS"),
+    ("compression", "compression level (0-9) of data sent to other processes", "LEVEL"),
+    ("compression-threshold", "bytes below which messages are not compressed", "BYTES"),
+    ("queue-budget", "bytes queued for each remote process before applying backpressure", "BYTES"),
+    ("type-checks", "checks that workers allocate channels with the same types (true or false)", "BOOL"),
+    ("validate-frames", "checks messages between processes with magic bytes and checksums (true or false)", "BOOL"),
+    ("max-message", "bytes in the largest message accepted from other processes", "BYTES"),
+];
+
+/// A builder for the communication infrastructure.
+///
+/// # Examples
+/// ```
+/// use timely_communication::Config;
+///
+/// // configure for two threads, just one process.
+/// let config = Config::new().threads(2);
+///
+/// // initializes communication, spawns workers
+/// let guards = timely_communication::initialize(config, |allocator| {
+///     println!("worker {} started", allocator.index());
+///     allocator.index()
+/// });
+/// # assert!(guards.is_ok());
+/// ```
+pub struct Config {
+    threads: usize,
+    process: usize,
+    processes: usize,
+    hostfile: Option<PathBuf>,
+    addresses: Option<Vec<String>>,
+    worker_counts: Option<Vec<usize>>,
+    shared_memory: Option<PathBuf>,
+    ring_capacity: usize,
+    options: ConnectionOptions,
+    report: bool,
+    log_fn: Box<Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync>,
+    allocator: Option<Allocator>,
+}
+
+/// The allocators `Config::try_build` may assemble.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Allocator {
+    Thread,
+    Process,
+    Networking,
+    SharedMemory,
+}
+
+impl Config {
+
+    /// A configuration for one worker thread in one process.
+    pub fn new() -> Self {
+        Config {
+            threads: 1,
+            process: 0,
+            processes: 1,
+            hostfile: None,
+            addresses: None,
+            worker_counts: None,
+            shared_memory: None,
+            ring_capacity: DEFAULT_RING_CAPACITY,
+            options: ConnectionOptions::default(),
+            report: false,
+            log_fn: Box::new(|_| None),
+            allocator: None,
+        }
+    }
+
+    /// Sets the number of worker threads in this process.
+    pub fn threads(mut self, threads: usize) -> Self { self.threads = threads; self }
+    /// Sets the index of this process.
+    pub fn process(mut self, process: usize) -> Self { self.process = process; self }
+    /// Sets the number of processes.
+    ///
+    /// Unless addresses are otherwise provided, processes listen on `localhost` at ports
+    /// increasing from 2101.
+    pub fn processes(mut self, processes: usize) -> Self { self.processes = processes; self }
+    /// Sets a file whose first lines are the addresses of the processes.
+    ///
+    /// Each address may be followed by whitespace and the number of worker threads in that
+    /// process, which otherwise defaults to the number set with `threads`.
+    pub fn hostfile<P: Into<PathBuf>>(mut self, path: P) -> Self { self.hostfile = Some(path.into()); self }
+    /// Sets the addresses of the processes, and the number of processes to match.
+    pub fn addresses(mut self, addresses: Vec<String>) -> Self {
+        self.processes = addresses.len();
+        self.addresses = Some(addresses);
+        self
+    }
+    /// Sets the number of worker threads in each process.
+    ///
+    /// Processes may have different numbers of workers; global worker indices are assigned to
+    /// processes in order.
+    pub fn worker_counts(mut self, counts: Vec<usize>) -> Self { self.worker_counts = Some(counts); self }
+    /// Communicates with other processes on this host through shared memory in `directory`.
+    ///
+    /// Compression and frame validation apply only to network connections, and are not used.
+    pub fn shared_memory<P: Into<PathBuf>>(mut self, directory: P) -> Self { self.shared_memory = Some(directory.into()); self }
+    /// Sets the capacity in bytes of each shared-memory ring.
+    pub fn ring_capacity(mut self, capacity: usize) -> Self { self.ring_capacity = capacity; self }
+    /// Sets log2 of the size in bytes of the buffers into which data are serialized and received.
+    pub fn buffer_shift(mut self, shift: usize) -> Self { self.options.buffer_shift = shift; self }
+    /// Sets the time after which connecting to other processes is abandoned.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self { self.options.deadline = Some(timeout); self }
+    /// Sets the initial and largest delays between attempts to connect to another process.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.options.initial_backoff = initial;
+        self.options.max_backoff = max;
+        self
+    }
+    /// Sets the time to wait for the handshake of a connection from another process.
+    pub fn handshake_timeout(mut self, timeout: Duration) -> Self { self.options.handshake_timeout = timeout; self }
+    /// Compresses data sent to other processes.
+    pub fn compression(mut self, compression: Compression) -> Self { self.options.compression = Some(compression); self }
+    /// Sets the bytes queued between each worker and each remote process before applying backpressure.
+    pub fn queue_budget(mut self, budget: usize) -> Self { self.options.queue_budget = Some(budget); self }
+    /// Checks that workers in different processes allocate each channel with the same type.
+    ///
+    /// Checks are enabled by default in debug builds.
+    pub fn type_checks(mut self, checks: bool) -> Self { self.options.type_checks = checks; self }
+    /// Validates messages between processes with magic bytes and checksums.
+    ///
+    /// A corrupted stream, or a connection from something other than a timely process, is then
+    /// reported as a failure of the connection. All processes must agree on this option.
+    pub fn validate_frames(mut self, validate: bool) -> Self { self.options.validate_frames = validate; self }
+    /// Sets the largest message, in bytes, accepted from other processes.
+    ///
+    /// Larger messages are reported as a failure of the connection, rather than allocated.
+    pub fn max_message(mut self, bytes: usize) -> Self { self.options.max_message = bytes; self }
+    /// Replaces all options for connections between processes.
+    pub fn connection_options(mut self, options: ConnectionOptions) -> Self { self.options = options; self }
+    /// Reports connection progress.
+    pub fn report(mut self, report: bool) -> Self { self.report = report; self }
+    /// Sets the function that provides loggers for communication threads.
+    pub fn logging<F>(mut self, log_fn: F) -> Self
+    where
+        F: Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync+'static
+    {
+        self.log_fn = Box::new(log_fn);
+        self
+    }
+
+    /// Sets the option named by `key` from the text `value`.
+    ///
+    /// The keys are those listed in the module documentation.
+    pub fn set(self, key: &str, value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        Ok(match key {
+            "threads" => self.threads(parse(key, value)?),
+            "process" => self.process(parse(key, value)?),
+            "processes" => self.processes(parse(key, value)?),
+            "hostfile" => self.hostfile(value),
+            "addresses" => self.addresses(value.split(',').map(|x| x.trim().to_owned()).collect()),
+            "worker-counts" => {
+                let mut counts = Vec::new();
+                for count in value.split(',') { counts.push(parse(key, count.trim())?); }
+                self.worker_counts(counts)
+            },
+            "shared-memory" => self.shared_memory(value),
+            "ring-capacity" => self.ring_capacity(parse(key, value)?),
+            "buffer-shift" => self.buffer_shift(parse(key, value)?),
+            "connect-timeout" => {
+                let seconds: f64 = parse(key, value)?;
+                self.connect_timeout(Duration::from_millis((seconds * 1000.0) as u64))
+            },
+            "compression" => {
+                let mut compression = self.options.compression.unwrap_or(Compression::default());
+                compression.level = parse(key, value)?;
+                self.compression(compression)
+            },
+            "compression-threshold" => {
+                let mut compression = self.options.compression.unwrap_or(Compression::default());
+                compression.threshold = parse(key, value)?;
+                self.compression(compression)
+            },
+            "queue-budget" => self.queue_budget(parse(key, value)?),
+            "type-checks" => self.type_checks(parse(key, value)?),
+            "validate-frames" => self.validate_frames(parse(key, value)?),
+            "max-message" => self.max_message(parse(key, value)?),
+            "report" => self.report(parse(key, value)?),
+            _ => return Err(format!("unrecognized configuration key: {}", key)),
+        })
+    }
+
+    /// Sets options from environment variables `TIMELY_KEY`, for each recognized key.
+    ///
+    /// Keys are upper-cased with dashes replaced by underscores, as in `TIMELY_WORKER_COUNTS`.
+    pub fn apply_env(mut self) -> Result<Self, String> {
+        for key in KEYS.iter().map(|&(key, _, _)| key).chain(Some("report")) {
+            let name = format!("TIMELY_{}", key.to_uppercase().replace('-', "_"));
+            if let Ok(value) = ::std::env::var(&name) {
+                self = self.set(key, &value).map_err(|error| format!("{}: {}", name, error))?;
+            }
+        }
+        Ok(self)
+    }
+
+    /// Sets options from a file whose lines have the form `key = value`.
+    ///
+    /// Blank lines, and text following a `#`, are ignored.
+    pub fn apply_file<P: AsRef<Path>>(mut self, path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|error| format!("could not open {}: {}", path.display(), error))?;
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|error| format!("could not read {}: {}", path.display(), error))?;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() { continue; }
+            let mut parts = line.splitn(2, '=');
+            let key = parts.next().unwrap_or("").trim();
+            let value = parts.next().ok_or_else(|| format!("{}:{}: expected `key = value`", path.display(), number + 1))?;
+            self = self.set(key, value).map_err(|error| format!("{}:{}: {}", path.display(), number + 1, error))?;
+        }
+        Ok(self)
+    }
+
+    /// Constructs a configuration from environment variables.
+    pub fn from_env() -> Result<Self, String> {
+        Config::new().apply_env()
+    }
+
+    /// Constructs a configuration from a file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        Config::new().apply_file(path)
+    }
+
+    /// Constructs a configuration from environment variables, a configuration file, and
+    /// supplied text arguments, in increasing order of precedence.
+    ///
+    /// Each key is accepted as a long argument `--key value`, in addition to the short arguments
+    /// `-w` (threads), `-p` (process), `-n` (processes), `-h` (hostfile), and `-r` (report).
+    /// A configuration file is named by `--config FILE`. Environment variables are read as by
+    /// `apply_env`; to ignore them, use `Config::new().apply_args(args)` instead.
+    ///
+    /// Most commonly, this uses `std::env::Args()` as the supplied iterator.
+    #[cfg(feature = "arg_parse")]
+    pub fn from_args<I: Iterator<Item=String>>(args: I) -> Result<Self, String> {
+        Config::from_env()?.apply_args(args)
+    }
+
+    /// Sets options from a configuration file and supplied text arguments, in increasing order
+    /// of precedence, as described for `from_args`.
+    #[cfg(feature = "arg_parse")]
+    pub fn apply_args<I: Iterator<Item=String>>(mut self, args: I) -> Result<Self, String> {
+
+        let mut opts = getopts::Options::new();
+        for &(key, description, hint) in KEYS.iter() {
+            let short = match key {
+                "threads" => "w",
+                "process" => "p",
+                "processes" => "n",
+                "hostfile" => "h",
+                _ => "",
+            };
+            opts.optopt(short, key, description, hint);
+        }
+        opts.optflag("r", "report", "reports connection progress");
+        opts.optopt("", "config", "text file whose lines are `key = value` options", "FILE");
+
+        let matches = opts.parse(args).map_err(|e| format!("{:?}", e))?;
+
+        if let Some(path) = matches.opt_str("config") {
+            self = self.apply_file(path)?;
+        }
+        for &(key, _, _) in KEYS.iter() {
+            if let Some(value) = matches.opt_str(key) {
+                self = self.set(key, &value)?;
+            }
+        }
+        if matches.opt_present("report") {
+            self = self.report(true);
+        }
+
+        Ok(self)
+    }
+
+    /// Attempts to assemble the described communication infrastructure.
+    ///
+    /// An error is returned if the configuration is inconsistent, if connections cannot be
+    /// established before the connection timeout, or if processes disagree about the
+    /// configuration (for example, the number of processes, or two processes claiming the
+    /// same index).
+    pub fn try_build(self) -> Result<(Vec<GenericBuilder>, Box<Any>), String> {
+
+        if self.process >= self.processes {
+            return Err(format!("process index {} out of range for {} processes", self.process, self.processes));
+        }
+
+        // Addresses, and the worker counts a hostfile may declare alongside them.
+        let (addresses, declared) = match (self.addresses, self.hostfile) {
+            (Some(addresses), _) => (addresses, None),
+            (None, Some(hostfile)) if self.processes > 1 && self.shared_memory.is_none() => {
+                read_hostfile(&hostfile, self.processes, self.threads)?
+            },
+            _ => ((0 .. self.processes).map(|index| format!("localhost:{}", 2101 + index)).collect(), None),
+        };
+
+        let workers = match (self.worker_counts, declared) {
+            (Some(counts), Some(declared)) => {
+                if counts != declared {
+                    return Err(format!("worker counts {:?} differ from those in the hostfile, {:?}", counts, declared));
+                }
+                counts
+            },
+            (Some(counts), None) => counts,
+            (None, Some(declared)) => declared,
+            (None, None) => vec![self.threads; self.processes],
+        };
+        if workers.len() != self.processes {
+            return Err(format!("{} worker counts provided for {} processes", workers.len(), self.processes));
+        }
+        if let Some(process) = workers.iter().position(|&count| count == 0) {
+            return Err(format!("process {} has no workers", process));
+        }
+        let threads = workers[self.process];
+
+        // The allocator a `Configuration` named, or otherwise the simplest that serves the workers.
+        let allocator = match self.allocator {
+            Some(allocator) => allocator,
+            None if self.processes > 1 && self.shared_memory.is_some() => Allocator::SharedMemory,
+            None if self.processes > 1 => Allocator::Networking,
+            None if threads > 1 => Allocator::Process,
+            None => Allocator::Thread,
+        };
+
+        match allocator {
+            Allocator::SharedMemory => {
+                let directory = self.shared_memory.ok_or_else(|| "no shared memory directory provided".to_owned())?;
+                match initialize_shared_memory(directory, self.process, workers, self.report, self.options, self.ring_capacity, self.log_fn) {
+                    Ok((stuff, guard)) => {
+                        Ok((stuff.into_iter().map(|x| GenericBuilder::SharedMemory(x)).collect(), Box::new(guard)))
+                    },
+                    Err(error) => Err(format!("failed to initialize shared memory: {}", error)),
+                }
+            },
+            Allocator::Networking => {
+                if addresses.len() != self.processes {
+                    return Err(format!("{} addresses provided for {} processes", addresses.len(), self.processes));
+                }
+                match initialize_networking(addresses, self.process, workers, self.report, self.options, self.log_fn) {
+                    Ok((stuff, guard)) => {
+                        Ok((stuff.into_iter().map(|x| GenericBuilder::ZeroCopy(x)).collect(), Box::new(guard)))
+                    },
+                    Err(error) => Err(format!("failed to initialize networking: {}", error)),
+                }
+            },
+            Allocator::Process => {
+                Ok((Process::new_vector(threads).into_iter().map(|x| GenericBuilder::Process(x)).collect(), Box::new(())))
+            },
+            Allocator::Thread => {
+                Ok((vec![GenericBuilder::Thread(Thread)], Box::new(())))
+            },
+        }
+    }
+}
+
+/// Describes the same infrastructure as the `Configuration`, assembled with the allocator it
+/// names even where `try_build` would otherwise choose a simpler one (for example, `Process(1)`
+/// still uses the `Process` allocator, and a `Cluster` of one process still uses networking).
+impl From<Configuration> for Config {
+    fn from(configuration: Configuration) -> Config {
+        let (mut config, allocator) = match configuration {
+            Configuration::Thread => (Config::new(), Allocator::Thread),
+            Configuration::Process(threads) => (Config::new().threads(threads), Allocator::Process),
+            Configuration::Cluster(threads, process, addresses, report, log_fn) => {
+                let mut config = Config::new().threads(threads).process(process).addresses(addresses).report(report);
+                config.log_fn = log_fn;
+                (config, Allocator::Networking)
+            },
+            Configuration::SharedMemory(threads, process, processes, directory, report, log_fn) => {
+                let mut config = Config::new().threads(threads).process(process).processes(processes).shared_memory(directory).report(report);
+                config.log_fn = log_fn;
+                (config, Allocator::SharedMemory)
+            },
+        };
+        config.allocator = Some(allocator);
+        config
+    }
+}
+
+/// Parses `value` for the option `key`.
+fn parse<T: ::std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid value for {}: {:?}", key, value))
+}
+
+/// Reads the addresses of `processes` processes from the first lines of a file.
+///
+/// Each line is an address, optionally followed by whitespace and the number of worker threads
+/// in that process. If any line declares a number of workers, the numbers are also returned,
+/// with `threads` for lines that do not declare one.
+pub(crate) fn read_hostfile(path: &Path, processes: usize, threads: usize) -> Result<(Vec<String>, Option<Vec<usize>>), String> {
+    let file = File::open(path).map_err(|error| format!("could not open {}: {}", path.display(), error))?;
+    let mut addresses = Vec::new();
+    let mut workers = Vec::new();
+    let mut declared = false;
+    for line in BufReader::new(file).lines().take(processes) {
+        let line = line.map_err(|error| format!("could not read {}: {}", path.display(), error))?;
+        let mut fields = line.split_whitespace();
+        addresses.push(fields.next().unwrap_or("").to_owned());
+        match fields.next() {
+            Some(count) => {
+                workers.push(parse("worker count", count).map_err(|error| format!("{}: {}", path.display(), error))?);
+                declared = true;
+            },
+            None => workers.push(threads),
+        }
+    }
+    if addresses.len() < processes {
+        return Err(format!("could only read {} addresses from {}, but -n: {}", addresses.len(), path.display(), processes));
+    }
+    Ok((addresses, if declared { Some(workers) } else { None }))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    use super::Config;
+
+    // Writes `contents` to a temporary file named for `name`.
+    fn write_file(name: &str, contents: &str) -> PathBuf {
+        let path = ::std::env::temp_dir().join(format!("timely-config-{}-{}", name, ::std::process::id()));
+        File::create(&path).unwrap().write_all(contents.as_bytes()).unwrap();
+        path
+    }
+
+    #[cfg(feature = "arg_parse")]
+    fn args(args: &[&str]) -> ::std::vec::IntoIter<String> {
+        Some("program").into_iter().chain(args.iter().cloned()).map(|x| x.to_owned()).collect::<Vec<_>>().into_iter()
+    }
+
+    #[test]
+    #[cfg(feature = "arg_parse")]
+    fn parse_args() {
+        let config = Config::new().apply_args(args(&["-w", "3", "--process", "1", "--addresses", "a:1, b:2", "--worker-counts", "2,3", "--compression", "6", "--validate-frames", "true", "-r"])).unwrap();
+        assert_eq!(config.threads, 3);
+        assert_eq!(config.process, 1);
+        assert_eq!(config.processes, 2);
+        assert_eq!(config.addresses, Some(vec!["a:1".to_owned(), "b:2".to_owned()]));
+        assert_eq!(config.worker_counts, Some(vec![2, 3]));
+        assert_eq!(config.options.compression.map(|c| c.level), Some(6));
+        assert!(config.options.validate_frames);
+        assert!(config.report);
+
+        assert!(Config::new().apply_args(args(&["--threads", "many"])).is_err());
+        assert!(Config::new().apply_args(args(&["--unknown", "1"])).is_err());
+    }
+
+    #[test]
+    fn parse_file() {
+        let parse = write_file("parse", "# a comment\n\nthreads = 4\nqueue-budget = 1024  # trailing comment\nshared-memory = /tmp/timely\n");
+        let config = Config::from_file(&parse).unwrap();
+        assert_eq!(config.threads, 4);
+        assert_eq!(config.options.queue_budget, Some(1024));
+        assert_eq!(config.shared_memory, Some(PathBuf::from("/tmp/timely")));
+
+        let malformed = write_file("malformed", "threads = 4\nprocesses\n");
+        let error = Config::from_file(&malformed).err().expect("malformed file accepted");
+        assert!(error.contains(":2:"), "{}", error);
+
+        let unknown = write_file("unknown", "color = blue\n");
+        let error = Config::from_file(&unknown).err().expect("unknown key accepted");
+        assert!(error.contains("unrecognized configuration key: color"), "{}", error);
+
+        for path in &[parse, malformed, unknown] {
+            let _ = ::std::fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn configuration_keeps_allocator() {
+        use allocator::GenericBuilder;
+        use initialize::Configuration;
+
+        let (builders, _guard) = Config::from(Configuration::Process(1)).try_build().unwrap();
+        assert_eq!(builders.len(), 1);
+        match builders[0] { GenericBuilder::Process(_) => { }, _ => panic!("Process(1) built another allocator") }
+
+        let (builders, _guard) = Config::new().try_build().unwrap();
+        match builders[0] { GenericBuilder::Thread(_) => { }, _ => panic!("one worker built another allocator") }
+    }
+
+    // The only test to set environment variables, which other tests would otherwise observe.
+    #[test]
+    #[cfg(feature = "arg_parse")]
+    fn env_and_precedence() {
+        ::std::env::set_var("TIMELY_RING_CAPACITY", "4096");
+        ::std::env::set_var("TIMELY_MAX_MESSAGE", "100");
+        ::std::env::set_var("TIMELY_BUFFER_SHIFT", "10");
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.ring_capacity, 4096);
+        assert_eq!(config.options.max_message, 100);
+        assert_eq!(config.options.buffer_shift, 10);
+
+        // Files take precedence over the environment, and arguments over both.
+        let path = write_file("precedence", "max-message = 200\nbuffer-shift = 12\n");
+        let config = Config::from_args(args(&["--config", path.to_str().unwrap(), "--buffer-shift", "14"])).unwrap();
+        assert_eq!(config.ring_capacity, 4096);
+        assert_eq!(config.options.max_message, 200);
+        assert_eq!(config.options.buffer_shift, 14);
+
+        // Without the environment.
+        let config = Config::new().apply_args(args(&["--config", path.to_str().unwrap()])).unwrap();
+        assert_eq!(config.ring_capacity, super::DEFAULT_RING_CAPACITY);
+        let _ = ::std::fs::remove_file(&path);
+
+        ::std::env::set_var("TIMELY_BUFFER_SHIFT", "large");
+        let error = Config::from_env().err().expect("invalid variable accepted");
+        assert!(error.contains("TIMELY_BUFFER_SHIFT"), "{}", error);
+
+        ::std::env::remove_var("TIMELY_RING_CAPACITY");
+        ::std::env::remove_var("TIMELY_MAX_MESSAGE");
+        ::std::env::remove_var("TIMELY_BUFFER_SHIFT");
+    }
+}
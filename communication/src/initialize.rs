@@ -2,44 +2,56 @@
 
 use std::thread;
 #[cfg(feature = "arg_parse")]
-use std::io::BufRead;
-#[cfg(feature = "arg_parse")]
 use getopts;
 use std::sync::Arc;
+#[cfg(feature = "arg_parse")]
+use std::path::Path;
+use std::path::PathBuf;
 
 use std::any::Any;
+use std::fmt::{Display, Formatter, Error};
 
-use allocator::{AllocateBuilder, Thread, Process, Generic, GenericBuilder};
-use allocator::zero_copy::initialize::initialize_networking;
+use allocator::{AllocateBuilder, Generic, GenericBuilder};
+use allocator::zero_copy::failure::PeerFailure;
+use networking::ConnectionOptions;
+use config::Config;
+#[cfg(feature = "arg_parse")]
+use config::read_hostfile;
 
 use ::logging::{CommunicationSetup, CommunicationEvent};
 use logging_core::Logger;
 
 
 /// Possible configurations for the communication infrastructure.
+///
+/// The `Config` builder describes the same configurations, and more options besides.
 pub enum Configuration {
     /// Use one thread.
     Thread,
     /// Use one process with an indicated number of threads.
     Process(usize),
     /// Expect multiple processes indicated by `(threads, process, host_list, report)`.
-    Cluster(usize, usize, Vec<String>, bool, Box<Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync>)
+    Cluster(usize, usize, Vec<String>, bool, Box<Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync>),
+    /// Expect multiple processes on one host, communicating through shared memory in a common
+    /// directory, indicated by `(threads, process, processes, directory, report)`.
+    SharedMemory(usize, usize, usize, PathBuf, bool, Box<Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync>),
 }
 
-#[cfg(feature = "arg_parse")]
 impl Configuration {
 
     /// Constructs a new configuration by parsing supplied text arguments.
     ///
     /// Most commonly, this uses `std::env::Args()` as the supplied iterator.
+    #[cfg(feature = "arg_parse")]
     pub fn from_args<I: Iterator<Item=String>>(args: I) -> Result<Configuration,String> {
 
         let mut opts = getopts::Options::new();
         opts.optopt("w", "threads", "number of per-process worker threads", "NUM");
         opts.optopt("p", "process", "identity of this process", "IDX");
         opts.optopt("n", "processes", "number of processes", "NUM");
-        opts.optopt("h", "hostfile", "text file whose lines are process addresses", "FILE");
+        opts.optopt("h", "hostfile", "text file whose lines are process addresses (host:port, or unix:path)", "FILE");
         opts.optflag("r", "report", "reports connection progress");
+        opts.optopt("", "shared-memory", "directory through which processes on one host share memory, instead of a hostfile", "DIR");
 
         opts.parse(args)
             .map_err(|e| format!("{:?}", e))
@@ -53,16 +65,18 @@ impl Configuration {
 
             assert!(process < processes);
 
-            if processes > 1 {
+            if processes > 1 && matches.opt_present("shared-memory") {
+                let directory = matches.opt_str("shared-memory").unwrap();
+                Configuration::SharedMemory(threads, process, processes, PathBuf::from(directory), report, Box::new(|_| None))
+            }
+            else if processes > 1 {
                 let mut addresses = Vec::new();
                 if let Some(hosts) = matches.opt_str("h") {
-                    let reader = ::std::io::BufReader::new(::std::fs::File::open(hosts.clone()).unwrap());
-                    for x in reader.lines().take(processes) {
-                        addresses.push(x.unwrap());
-                    }
-                    if addresses.len() < processes {
-                        panic!("could only read {} addresses from {}, but -n: {}", addresses.len(), hosts, processes);
+                    let (read, workers) = read_hostfile(Path::new(&hosts), processes, threads).unwrap_or_else(|error| panic!("{}", error));
+                    if workers.map(|workers| workers.iter().any(|&count| count != threads)).unwrap_or(false) {
+                        panic!("{} declares worker counts other than -w: {}; use Config to vary them", hosts, threads);
                     }
+                    addresses = read;
                 }
                 else {
                     for index in 0..processes {
@@ -80,29 +94,25 @@ impl Configuration {
 
     /// Attempts to assemble the described communication infrastructure.
     pub fn try_build(self) -> Result<(Vec<GenericBuilder>, Box<Any>), String> {
-        match self {
-            Configuration::Thread => {
-                Ok((vec![GenericBuilder::Thread(Thread)], Box::new(())))
-            },
-            Configuration::Process(threads) => {
-                Ok((Process::new_vector(threads).into_iter().map(|x| GenericBuilder::Process(x)).collect(), Box::new(())))
-            },
-            Configuration::Cluster(threads, process, addresses, report, log_fn) => {
-                if let Ok((stuff, guard)) = initialize_networking(addresses, process, threads, report, log_fn) {
-                    Ok((stuff.into_iter().map(|x| GenericBuilder::ZeroCopy(x)).collect(), Box::new(guard)))
-                }
-                else {
-                    Err("failed to initialize networking".to_owned())
-                }
-            },
-        }
+        self.try_build_with(ConnectionOptions::default())
+    }
+
+    /// Attempts to assemble the described communication infrastructure, connecting processes
+    /// as directed by `options`.
+    ///
+    /// An error is returned if connections cannot be established before the deadline in
+    /// `options`, or if processes disagree about the configuration (for example, the number
+    /// of processes, or two processes claiming the same index).
+    pub fn try_build_with(self, options: ConnectionOptions) -> Result<(Vec<GenericBuilder>, Box<Any>), String> {
+        Config::from(self).connection_options(options).try_build()
     }
 }
 
 /// Initializes communication and executes a distributed computation.
 ///
 /// This method allocates an `allocator::Generic` for each thread, spawns local worker threads,
-/// and invokes the supplied function with the allocator.
+/// and invokes the supplied function with the allocator. The configuration may be either a
+/// `Configuration` or a `Config`.
 /// The method returns a `WorkerGuards<T>` which can be `join`ed to retrieve the return values
 /// (or errors) of the workers.
 ///
@@ -164,11 +174,11 @@ impl Configuration {
 /// result: Ok(0)
 /// result: Ok(1)
 /// ```
-pub fn initialize<T:Send+'static, F: Fn(Generic)->T+Send+Sync+'static>(
-    config: Configuration,
+pub fn initialize<C: Into<Config>, T:Send+'static, F: Fn(Generic)->T+Send+Sync+'static>(
+    config: C,
     func: F,
 ) -> Result<WorkerGuards<T>,String> {
-    let (allocators, others) = try!(config.try_build());
+    let (allocators, others) = try!(config.into().try_build());
     initialize_from(allocators, others, func)
 }
 
@@ -257,17 +267,68 @@ pub struct WorkerGuards<T:Send+'static> {
 
 impl<T:Send+'static> WorkerGuards<T> {
     /// Waits on the worker threads and returns the results they produce.
-    pub fn join(mut self) -> Vec<Result<T, String>> {
+    ///
+    /// Workers that did not complete report a `WorkerError`, which distinguishes the loss of a
+    /// connection to a remote process from other panics. Communication threads that stop
+    /// because a worker panicked are then joined without panicking again, as the workers'
+    /// errors already report the panic.
+    pub fn join(mut self) -> Vec<Result<T, WorkerError>> {
+        let results: Vec<_> =
         self.guards.drain(..)
-                   .map(|guard| guard.join().map_err(|e| format!("{:?}", e)))
-                   .collect()
+                   .map(|guard| guard.join().map_err(WorkerError::from_panic))
+                   .collect();
+        if results.iter().any(|result| result.is_err()) {
+            let others = ::std::mem::replace(&mut self._others, Box::new(()));
+            let _ = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(move || drop(others)));
+        }
+        results
+    }
+}
+
+/// The reason a worker failed to produce a result.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WorkerError {
+    /// The connection to a remote process failed.
+    PeerFailure(PeerFailure),
+    /// The worker panicked, with the panic message if it could be recovered.
+    Panic(String),
+}
+
+impl WorkerError {
+    /// Interprets the payload of a panicked worker thread.
+    fn from_panic(payload: Box<Any+Send>) -> Self {
+        match payload.downcast::<PeerFailure>() {
+            Ok(failure) => WorkerError::PeerFailure(*failure),
+            Err(payload) => {
+                if let Some(message) = payload.downcast_ref::<&'static str>() {
+                    WorkerError::Panic(message.to_string())
+                }
+                else if let Some(message) = payload.downcast_ref::<String>() {
+                    WorkerError::Panic(message.clone())
+                }
+                else {
+                    WorkerError::Panic("unknown panic payload".to_owned())
+                }
+            }
+        }
+    }
+}
+
+impl Display for WorkerError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match *self {
+            WorkerError::PeerFailure(ref failure) => write!(f, "{}", failure),
+            WorkerError::Panic(ref message) => write!(f, "worker panicked: {}", message),
+        }
     }
 }
 
 impl<T:Send+'static> Drop for WorkerGuards<T> {
     fn drop(&mut self) {
         for guard in self.guards.drain(..) {
-            guard.join().expect("Worker panic");
+            if let Err(payload) = guard.join() {
+                panic!("Worker panic: {}", WorkerError::from_panic(payload));
+            }
         }
         // println!("WORKER THREADS JOINED");
     }
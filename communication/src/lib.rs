@@ -79,6 +79,8 @@
 extern crate getopts;
 extern crate abomonation;
 #[macro_use] extern crate abomonation_derive;
+extern crate memmap;
+extern crate flate2;
 
 extern crate timely_bytes as bytes;
 extern crate timely_logging as logging_core;
@@ -86,6 +88,7 @@ extern crate timely_logging as logging_core;
 pub mod allocator;
 pub mod networking;
 pub mod initialize;
+pub mod config;
 pub mod logging;
 pub mod message;
 
@@ -95,7 +98,8 @@ use abomonation::Abomonation;
 
 pub use allocator::Generic as Allocator;
 pub use allocator::Allocate;
-pub use initialize::{initialize, initialize_from, Configuration, WorkerGuards};
+pub use initialize::{initialize, initialize_from, Configuration, WorkerGuards, WorkerError};
+pub use config::Config;
 pub use message::Message;
 
 /// A composite trait for types that may be used with channels.
@@ -18,6 +18,10 @@ pub enum CommunicationEvent {
     Message(MessageEvent),
     /// A state transition.
     State(StateEvent),
+    /// A compressed message.
+    Compression(CompressionEvent),
+    /// The depth of queues between workers and a communication thread.
+    Queue(QueueEvent),
 }
 
 /// An observed message.
@@ -29,6 +33,28 @@ pub struct MessageEvent {
     pub header: ::networking::MessageHeader,
 }
 
+/// A message compressed for sending, or decompressed on receipt.
+#[derive(Abomonation, Debug, Clone)]
+pub struct CompressionEvent {
+    /// true for send event, false for receive event
+    pub is_send: bool,
+    /// header of the uncompressed message.
+    pub header: ::networking::MessageHeader,
+    /// length in bytes of the compressed payload.
+    pub compressed: usize,
+}
+
+/// Bytes queued between workers and a communication thread.
+#[derive(Abomonation, Debug, Clone)]
+pub struct QueueEvent {
+    /// true for queues to a send thread, false for queues from a receive thread.
+    pub is_send: bool,
+    /// total bytes in the queues.
+    pub queued: usize,
+    /// true if some queue holds at least its budget of bytes.
+    pub full: bool,
+}
+
 /// Starting or stopping communication threads.
 #[derive(Abomonation, Debug, Clone)]
 pub struct StateEvent {
@@ -48,3 +74,9 @@ impl From<MessageEvent> for CommunicationEvent {
 impl From<StateEvent> for CommunicationEvent {
     fn from(v: StateEvent) -> CommunicationEvent { CommunicationEvent::State(v) }
 }
+impl From<CompressionEvent> for CommunicationEvent {
+    fn from(v: CompressionEvent) -> CommunicationEvent { CommunicationEvent::Compression(v) }
+}
+impl From<QueueEvent> for CommunicationEvent {
+    fn from(v: QueueEvent) -> CommunicationEvent { CommunicationEvent::Queue(v) }
+}
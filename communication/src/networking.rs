@@ -1,14 +1,22 @@
 //! Networking code for sending and receiving fixed size `Vec<u8>` between machines.
 
-use std::io::{Read, Result};
-use std::net::{TcpListener, TcpStream};
+use std::io::{Read, Write, Result, Error, ErrorKind};
+use std::net::{TcpListener, TcpStream, Shutdown};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::channel;
 use std::thread;
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use abomonation::{encode, decode};
 
+use allocator::zero_copy::compression::Compression;
+
 /// Framing data for each `Vec<u8>` transmission, indicating a typed channel, the source and
 /// destination workers, and the length in bytes.
 #[derive(Copy, Clone, Debug, Abomonation)]
@@ -23,6 +31,8 @@ pub struct MessageHeader {
     pub length:     usize,
     /// sequence number.
     pub seqno:      usize,
+    /// properties of the payload, such as compression.
+    pub flags:      usize,
 }
 
 impl MessageHeader {
@@ -47,49 +57,464 @@ impl MessageHeader {
     }
 
     /// The number of bytes required for the header and data.
+    ///
+    /// Saturates rather than overflows, for lengths no buffer could hold.
     #[inline(always)]
     pub fn required_bytes(&self) -> usize {
-        ::std::mem::size_of::<MessageHeader>() + self.length
+        self.length.saturating_add(::std::mem::size_of::<MessageHeader>())
+    }
+}
+
+/// Version of the protocol spoken between processes, checked when connecting.
+///
+/// This should be incremented whenever the framing of data between processes changes.
+pub const PROTOCOL_VERSION: u64 = 5;
+
+/// Options controlling connections between processes.
+#[derive(Clone, Debug)]
+pub struct ConnectionOptions {
+    /// Time after which connecting to other processes is abandoned, or `None` to wait forever.
+    pub deadline: Option<Duration>,
+    /// Delay before the first retry of a failed connection attempt.
+    pub initial_backoff: Duration,
+    /// Largest delay between connection attempts; delays double up to this bound.
+    pub max_backoff: Duration,
+    /// Time to wait for the handshake of an accepted connection, which is dropped if none arrives.
+    ///
+    /// Bounds the time a client that connects but never completes a handshake can stall startup.
+    /// Must be non-zero.
+    pub handshake_timeout: Duration,
+    /// Compression of data sent to other processes, if any.
+    pub compression: Option<Compression>,
+    /// Bytes queued between each worker and each remote process before applying backpressure,
+    /// or `None` to queue without limit.
+    ///
+    /// Workers stop taking data from a remote process while as many bytes they took from it for
+    /// channels they have allocated are not yet consumed, and receive threads stop reading from the
+    /// network while a local worker's queue is full. Workers report themselves as backpressured
+    /// while their queue to a remote process is full, unless they have received data to consume.
+    pub queue_budget: Option<usize>,
+    /// Log2 of the size in bytes of the buffers into which data are serialized and received.
+    pub buffer_shift: usize,
+    /// Announces the type of each allocated channel to remote workers, which report an error
+    /// if they allocate the channel with a different type.
+    ///
+    /// Enabled by default in debug builds.
+    pub type_checks: bool,
+    /// Precedes each message between processes with magic bytes and checksums, which are
+    /// validated on receipt, so that corrupted streams are reported rather than trusted.
+    ///
+    /// All processes must agree on this option.
+    pub validate_frames: bool,
+    /// Largest message, in bytes, accepted from other processes.
+    ///
+    /// A message declaring a greater length is reported as a failure of the connection rather
+    /// than allocated.
+    pub max_message: usize,
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        ConnectionOptions {
+            deadline: None,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(1),
+            handshake_timeout: Duration::from_secs(10),
+            compression: None,
+            queue_budget: None,
+            buffer_shift: 20,
+            type_checks: cfg!(debug_assertions),
+            validate_frames: false,
+            max_message: 1 << 30,
+        }
     }
 }
 
+/// Leading bytes of each `Handshake`, distinguishing it from data sent by other programs.
+pub const HANDSHAKE_MAGIC: u64 = 0x54494d454c594853;
+
+/// Description of a process, exchanged when connecting to detect misconfigurations.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Abomonation)]
+pub struct Handshake {
+    /// Always `HANDSHAKE_MAGIC`.
+    pub magic:      u64,
+    /// Protocol version spoken by the process.
+    pub protocol:   u64,
+    /// Index of the process.
+    pub process:    u64,
+    /// Number of processes the process expects.
+    pub processes:  u64,
+    /// Number of worker threads in the process.
+    pub threads:    u64,
+    /// Digest of the number of worker threads the process expects in each process.
+    pub workers:    u64,
+    /// Non-zero if the process validates the frames of messages.
+    pub validated:  u64,
+}
+
+impl Handshake {
+    /// Describes the local process, where `workers` lists the number of worker threads in each
+    /// process, and `validated` indicates whether messages are framed for validation.
+    pub fn new(process: usize, workers: &[usize], validated: bool) -> Self {
+        // FNV-1a, over the counts in order.
+        let mut digest = 0xcbf29ce484222325u64;
+        for &count in workers.iter() {
+            digest ^= count as u64;
+            digest = digest.wrapping_mul(0x100000001b3);
+        }
+        Handshake {
+            magic: HANDSHAKE_MAGIC,
+            protocol: PROTOCOL_VERSION,
+            process: process as u64,
+            processes: workers.len() as u64,
+            threads: workers[process] as u64,
+            workers: digest,
+            validated: if validated { 1 } else { 0 },
+        }
+    }
+
+    /// Writes the handshake as binary data.
+    pub fn write_to<W: ::std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        unsafe { encode(self, writer) }
+    }
+
+    /// Reads a handshake from binary data.
+    ///
+    /// Data not starting with `HANDSHAKE_MAGIC` are reported as invalid.
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Handshake> {
+        let mut buffer = vec![0u8; ::std::mem::size_of::<Handshake>()];
+        reader.read_exact(&mut buffer[..])?;
+        let handshake = unsafe { decode::<Handshake>(&mut buffer) }
+            .map(|(handshake, _)| handshake.clone())
+            .ok_or_else(|| invalid_data("failed to decode handshake".to_owned()))?;
+        if handshake.magic != HANDSHAKE_MAGIC {
+            return Err(invalid_data(format!("expected a handshake, but received {:#x}", handshake.magic)));
+        }
+        Ok(handshake)
+    }
+
+    /// Checks that `remote`, received from process `index`, is consistent with `self`.
+    ///
+    /// If `index` is `None` the remote process is not yet known, and any index other than
+    /// our own is accepted.
+    pub fn validate(&self, remote: &Handshake, index: Option<usize>) -> Result<()> {
+        if remote.protocol != self.protocol {
+            return Err(invalid_data(format!("process {} speaks protocol version {}, but process {} speaks version {}", remote.process, remote.protocol, self.process, self.protocol)));
+        }
+        if remote.processes != self.processes {
+            return Err(invalid_data(format!("process {} expects {} processes, but process {} expects {}", remote.process, remote.processes, self.process, self.processes)));
+        }
+        if remote.workers != self.workers {
+            return Err(invalid_data(format!("process {} (with {} threads) expects different numbers of threads per process than process {} (with {} threads)", remote.process, remote.threads, self.process, self.threads)));
+        }
+        if remote.validated != self.validated {
+            let (validated, unvalidated) = if self.validated != 0 { (self.process, remote.process) } else { (remote.process, self.process) };
+            return Err(invalid_data(format!("process {} validates message frames, but process {} does not", validated, unvalidated)));
+        }
+        if remote.process >= remote.processes {
+            return Err(invalid_data(format!("process {} is out of range for {} processes", remote.process, remote.processes)));
+        }
+        if remote.process == self.process {
+            return Err(invalid_data(format!("two processes claim index {}", remote.process)));
+        }
+        if let Some(index) = index {
+            if remote.process != index as u64 {
+                return Err(invalid_data(format!("expected process {} at its address, but found process {}", index, remote.process)));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+/// Prefix of addresses naming Unix domain sockets, rather than TCP addresses.
+pub const UNIX_PREFIX: &'static str = "unix:";
+
+/// A connection to another process, over TCP or a Unix domain socket.
+pub enum Connection {
+    /// A TCP connection.
+    Tcp(TcpStream),
+    /// A Unix domain socket connection, for processes on the same host.
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Connection {
+    /// Connects to `address`, either a TCP address or `unix:` followed by a socket path.
+    pub fn connect(address: &str) -> Result<Connection> {
+        if address.starts_with(UNIX_PREFIX) {
+            connect_unix(&address[UNIX_PREFIX.len()..])
+        }
+        else {
+            let stream = TcpStream::connect(address)?;
+            stream.set_nodelay(true)?;
+            Ok(Connection::Tcp(stream))
+        }
+    }
+    /// Creates a new handle to the same underlying connection.
+    pub fn try_clone(&self) -> Result<Connection> {
+        match *self {
+            Connection::Tcp(ref stream) => stream.try_clone().map(Connection::Tcp),
+            #[cfg(unix)]
+            Connection::Unix(ref stream) => stream.try_clone().map(Connection::Unix),
+        }
+    }
+    /// Shuts down the read, write, or both halves of the connection.
+    pub fn shutdown(&self, how: Shutdown) -> Result<()> {
+        match *self {
+            Connection::Tcp(ref stream) => stream.shutdown(how),
+            #[cfg(unix)]
+            Connection::Unix(ref stream) => stream.shutdown(how),
+        }
+    }
+    /// Sets the read timeout of the connection; `None` blocks indefinitely.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        match *self {
+            Connection::Tcp(ref stream) => stream.set_read_timeout(timeout),
+            #[cfg(unix)]
+            Connection::Unix(ref stream) => stream.set_read_timeout(timeout),
+        }
+    }
+    /// Moves the connection into or out of nonblocking mode.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<()> {
+        match *self {
+            Connection::Tcp(ref stream) => stream.set_nonblocking(nonblocking),
+            #[cfg(unix)]
+            Connection::Unix(ref stream) => stream.set_nonblocking(nonblocking),
+        }
+    }
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        match *self {
+            Connection::Tcp(ref mut stream) => stream.read(buf),
+            #[cfg(unix)]
+            Connection::Unix(ref mut stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        match *self {
+            Connection::Tcp(ref mut stream) => stream.write(buf),
+            #[cfg(unix)]
+            Connection::Unix(ref mut stream) => stream.write(buf),
+        }
+    }
+    fn flush(&mut self) -> Result<()> {
+        match *self {
+            Connection::Tcp(ref mut stream) => stream.flush(),
+            #[cfg(unix)]
+            Connection::Unix(ref mut stream) => stream.flush(),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn connect_unix(path: &str) -> Result<Connection> {
+    UnixStream::connect(path).map(Connection::Unix)
+}
+
+#[cfg(not(unix))]
+fn connect_unix(path: &str) -> Result<Connection> {
+    Err(Error::new(ErrorKind::Other, format!("cannot connect to {}: Unix domain sockets are not supported on this platform", path)))
+}
+
+/// Listens for connections at an address, either over TCP or a Unix domain socket.
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf),
+}
+
+impl Listener {
+    fn bind(address: &str) -> Result<Listener> {
+        if address.starts_with(UNIX_PREFIX) {
+            bind_unix(&address[UNIX_PREFIX.len()..])
+        }
+        else {
+            TcpListener::bind(address).map(Listener::Tcp)
+        }
+    }
+    fn set_nonblocking(&self, nonblocking: bool) -> Result<()> {
+        match *self {
+            Listener::Tcp(ref listener) => listener.set_nonblocking(nonblocking),
+            #[cfg(unix)]
+            Listener::Unix(ref listener, _) => listener.set_nonblocking(nonblocking),
+        }
+    }
+    fn accept(&self) -> Result<Connection> {
+        match *self {
+            Listener::Tcp(ref listener) => {
+                let stream = listener.accept()?.0;
+                stream.set_nonblocking(false)?;
+                stream.set_nodelay(true)?;
+                Ok(Connection::Tcp(stream))
+            },
+            #[cfg(unix)]
+            Listener::Unix(ref listener, _) => {
+                let stream = listener.accept()?.0;
+                stream.set_nonblocking(false)?;
+                Ok(Connection::Unix(stream))
+            },
+        }
+    }
+}
+
+// Remove the socket file once connections are established, so that it does not linger.
+impl Drop for Listener {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        {
+            if let Listener::Unix(_, ref path) = *self {
+                let _ = ::std::fs::remove_file(path);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn bind_unix(path: &str) -> Result<Listener> {
+    // An existing socket may belong to a live process, which would take any connection made to
+    // check (even one closed at once) for a peer, and so we neither probe nor replace it. Sockets
+    // are removed once connections are established, and only remain if a process stopped early.
+    if ::std::path::Path::new(path).exists() {
+        return Err(Error::new(ErrorKind::AddrInUse, format!("socket {} already exists; it may be in use by another process, or remain from one that stopped early, in which case remove it", path)));
+    }
+    UnixListener::bind(path).map(|listener| Listener::Unix(listener, PathBuf::from(path)))
+}
+
+#[cfg(not(unix))]
+fn bind_unix(path: &str) -> Result<Listener> {
+    Err(Error::new(ErrorKind::Other, format!("cannot listen on {}: Unix domain sockets are not supported on this platform", path)))
+}
+
 /// Creates socket connections from a list of host addresses.
-pub fn create_sockets(addresses: Vec<String>, my_index: usize, noisy: bool) -> Result<Vec<Option<TcpStream>>> {
+///
+/// Addresses starting with `unix:` name the path of a Unix domain socket, and other addresses
+/// are used for TCP connections.
+///
+/// The number of worker threads in each process is listed in `workers`, which must have the
+/// same length as `addresses`.
+///
+/// Each connection is validated by exchanging a `Handshake`, and an error is returned if the
+/// processes disagree about the configuration, or if `options.deadline` elapses first.
+/// Accepted connections that do not send a handshake within `options.handshake_timeout` are
+/// dropped, as are those sending something else, and other connections are awaited instead.
+pub fn create_sockets(addresses: Vec<String>, my_index: usize, workers: &[usize], noisy: bool, options: ConnectionOptions) -> Result<Vec<Option<Connection>>> {
+
+    assert_eq!(addresses.len(), workers.len());
+    let handshake = Handshake::new(my_index, workers, options.validate_frames);
+    let deadline = options.deadline.map(|duration| Instant::now() + duration);
 
     let hosts1 = Arc::new(addresses);
     let hosts2 = hosts1.clone();
+    let options1 = options.clone();
+    let options2 = options;
+
+    // Either task failing cancels the other, which stops at its next attempt to connect.
+    let cancel1 = Arc::new(AtomicBool::new(false));
+    let cancel2 = cancel1.clone();
+    let cancel = cancel1.clone();
 
-    let start_task = thread::spawn(move || start_connections(hosts1, my_index, noisy));
-    let await_task = thread::spawn(move || await_connections(hosts2, my_index, noisy));
+    // Report results as they complete, so that either task can fail fast.
+    let (send1, recv) = channel();
+    let send2 = send1.clone();
+    let threads = vec![
+        thread::spawn(move || send1.send((true, start_connections(hosts1, handshake, deadline, &cancel1, options1, noisy)))),
+        thread::spawn(move || send2.send((false, await_connections(hosts2, handshake, deadline, &cancel2, options2, noisy)))),
+    ];
 
-    let mut results = start_task.join().unwrap()?;
+    let mut started = None;
+    let mut awaited = None;
+    let mut failure = None;
+    for _ in 0 .. 2 {
+        match recv.recv().expect("connection thread panicked") {
+            (true, Ok(result)) => { started = Some(result); },
+            (false, Ok(result)) => { awaited = Some(result); },
+            (_, Err(error)) => {
+                cancel.store(true, Ordering::SeqCst);
+                // Report the first failure, rather than the cancellation it causes.
+                if failure.is_none() { failure = Some(error); }
+            },
+        }
+    }
+
+    // Both tasks have reported, and hold nothing (for example, a listener) once joined.
+    for thread in threads {
+        let _ = thread.join();
+    }
+    if let Some(error) = failure {
+        return Err(error);
+    }
+
+    let mut results = started.unwrap();
     results.push(None);
-    let to_extend = await_task.join().unwrap()?;
-    results.extend(to_extend.into_iter());
+    results.extend(awaited.unwrap().into_iter());
 
     if noisy { println!("worker {}:\tinitialization complete", my_index) }
 
     Ok(results)
 }
 
+/// The time remaining before `deadline`, or an error if it has passed or `cancel` is set.
+fn remaining(deadline: Option<Instant>, cancel: &AtomicBool, my_index: usize, waiting_for: &str) -> Result<Option<Duration>> {
+    if cancel.load(Ordering::SeqCst) {
+        return Err(Error::new(ErrorKind::Interrupted, format!("worker {}: cancelled waiting for {}", my_index, waiting_for)));
+    }
+    match deadline {
+        Some(deadline) => {
+            let now = Instant::now();
+            if now >= deadline {
+                Err(Error::new(ErrorKind::TimedOut, format!("worker {}: deadline elapsed waiting for {}", my_index, waiting_for)))
+            }
+            else {
+                Ok(Some(deadline - now))
+            }
+        },
+        None => Ok(None),
+    }
+}
+
+/// Sleeps for `backoff` (or until `deadline`), and doubles `backoff` up to `max_backoff`.
+fn back_off(backoff: &mut Duration, max_backoff: Duration, deadline: Option<Instant>) {
+    let mut delay = *backoff;
+    if let Some(deadline) = deadline {
+        let now = Instant::now();
+        if deadline > now && deadline - now < delay { delay = deadline - now; }
+    }
+    sleep(delay);
+    *backoff = ::std::cmp::min(*backoff * 2, max_backoff);
+}
 
 /// Result contains connections [0, my_index - 1].
-pub fn start_connections(addresses: Arc<Vec<String>>, my_index: usize, noisy: bool) -> Result<Vec<Option<TcpStream>>> {
+pub fn start_connections(addresses: Arc<Vec<String>>, handshake: Handshake, deadline: Option<Instant>, cancel: &AtomicBool, options: ConnectionOptions, noisy: bool) -> Result<Vec<Option<Connection>>> {
+    let my_index = handshake.process as usize;
     let mut results: Vec<_> = (0..my_index).map(|_| None).collect();
     for index in 0..my_index {
+        let mut backoff = options.initial_backoff;
         let mut connected = false;
         while !connected {
-            match TcpStream::connect(&addresses[index][..]) {
+            remaining(deadline, cancel, my_index, &format!("connection to worker {}", index))?;
+            match Connection::connect(&addresses[index][..]) {
                 Ok(mut stream) => {
-                    stream.set_nodelay(true).expect("set_nodelay call failed");
-                    unsafe { encode(&(my_index as u64), &mut stream) }.expect("failed to encode/send worker index");
+                    stream.set_read_timeout(remaining(deadline, cancel, my_index, &format!("handshake from worker {}", index))?)?;
+                    handshake.write_to(&mut stream)?;
+                    let remote = Handshake::read_from(&mut stream)?;
+                    handshake.validate(&remote, Some(index))?;
+                    stream.set_read_timeout(None)?;
                     results[index as usize] = Some(stream);
                     if noisy { println!("worker {}:\tconnection to worker {}", my_index, index); }
                     connected = true;
                 },
                 Err(error) => {
                     println!("worker {}:\terror connecting to worker {}: {}; retrying", my_index, index, error);
-                    sleep(Duration::from_secs(1));
+                    back_off(&mut backoff, options.max_backoff, deadline);
                 },
             }
         }
@@ -99,19 +524,250 @@ pub fn start_connections(addresses: Arc<Vec<String>>, my_index: usize, noisy: bo
 }
 
 /// Result contains connections [my_index + 1, addresses.len() - 1].
-pub fn await_connections(addresses: Arc<Vec<String>>, my_index: usize, noisy: bool) -> Result<Vec<Option<TcpStream>>> {
+pub fn await_connections(addresses: Arc<Vec<String>>, handshake: Handshake, deadline: Option<Instant>, cancel: &AtomicBool, options: ConnectionOptions, noisy: bool) -> Result<Vec<Option<Connection>>> {
+    let my_index = handshake.process as usize;
     let mut results: Vec<_> = (0..(addresses.len() - my_index - 1)).map(|_| None).collect();
-    let listener = try!(TcpListener::bind(&addresses[my_index][..]));
+    let listener = try!(Listener::bind(&addresses[my_index][..]));
+
+    // Poll for connections, so that the deadline and cancellation are respected.
+    listener.set_nonblocking(true)?;
 
     for _ in (my_index + 1) .. addresses.len() {
-        let mut stream = listener.accept()?.0;
-        stream.set_nodelay(true).expect("set_nodelay call failed");
-        let mut buffer = [0u8;8];
-        stream.read_exact(&mut buffer).expect("failed to read worker index");
-        let identifier = unsafe { decode::<u64>(&mut buffer) }.expect("failed to decode worker index").0.clone() as usize;
+        let (mut stream, remote) = loop {
+            let mut backoff = options.initial_backoff;
+            let mut stream = loop {
+                remaining(deadline, cancel, my_index, "connections from other workers")?;
+                match listener.accept() {
+                    Ok(stream) => break stream,
+                    Err(ref error) if error.kind() == ErrorKind::WouldBlock => {
+                        // Connections are expected promptly, so poll without growing the delay.
+                        back_off(&mut backoff, options.initial_backoff, deadline);
+                    },
+                    Err(error) => return Err(error),
+                }
+            };
+            // Wait a bounded time for the handshake, so that a client that never sends one cannot
+            // stall startup, and drop connections that do not start with one.
+            let timeout = match remaining(deadline, cancel, my_index, "handshake from connecting worker")? {
+                Some(remaining) => ::std::cmp::min(remaining, options.handshake_timeout),
+                None => options.handshake_timeout,
+            };
+            stream.set_read_timeout(Some(timeout))?;
+            match Handshake::read_from(&mut stream) {
+                Ok(remote) => break (stream, remote),
+                Err(error) => {
+                    if noisy { println!("worker {}:	dropped connection without a handshake: {}", my_index, error); }
+                },
+            }
+        };
+        // Respond before validating, so that the remote process can report the problem too.
+        handshake.write_to(&mut stream)?;
+        handshake.validate(&remote, None)?;
+        let identifier = remote.process as usize;
+        if identifier < my_index {
+            return Err(invalid_data(format!("process {} connected to process {}, but should await its connection", identifier, my_index)));
+        }
+        if results[identifier - my_index - 1].is_some() {
+            return Err(invalid_data(format!("two processes claim index {}", identifier)));
+        }
+        stream.set_read_timeout(None)?;
         results[identifier - my_index - 1] = Some(stream);
         if noisy { println!("worker {}:\tconnection from worker {}", my_index, identifier); }
     }
 
     Ok(results)
 }
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Write;
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use std::io::{Result, ErrorKind};
+
+    use super::{create_sockets, Connection, ConnectionOptions, Handshake};
+
+    fn free_addresses(processes: usize) -> Vec<String> {
+        let listeners = (0 .. processes).map(|_| TcpListener::bind("127.0.0.1:0").unwrap()).collect::<Vec<_>>();
+        listeners.iter().map(|listener| format!("127.0.0.1:{}", listener.local_addr().unwrap().port())).collect()
+    }
+
+    fn options() -> ConnectionOptions {
+        let mut options = ConnectionOptions::default();
+        options.deadline = Some(Duration::from_secs(10));
+        options.initial_backoff = Duration::from_millis(10);
+        options
+    }
+
+    // Connects processes 0 and 1 of `addresses`, which expect the worker counts `workers0` and
+    // `workers1` respectively, returning the result of each.
+    fn connect_pair(addresses: Vec<String>, workers0: Vec<usize>, workers1: Vec<usize>) -> (Result<Vec<Option<Connection>>>, Result<Vec<Option<Connection>>>) {
+        let mut addresses0 = addresses.clone();
+        addresses0.truncate(workers0.len());
+        let mut addresses1 = addresses;
+        addresses1.truncate(workers1.len());
+        let process0 = thread::spawn(move || create_sockets(addresses0, 0, &workers0, false, options()));
+        let process1 = thread::spawn(move || create_sockets(addresses1, 1, &workers1, false, options()));
+        (process0.join().unwrap(), process1.join().unwrap())
+    }
+
+    fn assert_invalid(result: Result<Vec<Option<Connection>>>, message: &str) {
+        match result {
+            Err(error) => {
+                assert_eq!(error.kind(), ErrorKind::InvalidData, "{}", error);
+                assert!(error.to_string().contains(message), "{}", error);
+            },
+            Ok(_) => panic!("connected despite expecting: {}", message),
+        }
+    }
+
+    #[test]
+    fn handshake_accepted() {
+        let (result0, result1) = connect_pair(free_addresses(2), vec![2, 3], vec![2, 3]);
+        assert!(result0.unwrap()[1].is_some());
+        assert!(result1.unwrap()[0].is_some());
+    }
+
+    #[test]
+    fn process_count_mismatch() {
+        // Process 1 also awaits a third process, which must be cancelled rather than awaited.
+        let (result0, result1) = connect_pair(free_addresses(3), vec![2, 2], vec![2, 2, 2]);
+        assert_invalid(result0, "expects 3 processes, but process 0 expects 2");
+        assert_invalid(result1, "expects 2 processes, but process 1 expects 3");
+    }
+
+    #[test]
+    fn thread_count_mismatch() {
+        let (result0, result1) = connect_pair(free_addresses(2), vec![2, 2], vec![2, 3]);
+        assert_invalid(result0, "process 1 (with 3 threads) expects different numbers of threads per process");
+        assert_invalid(result1, "process 0 (with 2 threads) expects different numbers of threads per process");
+    }
+
+    #[test]
+    fn worker_count_mismatch() {
+        // The same numbers of processes and of workers in total, distributed differently.
+        let local = Handshake::new(0, &[2, 3], false);
+        let remote = Handshake::new(1, &[3, 2], false);
+        let error = local.validate(&remote, Some(1)).err().expect("mismatched counts accepted");
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(error.to_string().contains("process 1 (with 2 threads) expects different numbers of threads per process than process 0 (with 2 threads)"), "{}", error);
+
+        // Uneven counts that agree are accepted.
+        assert!(local.validate(&Handshake::new(1, &[2, 3], false), Some(1)).is_ok());
+    }
+
+    #[test]
+    fn protocol_mismatch() {
+        let addresses = free_addresses(2);
+
+        // Process 0 speaks a later version of the protocol.
+        let listener = TcpListener::bind(&addresses[0][..]).unwrap();
+        let process0 = thread::spawn(move || {
+            let mut stream = listener.accept().unwrap().0;
+            let remote = Handshake::read_from(&mut stream).unwrap();
+            let mut handshake = Handshake::new(0, &[1, 1], false);
+            handshake.protocol = remote.protocol + 1;
+            handshake.write_to(&mut stream).unwrap();
+        });
+
+        let result = create_sockets(addresses, 1, &[1, 1], false, options());
+        process0.join().unwrap();
+        assert_invalid(result, "speaks protocol version");
+    }
+
+    #[test]
+    fn stray_connections_dropped() {
+        let addresses = free_addresses(2);
+        // Without a deadline, only the handshake timeout bounds the wait for stray clients.
+        let mut options0 = options();
+        options0.deadline = None;
+        options0.handshake_timeout = Duration::from_millis(200);
+        let addresses0 = addresses.clone();
+        let process0 = thread::spawn(move || create_sockets(addresses0, 0, &[1, 1], false, options0));
+
+        // One client connects and sends nothing, and another sends something other than a handshake.
+        let silent = loop {
+            match TcpStream::connect(&addresses[0][..]) {
+                Ok(stream) => break stream,
+                Err(_) => thread::sleep(Duration::from_millis(10)),
+            }
+        };
+        let mut garbage = TcpStream::connect(&addresses[0][..]).unwrap();
+        garbage.write_all(&[0xff; 64]).unwrap();
+
+        let result1 = create_sockets(addresses, 1, &[1, 1], false, options());
+        assert!(process0.join().unwrap().unwrap()[1].is_some());
+        assert!(result1.unwrap()[0].is_some());
+        drop(silent);
+    }
+
+    #[test]
+    fn deadline_elapses() {
+        // No process 0 listens, and process 2 never connects.
+        let mut options = options();
+        options.deadline = Some(Duration::from_millis(200));
+        let start = Instant::now();
+        let result = create_sockets(free_addresses(3), 1, &[1, 1, 1], false, options);
+        let elapsed = start.elapsed();
+        match result {
+            Err(error) => assert_eq!(error.kind(), ErrorKind::TimedOut, "{}", error),
+            Ok(_) => panic!("connected without other processes"),
+        }
+        assert!(elapsed >= Duration::from_millis(200), "returned after {:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(5), "returned after {:?}", elapsed);
+    }
+
+    #[cfg(unix)]
+    fn socket_path(name: &str) -> String {
+        let path = ::std::env::temp_dir().join(format!("timely-{}-{}", name, ::std::process::id()));
+        let _ = ::std::fs::remove_file(&path);
+        path.to_str().unwrap().to_owned()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_socket_stale() {
+        use std::os::unix::net::UnixListener;
+        let path = socket_path("stale");
+        // Dropping a listener leaves its socket behind, as a process that stopped early would.
+        drop(UnixListener::bind(&path).unwrap());
+        match super::bind_unix(&path) {
+            Err(error) => assert_eq!(error.kind(), ErrorKind::AddrInUse, "{}", error),
+            Ok(_) => panic!("replaced an existing socket"),
+        }
+        assert!(::std::path::Path::new(&path).exists());
+        ::std::fs::remove_file(&path).unwrap();
+        super::bind_unix(&path).unwrap();
+        assert!(!::std::path::Path::new(&path).exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_socket_in_use() {
+        use std::os::unix::net::UnixListener;
+        let path = socket_path("in-use");
+        let listener = UnixListener::bind(&path).unwrap();
+        match super::bind_unix(&path) {
+            Err(error) => assert_eq!(error.kind(), ErrorKind::AddrInUse, "{}", error),
+            Ok(_) => panic!("replaced a socket in use"),
+        }
+        // The process listening on the socket must not have been contacted.
+        listener.set_nonblocking(true).unwrap();
+        match listener.accept() {
+            Err(error) => assert_eq!(error.kind(), ErrorKind::WouldBlock),
+            Ok(_) => panic!("connected to a socket in use"),
+        }
+        ::std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn failure_releases_listener() {
+        let addresses = free_addresses(3);
+        let (result0, _) = connect_pair(addresses.clone(), vec![1, 1], vec![1, 1, 1]);
+        assert!(result0.is_err());
+        // Process 1 listened at its address while awaiting process 2, and must have stopped.
+        TcpListener::bind(&addresses[1][..]).unwrap();
+    }
+}
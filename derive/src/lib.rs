@@ -0,0 +1,201 @@
+//! Derive macros for timely dataflow timestamps.
+//!
+//! A struct whose fields are all timestamps is itself a timestamp, ordered as the product of its
+//! fields: one value is less or equal to another exactly when each of its fields is. Deriving
+//! `PartialOrder` implements this order, and deriving `Timestamp` introduces a summary type, named
+//! after the struct with a `Summary` suffix, whose fields are the summaries of the corresponding
+//! fields and which advances each field independently.
+//!
+//! The remaining traits timestamps require are derived by the standard and `abomonation_derive`
+//! macros; the derived `Ord` orders fields lexicographically, which is consistent with the product
+//! order. See the `Timestamp` trait in `timely::progress::timestamp` for an example.
+
+#![forbid(missing_docs)]
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate syn;
+#[macro_use]
+extern crate quote;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use syn::{Data, DeriveInput, Fields, Ident, Index, Type};
+
+/// Implements `timely::order::PartialOrder` as the product of the partial orders of the fields.
+#[proc_macro_derive(PartialOrder)]
+pub fn derive_partial_order(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let fields = match struct_fields(&input, "PartialOrder") {
+        Ok(fields) => fields,
+        Err(error) => return error.to_compile_error().into(),
+    };
+
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    for &(_, ty) in fields.iter() {
+        generics.make_where_clause().predicates.push(parse_quote!(#ty: ::timely::order::PartialOrder));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let less_equal = less_equal(&fields);
+
+    let expanded = quote! {
+        impl #impl_generics ::timely::order::PartialOrder for #name #ty_generics #where_clause {
+            #[inline]
+            fn less_equal(&self, other: &Self) -> bool {
+                #less_equal
+            }
+        }
+    };
+    expanded.into()
+}
+
+/// Implements `timely::progress::Timestamp`, with a summary type that summarizes each field.
+///
+/// The summary type is a struct with the name of the timestamp type followed by `Summary`, with
+/// the same visibility and shape, and whose fields are the summaries of the fields of the same
+/// name or position. It implements `PathSummary` by applying and composing the summaries of each
+/// field, and `PartialOrder` as the product of their orders.
+#[proc_macro_derive(Timestamp)]
+pub fn derive_timestamp(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let fields = match struct_fields(&input, "Timestamp") {
+        Ok(fields) => fields,
+        Err(error) => return error.to_compile_error().into(),
+    };
+
+    let name = &input.ident;
+    let vis = &input.vis;
+    let summary = Ident::new(&format!("{}Summary", name), name.span());
+
+    let mut generics = input.generics.clone();
+    for &(_, ty) in fields.iter() {
+        generics.make_where_clause().predicates.push(parse_quote!(#ty: ::timely::progress::Timestamp));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // The summary type mirrors the fields of the timestamp type.
+    let summary_doc = format!("A summary of paths for `{}`, advancing each field by a summary of its own.", name);
+    let summary_fields = match struct_data(&input).fields {
+        Fields::Named(ref named) => {
+            let fields = named.named.iter().map(|field| {
+                let (vis, ident, ty) = (&field.vis, &field.ident, &field.ty);
+                let doc = format!("The summary for field `{}`.", ident.as_ref().expect("named field without name"));
+                quote! {
+                    #[doc = #doc]
+                    #vis #ident: <#ty as ::timely::progress::Timestamp>::Summary
+                }
+            });
+            quote! { #where_clause { #(#fields),* } }
+        }
+        Fields::Unnamed(ref unnamed) => {
+            let fields = unnamed.unnamed.iter().map(|field| {
+                let (vis, ty) = (&field.vis, &field.ty);
+                quote! { #vis <#ty as ::timely::progress::Timestamp>::Summary }
+            });
+            quote! { ( #(#fields),* ) #where_clause ; }
+        }
+        Fields::Unit => quote! { #where_clause ; },
+    };
+
+    let results_in = construct(&input, name, |member| quote! {
+        match ::timely::progress::timestamp::PathSummary::results_in(&self.#member, &src.#member) {
+            Some(time) => time,
+            None => return None,
+        }
+    });
+    let followed_by = construct(&input, &summary, |member| quote! {
+        match ::timely::progress::timestamp::PathSummary::followed_by(&self.#member, &other.#member) {
+            Some(summary) => summary,
+            None => return None,
+        }
+    });
+    let less_equal = less_equal(&fields);
+
+    let expanded = quote! {
+        #[doc = #summary_doc]
+        #[derive(Clone, Debug, Default, PartialEq, Eq)]
+        #vis struct #summary #generics #summary_fields
+
+        impl #impl_generics ::timely::progress::Timestamp for #name #ty_generics #where_clause {
+            type Summary = #summary #ty_generics;
+        }
+
+        impl #impl_generics ::timely::progress::timestamp::PathSummary<#name #ty_generics> for #summary #ty_generics #where_clause {
+            #[inline]
+            fn results_in(&self, src: &#name #ty_generics) -> Option<#name #ty_generics> {
+                Some(#results_in)
+            }
+            #[inline]
+            fn followed_by(&self, other: &Self) -> Option<Self> {
+                Some(#followed_by)
+            }
+        }
+
+        impl #impl_generics ::timely::order::PartialOrder for #summary #ty_generics #where_clause {
+            #[inline]
+            fn less_equal(&self, other: &Self) -> bool {
+                #less_equal
+            }
+        }
+    };
+    expanded.into()
+}
+
+// The struct data of `input`, which must be a struct.
+fn struct_data(input: &DeriveInput) -> &syn::DataStruct {
+    match input.data {
+        Data::Struct(ref data) => data,
+        _ => unreachable!("derive input checked to be a struct"),
+    }
+}
+
+// The members by which the fields of `input` are accessed, and their types, or an error if `input`
+// is not a struct.
+fn struct_fields<'a>(input: &'a DeriveInput, derive: &str) -> Result<Vec<(TokenStream2, &'a Type)>, syn::Error> {
+    match input.data {
+        Data::Struct(ref data) => {
+            Ok(data.fields.iter().enumerate().map(|(position, field)| {
+                let member = match field.ident {
+                    Some(ref ident) => quote! { #ident },
+                    None => { let index = Index::from(position); quote! { #index } },
+                };
+                (member, &field.ty)
+            }).collect())
+        }
+        _ => Err(syn::Error::new(input.ident.span(), format!("`{}` can only be derived for structs", derive))),
+    }
+}
+
+// An expression comparing the fields of `self` and `other` in the product order.
+fn less_equal(fields: &[(TokenStream2, &Type)]) -> TokenStream2 {
+    let comparisons = fields.iter().map(|field| {
+        let member = &field.0;
+        quote! { ::timely::order::PartialOrder::less_equal(&self.#member, &other.#member) }
+    });
+    quote! { true #(&& #comparisons)* }
+}
+
+// An expression constructing a `name` with the shape of `input`, each field the result of `field`
+// applied to the member accessing it.
+fn construct<F: Fn(&TokenStream2)->TokenStream2>(input: &DeriveInput, name: &Ident, field: F) -> TokenStream2 {
+    match struct_data(input).fields {
+        Fields::Named(ref named) => {
+            let fields = named.named.iter().map(|f| {
+                let ident = &f.ident;
+                let value = field(&quote! { #ident });
+                quote! { #ident: #value }
+            });
+            quote! { #name { #(#fields),* } }
+        }
+        Fields::Unnamed(ref unnamed) => {
+            let fields = (0 .. unnamed.unnamed.len()).map(|position| {
+                let index = Index::from(position);
+                field(&quote! { #index })
+            });
+            quote! { #name ( #(#fields),* ) }
+        }
+        Fields::Unit => quote! { #name },
+    }
+}
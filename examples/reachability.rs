@@ -1,60 +1,193 @@
 extern crate timely;
 
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use timely::dataflow::{InputHandle, Scope};
+use timely::dataflow::operators::{Input, Map, Filter, Concat, LoopVariable, ConnectLoop, Enter, Leave};
+use timely::logging::TimelyEvent;
+use timely::progress::frontier::Antichain;
+use timely::progress::nested::subgraph::{Source, Target};
+
+// The nodes, as numbers of inputs and outputs and internal summaries, and the edges of a graph.
+type Graph = (Vec<(usize, usize, Vec<Vec<Antichain<usize>>>)>, Vec<(Source, Target)>);
+
 fn main() {
     let nodes: usize = std::env::args().nth(1).unwrap().parse().unwrap();
     let rounds: usize = std::env::args().nth(2).unwrap().parse().unwrap();
+    let shape = std::env::args().nth(3).unwrap_or("ring".to_owned());
+
+    // Each graph has a tracker of its own, as each scope of a dataflow does.
+    let graphs = match shape.as_str() {
+        "ring" => vec![ring(nodes)],
+        "grid" => vec![grid(nodes)],
+        "nested" => nested(nodes),
+        _ => panic!("unrecognized graph shape: {:?}; expected ring, grid, or nested", shape),
+    };
+
+    test_alt(&graphs, rounds);
+    test_neu(&graphs, rounds);
+}
+
+// Nodes connected in sequence, looping around to the first from the last.
+fn ring(nodes: usize) -> Graph {
+    let mut graph = (Vec::new(), Vec::new());
+    for _ in 1 .. nodes {
+        graph.0.push((1, 1, vec![vec![Antichain::from_elem(0)]]));
+    }
+    graph.0.push((1, 1, vec![vec![Antichain::from_elem(1)]]));
+    for index in 1 .. nodes {
+        graph.1.push((Source { index: index - 1, port: 0 }, Target { index, port: 0 }));
+    }
+    graph.1.push((Source { index: nodes - 1, port: 0 }, Target { index: 0, port: 0 }));
+    graph
+}
 
-    test_alt(nodes, rounds);
-    test_neu(nodes, rounds);
+// Square layers of nodes, each connected to two nodes of the next layer, looping around to the first
+// layer from the last. There are many paths between pairs of nodes.
+fn grid(nodes: usize) -> Graph {
+    let width = (nodes as f64).sqrt() as usize;
+    let mut graph = (Vec::new(), Vec::new());
+    for layer in 0 .. width {
+        let summary = if layer + 1 < width { 0 } else { 1 };
+        for column in 0 .. width {
+            graph.0.push((1, 1, vec![vec![Antichain::from_elem(summary)]]));
+            let next = ((layer + 1) % width) * width;
+            let source = Source { index: layer * width + column, port: 0 };
+            graph.1.push((source, Target { index: next + column, port: 0 }));
+            graph.1.push((source, Target { index: next + (column + 1) % width, port: 0 }));
+        }
+    }
+    graph
 }
 
+// Builds a loop in a new scope around the loops `$rest` describe, each a token, or around a `map`.
+macro_rules! nest {
+    ($stream:expr;) => { $stream.map(|x: u64| x + 1) };
+    ($stream:expr; $head:tt $($rest:tt)*) => {{
+        let stream = $stream;
+        let mut scope = stream.scope();
+        scope.scoped::<u64,_,_>(|inner| {
+            let (handle, cycle) = inner.loop_variable::<u64>(2, 1);
+            let looped = nest!(stream.enter(inner).concat(&cycle); $($rest)*);
+            looped.filter(|x| x % 2 == 0).connect_loop(handle);
+            looped.leave()
+        })
+    }};
+}
+
+// The graphs of `depth` iterative scopes nested within one another, as built by `scoped`, recovered
+// from the operators and channels the worker logs. Timestamps are those of the innermost coordinate
+// of each scope, which only `Feedback` operators advance.
+fn nested(depth: usize) -> Vec<Graph> {
+    timely::execute(timely::Configuration::Thread, move |worker| {
+
+        let operators = Rc::new(RefCell::new(Vec::new()));
+        let channels = Rc::new(RefCell::new(Vec::new()));
+        let (operators2, channels2) = (operators.clone(), channels.clone());
+        worker.log_register().insert::<TimelyEvent,_>("timely", move |_time, data| {
+            for (_, _, event) in data.drain(..) {
+                match event {
+                    TimelyEvent::Operates(event) => operators2.borrow_mut().push(event),
+                    TimelyEvent::Channels(event) => channels2.borrow_mut().push(event),
+                    _ => { },
+                }
+            }
+        });
+
+        let mut input = InputHandle::new();
+        worker.dataflow::<u64,_,_>(|scope| {
+            let stream = scope.input_from(&mut input);
+            match depth {
+                1 => { nest!(stream; x); },
+                2 => { nest!(stream; x x); },
+                3 => { nest!(stream; x x x); },
+                4 => { nest!(stream; x x x x); },
+                5 => { nest!(stream; x x x x x); },
+                6 => { nest!(stream; x x x x x x); },
+                7 => { nest!(stream; x x x x x x x); },
+                8 => { nest!(stream; x x x x x x x x); },
+                _ => panic!("scopes may be nested from 1 to 8 deep, not {}", depth),
+            }
+        });
+        worker.log_register().flush();
+
+        // The scopes nested within the dataflow, from the outermost.
+        let operators = operators.borrow();
+        let channels = channels.borrow();
+        let mut scopes = operators.iter().filter(|op| op.name == "Subgraph" && op.addr.len() > 1).map(|op| op.addr.clone()).collect::<Vec<_>>();
+        scopes.sort_by_key(|addr| addr.len());
+
+        scopes.iter().map(|scope| {
+            let edges = channels.iter().filter(|channel| &channel.scope_addr == scope).map(|channel| {
+                (Source { index: channel.source.0, port: channel.source.1 }, Target { index: channel.target.0, port: channel.target.1 })
+            }).collect::<Vec<_>>();
+
+            let count = edges.iter().map(|&(source, target)| ::std::cmp::max(source.index, target.index) + 1).max().unwrap_or(1);
+            let nodes = (0 .. count).map(|index| {
+                let inputs = edges.iter().filter(|edge| edge.1.index == index).map(|edge| edge.1.port + 1).max().unwrap_or(0);
+                let outputs = edges.iter().filter(|edge| edge.0.index == index).map(|edge| edge.0.port + 1).max().unwrap_or(0);
+                let feedback = operators.iter().any(|op| op.name == "Feedback" && op.addr[.. op.addr.len() - 1] == scope[..] && op.addr[op.addr.len() - 1] == index);
+                // The scope's own node connects its outputs to none of its inputs.
+                let summary = if index == 0 { Antichain::new() } else { Antichain::from_elem(if feedback { 1 } else { 0 }) };
+                (inputs, outputs, vec![vec![summary; outputs]; inputs])
+            }).collect();
+
+            (nodes, edges)
+        }).collect::<Vec<_>>()
+
+    }).unwrap().join().pop().unwrap().unwrap()
+}
 
-fn test_alt(nodes: usize, rounds: usize) {
+fn test_alt(graphs: &[Graph], rounds: usize) {
 
-    use timely::progress::frontier::Antichain;
-    use timely::progress::nested::subgraph::{Source, Target};
     use timely::progress::nested::reachability::{Builder, Tracker};
 
     // This test means to exercise the efficiency of the tracker by performing local changes and expecting
     // that it will respond efficiently.
 
-    // allocate a new empty topology builder.
-    let mut builder = Builder::<usize>::new();
+    let nodes = graphs.iter().map(|graph| graph.0.len()).sum::<usize>();
+    let timer = ::std::time::Instant::now();
 
-    // Each node with one input connected to one output.
-    for index in 1 .. nodes {
-        builder.add_node(index - 1, 1, 1, vec![vec![Antichain::from_elem(0)]]);
-    }
-    builder.add_node(nodes - 1, 1, 1, vec![vec![Antichain::from_elem(1)]]);
+    let mut trackers = graphs.iter().map(|graph| {
+        // allocate a new empty topology builder.
+        let mut builder = Builder::<usize>::new();
+        for (index, &(inputs, outputs, ref summary)) in graph.0.iter().enumerate() {
+            builder.add_node(index, inputs, outputs, summary.clone());
+        }
+        for &(source, target) in graph.1.iter() {
+            builder.add_edge(source, target);
+        }
 
-    // Connect nodes in sequence, looping around to the first from the last.
-    for index in 1 .. nodes {
-        builder.add_edge(Source { index: index - 1, port: 0 }, Target { index, port: 0 } );
-    }
-    builder.add_edge(Source { index: nodes - 1, port: 0 }, Target { index: 0, port: 0 } );
+        // Construct a reachability tracker.
+        (Tracker::allocate_from(builder.summarize()), graph.0.len())
+    }).collect::<Vec<_>>();
 
-    // Construct a reachability tracker.
-    let mut tracker = Tracker::allocate_from(builder.summarize());
+    println!("Reachability (alt) built for {} nodes; total: {:?}", nodes, timer.elapsed());
 
     let timer = ::std::time::Instant::now();
 
-    tracker.update_target(Target { index: 0, port: 0 }, 0, 1);
-    tracker.propagate_all();
-    for index in 0 .. nodes { tracker.pushed_mut(index).iter_mut().for_each(|x| { x.drain(); }); }
+    for &mut (ref mut tracker, nodes) in trackers.iter_mut() {
+        tracker.update_target(Target { index: 0, port: 0 }, 0, 1);
+        tracker.propagate_all();
+        for index in 0 .. nodes { tracker.pushed_mut(index).iter_mut().for_each(|x| { x.drain(); }); }
+    }
 
     for round in 0 .. rounds {
+        for &mut (ref mut tracker, nodes) in trackers.iter_mut() {
 
-        for index in 1 .. nodes {
-            tracker.update_target(Target { index: index - 1, port: 0 }, round, -1);
-            tracker.update_target(Target { index, port: 0 }, round, 1);
+            for index in 1 .. nodes {
+                tracker.update_target(Target { index: index - 1, port: 0 }, round, -1);
+                tracker.update_target(Target { index, port: 0 }, round, 1);
+                tracker.propagate_all();
+                for index in 0 .. nodes { tracker.pushed_mut(index).iter_mut().for_each(|x| { x.drain(); }); }
+            }
+
+            tracker.update_target(Target { index: nodes - 1, port: 0 }, round, -1);
+            tracker.update_target(Target { index: 0, port: 0 }, round + 1, 1);
             tracker.propagate_all();
             for index in 0 .. nodes { tracker.pushed_mut(index).iter_mut().for_each(|x| { x.drain(); }); }
         }
-
-        tracker.update_target(Target { index: nodes - 1, port: 0 }, round, -1);
-        tracker.update_target(Target { index: 0, port: 0 }, round + 1, 1);
-        tracker.propagate_all();
-        for index in 0 .. nodes { tracker.pushed_mut(index).iter_mut().for_each(|x| { x.drain(); }); }
     }
 
     let elapsed = timer.elapsed();
@@ -62,63 +195,68 @@ fn test_alt(nodes: usize, rounds: usize) {
 
     let timer = ::std::time::Instant::now();
     for round in rounds .. 2 * rounds {
-
-        tracker.update_target(Target { index: 0, port: 0 }, round, -1);
-        tracker.update_target(Target { index: 0, port: 0 }, round + 1, 1);
-        tracker.propagate_all();
-        for index in 0 .. nodes { tracker.pushed_mut(index).iter_mut().for_each(|x| { x.drain(); }); }
+        for &mut (ref mut tracker, nodes) in trackers.iter_mut() {
+            tracker.update_target(Target { index: 0, port: 0 }, round, -1);
+            tracker.update_target(Target { index: 0, port: 0 }, round + 1, 1);
+            tracker.propagate_all();
+            for index in 0 .. nodes { tracker.pushed_mut(index).iter_mut().for_each(|x| { x.drain(); }); }
+        }
     }
 
     let elapsed = timer.elapsed();
     println!("Reachability (alt) elapsed for {} nodes; avg: {:?}, total: {:?}", nodes, elapsed / (rounds as u32), elapsed);
 }
 
-fn test_neu(nodes: usize, rounds: usize) {
+fn test_neu(graphs: &[Graph], rounds: usize) {
 
-    use timely::progress::frontier::Antichain;
-    use timely::progress::nested::subgraph::{Source, Target};
     use timely::progress::nested::reachability_neu::Builder;
 
     // This test means to exercise the efficiency of the tracker by performing local changes and expecting
     // that it will respond efficiently.
 
-    // allocate a new empty topology builder.
-    let mut builder = Builder::<usize>::new();
+    let nodes = graphs.iter().map(|graph| graph.0.len()).sum::<usize>();
+    let timer = ::std::time::Instant::now();
 
-    // Each node with one input connected to one output.
-    for index in 1 .. nodes {
-        builder.add_node(index - 1, 1, 1, vec![vec![Antichain::from_elem(0)]]);
-    }
-    builder.add_node(nodes - 1, 1, 1, vec![vec![Antichain::from_elem(1)]]);
+    let mut trackers = graphs.iter().map(|graph| {
+        // allocate a new empty topology builder.
+        let mut builder = Builder::<usize>::new();
+        for (index, &(inputs, outputs, ref summary)) in graph.0.iter().enumerate() {
+            builder.add_node(index, inputs, outputs, summary.clone());
+        }
+        for &(source, target) in graph.1.iter() {
+            builder.add_edge(source, target);
+        }
 
-    // Connect nodes in sequence, looping around to the first from the last.
-    for index in 1 .. nodes {
-        builder.add_edge(Source { index: index - 1, port: 0 }, Target { index, port: 0 } );
-    }
-    builder.add_edge(Source { index: nodes - 1, port: 0 }, Target { index: 0, port: 0 } );
+        // Summarize the graph, as subgraphs do to validate it, and construct a reachability tracker.
+        builder.summarize().expect("graph has cycles without timestamp advances");
+        (builder.build(), graph.0.len())
+    }).collect::<Vec<_>>();
 
-    // Construct a reachability tracker.
-    let mut tracker = builder.build();
+    println!("Reachability (neu) built for {} nodes; total: {:?}", nodes, timer.elapsed());
 
     let timer = ::std::time::Instant::now();
 
-    tracker.update_target(Target { index: 0, port: 0 }, 0, 1);
-    tracker.propagate_all();
-    tracker.pushed().drain();
+    for &mut (ref mut tracker, _nodes) in trackers.iter_mut() {
+        tracker.update_target(Target { index: 0, port: 0 }, 0, 1);
+        tracker.propagate_all();
+        tracker.pushed().drain();
+    }
 
     for round in 0 .. rounds {
+        for &mut (ref mut tracker, nodes) in trackers.iter_mut() {
+
+            for index in 1 .. nodes {
+                tracker.update_target(Target { index: index - 1, port: 0 }, round, -1);
+                tracker.update_target(Target { index, port: 0 }, round, 1);
+                tracker.propagate_all();
+                tracker.pushed().drain();
+            }
 
-        for index in 1 .. nodes {
-            tracker.update_target(Target { index: index - 1, port: 0 }, round, -1);
-            tracker.update_target(Target { index, port: 0 }, round, 1);
+            tracker.update_target(Target { index: nodes - 1, port: 0 }, round, -1);
+            tracker.update_target(Target { index: 0, port: 0 }, round + 1, 1);
             tracker.propagate_all();
             tracker.pushed().drain();
         }
-
-        tracker.update_target(Target { index: nodes - 1, port: 0 }, round, -1);
-        tracker.update_target(Target { index: 0, port: 0 }, round + 1, 1);
-        tracker.propagate_all();
-        tracker.pushed().drain();
     }
 
     let elapsed = timer.elapsed();
@@ -126,11 +264,12 @@ fn test_neu(nodes: usize, rounds: usize) {
 
     let timer = ::std::time::Instant::now();
     for round in rounds .. 2 * rounds {
-
-        tracker.update_target(Target { index: 0, port: 0 }, round, -1);
-        tracker.update_target(Target { index: 0, port: 0 }, round + 1, 1);
-        tracker.propagate_all();
-        tracker.pushed().drain();
+        for &mut (ref mut tracker, _nodes) in trackers.iter_mut() {
+            tracker.update_target(Target { index: 0, port: 0 }, round, -1);
+            tracker.update_target(Target { index: 0, port: 0 }, round + 1, 1);
+            tracker.propagate_all();
+            tracker.pushed().drain();
+        }
     }
 
     let elapsed = timer.elapsed();
@@ -0,0 +1,31 @@
+//! A wrapper which activates the operator receiving the records pushed past.
+
+use communication::Push;
+use scheduling::Activator;
+
+/// A wrapper which activates the operator receiving records, so that it is scheduled to read them.
+pub struct Activate<P> {
+    pushee: P,
+    activator: Activator,
+}
+
+impl<T, P: Push<T>> Push<T> for Activate<P> {
+    #[inline(always)]
+    fn push(&mut self, message: &mut Option<T>) {
+        // flushes (`None`) do not deliver records.
+        if message.is_some() {
+            self.activator.activate();
+        }
+        self.pushee.push(message);
+    }
+}
+
+impl<P> Activate<P> {
+    /// Allocates a new `Activate` from a pushee and the activator of the receiving operator.
+    pub fn new(pushee: P, activator: Activator) -> Activate<P> {
+        Activate {
+            pushee,
+            activator,
+        }
+    }
+}
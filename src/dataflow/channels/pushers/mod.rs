@@ -1,8 +1,10 @@
 pub use self::tee::{Tee, TeeHelper};
 pub use self::exchange::Exchange;
 pub use self::counter::Counter;
+pub use self::activate::Activate;
 
 pub mod tee;
 pub mod exchange;
 pub mod counter;
+pub mod activate;
 pub mod buffer;
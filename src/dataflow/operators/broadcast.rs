@@ -53,6 +53,11 @@ impl<G: Scope, D: ExchangeData> Broadcast<D> for Stream<G, D> {
 
         let operator_index = scope.add_operator(Box::new(operator));
 
+        // Data from other workers activate the operator.
+        let mut path = scope.addr();
+        path.push(operator_index);
+        scope.bind_channel(channel_id, &path[..]);
+
         for (i, pusher) in pushers.into_iter().enumerate() {
             let sender = LogPusher::new(pusher, scope.index(), i, channel_id, scope.logging());
             self.connect_to(Target { index: operator_index, port: i }, sender, channel_id);
@@ -29,6 +29,7 @@ use std::fmt::{self, Debug};
 use order::PartialOrder;
 use progress::Timestamp;
 use progress::ChangeBatch;
+use scheduling::Activator;
 
 /// An internal trait expressing the capability to send messages with a given timestamp.
 pub trait CapabilityTrait<T: Timestamp> {
@@ -257,6 +258,86 @@ pub fn mint_ref<'cap, T: Timestamp>(time: &'cap T, internal: Rc<RefCell<Vec<Rc<R
     }
 }
 
+/// A capability held outside of its operator, which activates the operator when changed.
+///
+/// Changes to capabilities are only reported when their operator is scheduled. A capability
+/// held outside of its operator, for example by an input handle, activates the operator each
+/// time it is downgraded or dropped, so that the change is reported.
+pub struct ActivateCapability<T: Timestamp> {
+    capability: Capability<T>,
+    activator: Activator,
+}
+
+impl<T: Timestamp> CapabilityTrait<T> for ActivateCapability<T> {
+    fn time(&self) -> &T { self.capability.time() }
+    fn valid_for_output(&self, query_buffer: &Rc<RefCell<ChangeBatch<T>>>) -> bool {
+        self.capability.valid_for_output(query_buffer)
+    }
+}
+
+impl<T: Timestamp> ActivateCapability<T> {
+    /// Creates a new activating capability from a capability and the activator of its operator.
+    pub fn new(capability: Capability<T>, activator: Activator) -> Self {
+        ActivateCapability {
+            capability,
+            activator,
+        }
+    }
+
+    /// The timestamp associated with this capability.
+    #[inline(always)]
+    pub fn time(&self) -> &T {
+        self.capability.time()
+    }
+
+    /// Makes a new capability for a timestamp `new_time` greater or equal to the timestamp of
+    /// the source capability (`self`).
+    ///
+    /// This method panics if `self.time` is not less or equal to `new_time`.
+    pub fn delayed(&self, new_time: &T) -> Self {
+        ActivateCapability::new(self.capability.delayed(new_time), self.activator.clone())
+    }
+
+    /// Downgrades the capability to one corresponding to `new_time`.
+    ///
+    /// This method panics if `self.time` is not less or equal to `new_time`.
+    pub fn downgrade(&mut self, new_time: &T) {
+        self.capability.downgrade(new_time);
+        self.activator.activate();
+    }
+
+    /// The underlying capability, which does not activate the operator when changed.
+    pub fn capability(&self) -> &Capability<T> {
+        &self.capability
+    }
+}
+
+impl<T: Timestamp> Drop for ActivateCapability<T> {
+    fn drop(&mut self) {
+        self.activator.activate();
+    }
+}
+
+impl<T: Timestamp> Clone for ActivateCapability<T> {
+    fn clone(&self) -> Self {
+        ActivateCapability::new(self.capability.clone(), self.activator.clone())
+    }
+}
+
+impl<T: Timestamp> Deref for ActivateCapability<T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        self.capability.time()
+    }
+}
+
+impl<T: Timestamp> Debug for ActivateCapability<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ActivateCapability {{ time: {:?}, internal: ... }}", self.capability.time())
+    }
+}
+
 /// A set of capabilities, for possibly incomparable times.
 pub struct CapabilitySet<T: Timestamp> {
     elements: Vec<Capability<T>>,
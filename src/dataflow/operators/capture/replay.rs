@@ -62,6 +62,9 @@ where I : IntoIterator,
         let mut builder = OperatorBuilder::new("Replay".to_owned(), scope.clone());
         let (targets, stream) = builder.new_output();
 
+        // The event streams are polled, and so the operator must reschedule itself.
+        let activator = scope.activator_for(builder.address());
+
         let mut output = PushBuffer::new(PushCounter::new(targets));
         let mut event_streams = self.into_iter().collect::<Vec<_>>();
         let mut started = false;
@@ -94,6 +97,8 @@ where I : IntoIterator,
                 output.cease();
                 output.inner().produced().borrow_mut().drain_into(&mut produced[0]);
 
+                activator.activate();
+
                 false
             }
         );
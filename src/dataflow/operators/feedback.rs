@@ -159,4 +159,6 @@ impl<T:Timestamp> Operate<T> for Operator<T> {
     }
 
     fn notify_me(&self) -> bool { false }
+
+    fn feedback(&self) -> bool { true }
 }
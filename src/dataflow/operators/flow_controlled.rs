@@ -1,5 +1,7 @@
 //! Methods to construct flow-controlled sources.
 
+use std::time::Duration;
+
 use ::Data;
 use order::{PartialOrder, TotalOrder};
 use dataflow::operators::generic::operator::source;
@@ -86,9 +88,13 @@ pub fn iterator_source<
         ) -> Stream<G, D> where G::Timestamp: TotalOrder {
 
     let mut target = Default::default();
-    source(scope, name, |cap| {
+    source(scope, name, |cap, info| {
+        let activator = scope.activator_for(&info.address[..]);
         let mut cap = Some(cap);
+        // delay before the next poll, which grows while no input can be ingested.
+        let mut backoff = Duration::new(0, 0);
         move |output| {
+            let mut progress = false;
             cap = cap.take().and_then(|mut cap| {
                 loop {
                     if !probe.less_than(&target) {
@@ -105,6 +111,7 @@ pub fn iterator_source<
                                 session.give_iterator(ds.into_iter());
                                 has_data = true;
                             }
+                            progress = progress || has_data;
 
                             cap = if cap.time().less_than(&lower_bound) { cap.delayed(&lower_bound) } else { cap };
                             if !has_data {
@@ -118,6 +125,17 @@ pub fn iterator_source<
                     }
                 }
             });
+            // poll again immediately after ingesting input, and otherwise back off exponentially,
+            // to at most a millisecond, rather than polling the probe and `input_f` continually.
+            if cap.is_some() {
+                backoff = if progress {
+                    Duration::new(0, 0)
+                }
+                else {
+                    ::std::cmp::min(::std::cmp::max(backoff * 2, Duration::new(0, 1_000)), Duration::from_millis(1))
+                };
+                activator.activate_after(backoff);
+            }
         }
     })
 }
@@ -15,6 +15,7 @@ use progress::{Timestamp, Operate, Antichain};
 use dataflow::{Stream, Scope};
 use dataflow::channels::pushers::Tee;
 use dataflow::channels::pact::ParallelizationContract;
+use scheduling::Activator;
 
 /// Contains type-free information about the operator properties.
 pub struct OperatorShape {
@@ -53,6 +54,7 @@ pub struct OperatorBuilder<G: Scope> {
     scope: G,
     index: usize,
     global: usize,
+    address: Vec<usize>,    // path to the operator from the worker.
     shape: OperatorShape,
     summary: Vec<Vec<Antichain<<G::Timestamp as Timestamp>::Summary>>>,
 }
@@ -64,12 +66,15 @@ impl<G: Scope> OperatorBuilder<G> {
 
         let global = scope.new_identifier();
         let index = scope.allocate_operator_index();
+        let mut address = scope.addr();
+        address.push(index);
         let peers = scope.peers();
 
         OperatorBuilder {
             scope,
             index,
             global,
+            address,
             shape: OperatorShape::new(name, peers),
             summary: vec![],
         }
@@ -85,6 +90,17 @@ impl<G: Scope> OperatorBuilder<G> {
         self.global
     }
 
+    /// The operator's path from the worker, used to activate it.
+    pub fn address(&self) -> &[usize] {
+        &self.address[..]
+    }
+
+    /// An activator for the operator, through which it can request to be scheduled, now or at a
+    /// later time.
+    pub fn activator(&self) -> Activator {
+        self.scope.activator_for(&self.address[..])
+    }
+
     /// Return a reference to the operator's shape
     pub fn shape(&self) -> &OperatorShape {
         &self.shape
@@ -111,6 +127,7 @@ impl<G: Scope> OperatorBuilder<G> {
         let channel_id = self.scope.new_identifier();
         let logging = self.scope.logging();
         let (sender, receiver) = pact.connect(&mut self.scope, channel_id, logging);
+        self.scope.bind_channel(channel_id, &self.address[..]);
         let target = Target { index: self.index, port: self.shape.inputs };
         stream.connect_to(target, sender, channel_id);
 
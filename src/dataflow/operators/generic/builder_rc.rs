@@ -19,6 +19,7 @@ use dataflow::operators::capability::Capability;
 use dataflow::operators::capability::mint as mint_capability;
 
 use dataflow::operators::generic::handles::{InputHandle, new_input_handle, OutputWrapper};
+use scheduling::Activator;
 
 use logging::TimelyLogger as Logger;
 
@@ -167,6 +168,47 @@ impl<G: Scope> OperatorBuilder<G> {
     pub fn global(&self) -> usize {
         self.builder.global()
     }
+
+    /// The operator's path from the worker, used to activate it.
+    pub fn address(&self) -> &[usize] {
+        self.builder.address()
+    }
+
+    /// An activator for the operator, through which it can request to be scheduled, now or at a
+    /// later time.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use timely::dataflow::operators::Inspect;
+    /// use timely::dataflow::operators::generic::builder_rc::OperatorBuilder;
+    ///
+    /// timely::example(|scope| {
+    ///
+    ///     let mut builder = OperatorBuilder::new("Ticker".to_owned(), scope.clone());
+    ///     let (mut output, stream) = builder.new_output();
+    ///     let activator = builder.activator();
+    ///
+    ///     builder.build(move |mut capabilities| {
+    ///         let mut cap = capabilities.pop();
+    ///         let mut ticks = 0;
+    ///         move |_frontiers| {
+    ///             if let Some(cap) = cap.as_mut() {
+    ///                 output.activate().session(&cap).give(ticks);
+    ///                 ticks += 1;
+    ///                 // tick again in a millisecond, rather than polling.
+    ///                 activator.activate_after(Duration::from_millis(1));
+    ///             }
+    ///             if ticks > 5 { cap = None; }
+    ///         }
+    ///     });
+    ///
+    ///     stream.inspect(|x| println!("tick: {:?}", x));
+    /// });
+    /// ```
+    pub fn activator(&self) -> Activator {
+        self.builder.activator()
+    }
 }
 
 
@@ -343,6 +343,7 @@ impl<G: Scope, D1: Data> Operator<G, D1> for Stream<G, D1> {
         let mut builder = OperatorBuilder::new(name.to_owned(), self.scope());
         let index = builder.index();
         let global = builder.global();
+        let address = builder.address().to_vec();
 
         let mut input = builder.new_input(self, pact);
         let (mut output, stream) = builder.new_output();
@@ -350,7 +351,7 @@ impl<G: Scope, D1: Data> Operator<G, D1> for Stream<G, D1> {
         builder.build(move |mut capabilities| {
             // `capabilities` should be a single-element vector.
             let capability = capabilities.pop().unwrap();
-            let operator_info = OperatorInfo::new(index, global);
+            let operator_info = OperatorInfo::new(index, global, &address[..]);
             let mut logic = constructor(capability, operator_info);
             move |frontiers| {
                 let mut input_handle = FrontieredInputHandle::new(&mut input, &frontiers[0]);
@@ -395,6 +396,7 @@ impl<G: Scope, D1: Data> Operator<G, D1> for Stream<G, D1> {
         let mut builder = OperatorBuilder::new(name.to_owned(), self.scope());
         let index = builder.index();
         let global = builder.global();
+        let address = builder.address().to_vec();
 
         let mut input = builder.new_input(self, pact);
         let (mut output, stream) = builder.new_output();
@@ -403,7 +405,7 @@ impl<G: Scope, D1: Data> Operator<G, D1> for Stream<G, D1> {
         builder.build(move |mut capabilities| {
             // `capabilities` should be a single-element vector.
             let capability = capabilities.pop().unwrap();
-            let operator_info = OperatorInfo::new(index, global);
+            let operator_info = OperatorInfo::new(index, global, &address[..]);
             let mut logic = constructor(capability, operator_info);
             move |_frontiers| {
                 let mut output_handle = output.activate();
@@ -428,6 +430,7 @@ impl<G: Scope, D1: Data> Operator<G, D1> for Stream<G, D1> {
         let mut builder = OperatorBuilder::new(name.to_owned(), self.scope());
         let index = builder.index();
         let global = builder.global();
+        let address = builder.address().to_vec();
 
         let mut input1 = builder.new_input(self, pact1);
         let mut input2 = builder.new_input(other, pact2);
@@ -436,7 +439,7 @@ impl<G: Scope, D1: Data> Operator<G, D1> for Stream<G, D1> {
         builder.build(move |mut capabilities| {
             // `capabilities` should be a single-element vector.
             let capability = capabilities.pop().unwrap();
-            let operator_info = OperatorInfo::new(index, global);
+            let operator_info = OperatorInfo::new(index, global, &address[..]);
             let mut logic = constructor(capability, operator_info);
             move |frontiers| {
                 let mut input1_handle = FrontieredInputHandle::new(&mut input1, &frontiers[0]);
@@ -490,6 +493,7 @@ impl<G: Scope, D1: Data> Operator<G, D1> for Stream<G, D1> {
         let mut builder = OperatorBuilder::new(name.to_owned(), self.scope());
         let index = builder.index();
         let global = builder.global();
+        let address = builder.address().to_vec();
 
         let mut input1 = builder.new_input(self, pact1);
         let mut input2 = builder.new_input(other, pact2);
@@ -499,7 +503,7 @@ impl<G: Scope, D1: Data> Operator<G, D1> for Stream<G, D1> {
         builder.build(move |mut capabilities| {
             // `capabilities` should be a single-element vector.
             let capability = capabilities.pop().unwrap();
-            let operator_info = OperatorInfo::new(index, global);
+            let operator_info = OperatorInfo::new(index, global, &address[..]);
             let mut logic = constructor(capability, operator_info);
             move |_frontiers| {
                 let mut output_handle = output.activate();
@@ -529,9 +533,11 @@ impl<G: Scope, D1: Data> Operator<G, D1> for Stream<G, D1> {
 
 /// Creates a new data stream source for a scope.
 ///
-/// The source is defined by a name, and a constructor which takes a default capability to
-/// a method that can be repeatedly called on a output handle. The method is then repeatedly
-/// invoked, and is expected to eventually send data and downgrade and release capabilities.
+/// The source is defined by a name, and a constructor which takes a default capability and
+/// information about the operator to a method that can be repeatedly called on a output handle.
+/// The method is invoked each time the operator is activated, and is expected to eventually send
+/// data and downgrade and release capabilities. As the source has no inputs, it must activate
+/// itself, using an `Activator` for `info.address`, for as long as it has more work to do.
 ///
 /// # Examples
 /// ```
@@ -541,7 +547,11 @@ impl<G: Scope, D1: Data> Operator<G, D1> for Stream<G, D1> {
 ///
 /// timely::example(|scope| {
 ///
-///     source(scope, "Source", |capability| {
+///     source(scope, "Source", |capability, info| {
+///
+///         // Acquire an activator, so that the operator can reschedule itself.
+///         let activator = scope.activator_for(&info.address[..]);
+///
 ///         let mut cap = Some(capability);
 ///         move |output| {
 ///
@@ -559,6 +569,7 @@ impl<G: Scope, D1: Data> Operator<G, D1> for Stream<G, D1> {
 ///             }
 ///
 ///             if done { cap = None; }
+///             else    { activator.activate(); }
 ///         }
 ///     })
 ///     .inspect(|x| println!("number: {:?}", x));
@@ -567,10 +578,11 @@ impl<G: Scope, D1: Data> Operator<G, D1> for Stream<G, D1> {
 pub fn source<G: Scope, D, B, L>(scope: &G, name: &str, constructor: B) -> Stream<G, D>
 where
     D: Data,
-    B: FnOnce(Capability<G::Timestamp>) -> L,
+    B: FnOnce(Capability<G::Timestamp>, OperatorInfo) -> L,
     L: FnMut(&mut OutputHandle<G::Timestamp, D, Tee<G::Timestamp, D>>)+'static {
 
     let mut builder = OperatorBuilder::new(name.to_owned(), scope.clone());
+    let operator_info = OperatorInfo::new(builder.index(), builder.global(), builder.address());
 
     let (mut output, stream) = builder.new_output();
     builder.set_notify(false);
@@ -578,7 +590,7 @@ where
     builder.build(move |mut capabilities| {
         // `capabilities` should be a single-element vector.
         let capability = capabilities.pop().unwrap();
-        let mut logic = constructor(capability);
+        let mut logic = constructor(capability, operator_info);
         move |_frontier| {
             logic(&mut output.activate());
         }
@@ -608,7 +620,7 @@ where
 /// });
 /// ```
 pub fn empty<G: Scope, D: Data>(scope: &G) -> Stream<G, D> {
-    source(scope, "Empty", |_capability| |_output| {
+    source(scope, "Empty", |_capability, _info| |_output| {
         // drop capability, do nothing
     })
 }
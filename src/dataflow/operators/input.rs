@@ -14,6 +14,7 @@ use communication::{Allocate, Push};
 use dataflow::{Stream, Scope, scopes::Child};
 use dataflow::channels::{Message, pushers::{Tee, Counter}};
 use worker::Worker;
+use scheduling::Activator;
 
 // TODO : This is an exogenous input, but it would be nice to wrap a Subgraph in something
 // TODO : more like a harness, with direct access to its inputs.
@@ -107,15 +108,19 @@ impl<'a, A: Allocate, T: Timestamp> Input<'a, A, T> for Child<'a, Worker<A>, T>
 
         let progress = Rc::new(RefCell::new(ChangeBatch::new()));
 
-        handle.register(counter, progress.clone());
+        let index = self.allocate_operator_index();
+        let mut address = self.addr();
+        address.push(index);
+
+        handle.register(counter, progress.clone(), self.activator_for(&address[..]));
 
         let copies = self.peers();
 
-        let index = self.add_operator(Box::new(Operator {
+        self.add_operator_with_index(Box::new(Operator {
             progress,
             messages: produced,
             copies,
-        }));
+        }), index);
 
         Stream::new(Source { index, port: 0 }, registrar, self.clone())
     }
@@ -159,6 +164,7 @@ pub struct Handle<T: Timestamp, D: Data> {
     buffer1: Vec<D>,
     buffer2: Vec<D>,
     now_at: Product<RootTimestamp, T>,
+    activators: Vec<Activator>,
 }
 
 impl<T:Timestamp, D: Data> Handle<T, D> {
@@ -196,6 +202,7 @@ impl<T:Timestamp, D: Data> Handle<T, D> {
             buffer1: Vec::with_capacity(Message::<T, D>::default_length()),
             buffer2: Vec::with_capacity(Message::<T, D>::default_length()),
             now_at: Default::default(),
+            activators: Vec::new(),
         }
     }
 
@@ -233,7 +240,8 @@ impl<T:Timestamp, D: Data> Handle<T, D> {
     fn register(
         &mut self,
         pusher: Counter<Product<RootTimestamp, T>, D, Tee<Product<RootTimestamp, T>, D>>,
-        progress: Rc<RefCell<ChangeBatch<Product<RootTimestamp, T>>>>
+        progress: Rc<RefCell<ChangeBatch<Product<RootTimestamp, T>>>>,
+        activator: Activator,
     ) {
         // flush current contents, so new registrant does not see existing data.
         if !self.buffer1.is_empty() { self.flush(); }
@@ -245,11 +253,20 @@ impl<T:Timestamp, D: Data> Handle<T, D> {
 
         self.progress.push(progress);
         self.pushers.push(pusher);
+        self.activators.push(activator);
+    }
+
+    // activates the input operators, so that they report the data sent and times closed.
+    fn activate(&self) {
+        for activator in self.activators.iter() {
+            activator.activate();
+        }
     }
 
     // flushes our buffer at each of the destinations. there can be more than one; clone if needed.
     #[inline(never)]
     fn flush(&mut self) {
+        self.activate();
         for index in 0 .. self.pushers.len() {
             if index < self.pushers.len() - 1 {
                 self.buffer2.extend_from_slice(&self.buffer1[..]);
@@ -266,6 +283,7 @@ impl<T:Timestamp, D: Data> Handle<T, D> {
 
     // closes the current epoch, flushing if needed, shutting if needed, and updating the frontier.
     fn close_epoch(&mut self) {
+        self.activate();
         if !self.buffer1.is_empty() { self.flush(); }
         for pusher in self.pushers.iter_mut() {
             pusher.done();
@@ -291,6 +309,7 @@ impl<T:Timestamp, D: Data> Handle<T, D> {
     pub fn send_batch(&mut self, buffer: &mut Vec<D>) {
 
         if !buffer.is_empty() {
+            self.activate();
             // flush buffered elements to ensure local fifo.
             if !self.buffer1.is_empty() { self.flush(); }
 
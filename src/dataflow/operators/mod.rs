@@ -58,4 +58,4 @@ pub mod count;
 
 // keep "mint" module-private
 mod capability;
-pub use self::capability::{Capability, CapabilityRef, CapabilitySet};
+pub use self::capability::{ActivateCapability, Capability, CapabilityRef, CapabilitySet};
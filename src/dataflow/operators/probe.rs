@@ -96,6 +96,7 @@ impl<G: Scope, D: Data> Probe<G, D> for Stream<G, D> {
         let (tee, stream) = builder.new_output();
         let mut output = PushBuffer::new(PushCounter::new(tee));
 
+        handle.addresses.borrow_mut().push(builder.address().to_vec());
         let frontier = handle.frontier.clone();
         let mut started = false;
 
@@ -138,7 +139,8 @@ impl<G: Scope, D: Data> Probe<G, D> for Stream<G, D> {
 
 /// Reports information about progress at the probe.
 pub struct Handle<T:Timestamp> {
-    frontier: Rc<RefCell<MutableAntichain<T>>>
+    frontier: Rc<RefCell<MutableAntichain<T>>>,
+    addresses: Rc<RefCell<Vec<Vec<usize>>>>,
 }
 
 impl<T: Timestamp> Handle<T> {
@@ -149,7 +151,15 @@ impl<T: Timestamp> Handle<T> {
     /// returns true iff the frontier is empty.
     #[inline] pub fn done(&self) -> bool { self.frontier.borrow().is_empty() }
     /// Allocates a new handle.
-    #[inline] pub fn new() -> Self { Handle { frontier: Rc::new(RefCell::new(MutableAntichain::new())) } }
+    #[inline] pub fn new() -> Self {
+        Handle {
+            frontier: Rc::new(RefCell::new(MutableAntichain::new())),
+            addresses: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    /// The paths from the worker of the probe operators reporting to this handle.
+    pub fn addresses(&self) -> Vec<Vec<usize>> { self.addresses.borrow().clone() }
 
     /// Invokes a method on the frontier, returning its result.
     ///
@@ -173,7 +183,8 @@ impl<T: Timestamp> Handle<T> {
 impl<T: Timestamp> Clone for Handle<T> {
     fn clone(&self) -> Self {
         Handle {
-            frontier: self.frontier.clone()
+            frontier: self.frontier.clone(),
+            addresses: self.addresses.clone(),
         }
     }
 }
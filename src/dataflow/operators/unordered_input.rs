@@ -13,24 +13,25 @@ use Data;
 use dataflow::channels::pushers::{Tee, Counter as PushCounter};
 use dataflow::channels::pushers::buffer::{Buffer as PushBuffer, AutoflushSession};
 
-use dataflow::operators::Capability;
+use dataflow::operators::ActivateCapability;
 use dataflow::operators::capability::mint as mint_capability;
 
 use dataflow::{Stream, Scope};
+use scheduling::Activator;
 
 /// Create a new `Stream` and `Handle` through which to supply input.
 pub trait UnorderedInput<G: Scope> {
     /// Create a new capability-based `Stream` and `Handle` through which to supply input. This
     /// input supports multiple open epochs (timestamps) at the same time.
     ///
-    /// The `new_unordered_input` method returns `((Handle, Capability), Stream)` where the `Stream` can be used
-    /// immediately for timely dataflow construction, `Handle` and `Capability` are later used to introduce
+    /// The `new_unordered_input` method returns `((Handle, ActivateCapability), Stream)` where the `Stream` can be used
+    /// immediately for timely dataflow construction, `Handle` and `ActivateCapability` are later used to introduce
     /// data into the timely dataflow computation.
     ///
-    /// The `Capability` returned is for the default value of the timestamp type in use. The
+    /// The `ActivateCapability` returned is for the default value of the timestamp type in use. The
     /// capability can be dropped to inform the system that the input has advanced beyond the
     /// capability's timestamp. To retain the ability to send, a new capability at a later timestamp
-    /// should be obtained first, via the `delayed` function for `Capability`.
+    /// should be obtained first, via the `delayed` function for `ActivateCapability`.
     ///
     /// To communicate the end-of-input drop all available capabilities.
     ///
@@ -74,12 +75,12 @@ pub trait UnorderedInput<G: Scope> {
     ///     assert_eq!(extract[i], (RootTimestamp::new(i), vec![i]));
     /// }
     /// ```
-    fn new_unordered_input<D:Data>(&mut self) -> ((UnorderedHandle<G::Timestamp, D>, Capability<G::Timestamp>), Stream<G, D>);
+    fn new_unordered_input<D:Data>(&mut self) -> ((UnorderedHandle<G::Timestamp, D>, ActivateCapability<G::Timestamp>), Stream<G, D>);
 }
 
 
 impl<G: Scope> UnorderedInput<G> for G {
-    fn new_unordered_input<D:Data>(&mut self) -> ((UnorderedHandle<G::Timestamp, D>, Capability<G::Timestamp>), Stream<G, D>) {
+    fn new_unordered_input<D:Data>(&mut self) -> ((UnorderedHandle<G::Timestamp, D>, ActivateCapability<G::Timestamp>), Stream<G, D>) {
 
         let (output, registrar) = Tee::<G::Timestamp, D>::new();
         let internal = Rc::new(RefCell::new(ChangeBatch::new()));
@@ -87,14 +88,20 @@ impl<G: Scope> UnorderedInput<G> for G {
         let cap = mint_capability(Default::default(), internal.clone());
         let counter = PushCounter::new(output);
         let produced = counter.produced().clone();
-        let helper = UnorderedHandle::new(counter);
+
+        let index = self.allocate_operator_index();
+        let mut address = self.addr();
+        address.push(index);
+
+        let cap = ActivateCapability::new(cap, self.activator_for(&address[..]));
+        let helper = UnorderedHandle::new(counter, self.activator_for(&address[..]));
         let peers = self.peers();
 
-        let index = self.add_operator(Box::new(UnorderedOperator {
+        self.add_operator_with_index(Box::new(UnorderedOperator {
             internal,
             produced,
             peers,
-        }));
+        }), index);
 
         ((helper, cap), Stream::new(Source { index, port: 0 }, registrar, self.clone()))
     }
@@ -139,18 +146,22 @@ impl<T:Timestamp> Operate<T> for UnorderedOperator<T> {
 /// A handle to an input `Stream`, used to introduce data to a timely dataflow computation.
 pub struct UnorderedHandle<T: Timestamp, D: Data> {
     buffer: PushBuffer<T, D, PushCounter<T, D, Tee<T, D>>>,
+    activator: Activator,
 }
 
 impl<T: Timestamp, D: Data> UnorderedHandle<T, D> {
-    fn new(pusher: PushCounter<T, D, Tee<T, D>>) -> UnorderedHandle<T, D> {
+    fn new(pusher: PushCounter<T, D, Tee<T, D>>, activator: Activator) -> UnorderedHandle<T, D> {
         UnorderedHandle {
             buffer: PushBuffer::new(pusher),
+            activator,
         }
     }
 
     /// Allocates a new automatically flushing session based on the supplied capability.
-    pub fn session<'b>(&'b mut self, cap: Capability<T>) -> AutoflushSession<'b, T, D, PushCounter<T, D, Tee<T, D>>> {
-        self.buffer.autoflush_session(cap)
+    pub fn session<'b>(&'b mut self, cap: ActivateCapability<T>) -> AutoflushSession<'b, T, D, PushCounter<T, D, Tee<T, D>>> {
+        // the operator must report the data and capability changes of the session.
+        self.activator.activate();
+        self.buffer.autoflush_session(cap.capability().clone())
     }
 }
 
@@ -1,13 +1,18 @@
 //! A child dataflow scope, used to build nested dataflow scopes.
 
+use std::rc::Rc;
 use std::cell::RefCell;
+use std::ops::Range;
+use std::time::Duration;
 
 use progress::{Timestamp, Operate, SubgraphBuilder};
 use progress::nested::{Source, Target};
 use progress::nested::product::Product;
+use progress::broadcast::{ProgressMode, ProgressBatching};
 use communication::{Allocate, Data, Push, Pull};
 use logging::TimelyLogger as Logger;
 use worker::AsWorker;
+use scheduling::Activations;
 
 use super::{ScopeParent, Scope};
 
@@ -38,6 +43,11 @@ impl<'a, G: ScopeParent, T: Timestamp> AsWorker for Child<'a, G, T> {
     fn log_register(&self) -> ::std::cell::RefMut<::logging_core::Registry<::logging::WorkerIdentifier>> {
         self.parent.log_register()
     }
+    fn activations(&self) -> Rc<RefCell<Activations>> { self.parent.activations() }
+    fn bind_channel(&mut self, identifier: usize, path: &[usize]) { self.parent.bind_channel(identifier, path) }
+    fn progress_mode(&self) -> ProgressMode { self.parent.progress_mode() }
+    fn progress_batching(&self) -> ProgressBatching { self.parent.progress_batching() }
+    fn progress_checking(&self) -> bool { self.parent.progress_checking() }
 }
 
 impl<'a, G: ScopeParent, T: Timestamp> ScopeParent for Child<'a, G, T> {
@@ -73,9 +83,11 @@ impl<'a, G: ScopeParent, T: Timestamp> Scope for Child<'a, G, T> {
             };
             func(&mut builder)
         };
-        let subscope = subscope.into_inner().build(self);
-
-        self.add_operator_with_index(Box::new(subscope), index);
+        // An invalid nested scope is reported when this scope is built.
+        match subscope.into_inner().try_build(self) {
+            Ok(subscope) => self.add_operator_with_index(Box::new(subscope), index),
+            Err(error) => self.subgraph.borrow_mut().add_error(error),
+        }
 
         result
     }
@@ -86,9 +98,14 @@ use communication::Message;
 impl<'a, G: ScopeParent, T: Timestamp> Allocate for Child<'a, G, T> {
     fn index(&self) -> usize { self.parent.index() }
     fn peers(&self) -> usize { self.parent.peers() }
+    fn processes(&self) -> Vec<Range<usize>> { self.parent.processes() }
     fn allocate<D: Data>(&mut self, identifier: usize) -> (Vec<Box<Push<Message<D>>>>, Box<Pull<Message<D>>>) {
         self.parent.allocate(identifier)
     }
+    fn backpressured(&self) -> bool { self.parent.backpressured() }
+    fn release(&mut self, identifier: usize) { self.parent.release(identifier) }
+    fn drain_events(&mut self, channels: &mut Vec<usize>) { self.parent.drain_events(channels) }
+    fn await_events(&self, duration: Option<Duration>) { self.parent.await_events(duration) }
 }
 
 impl<'a, G: ScopeParent, T: Timestamp> Clone for Child<'a, G, T> {
@@ -5,6 +5,7 @@ use progress::nested::{Source, Target};
 // use logging::TimelyLogger as Logger;
 use communication::Allocate;
 use worker::AsWorker;
+use scheduling::Activator;
 
 // pub mod root;
 pub mod child;
@@ -35,6 +36,14 @@ pub trait Scope: ScopeParent {
     /// A sequence of scope identifiers describing the path from the `Root` to this scope.
     fn addr(&self) -> Vec<usize>;
 
+    /// Constructs an `Activator` for the operator at `path`, for example `OperatorInfo::address`.
+    ///
+    /// Operators are scheduled when activated, in addition to when data arrive at their inputs
+    /// or their input frontiers change.
+    fn activator_for(&self, path: &[usize]) -> Activator {
+        Activator::new(path, self.activations())
+    }
+
     /// Connects a source of data with a target of the data. This only links the two for
     /// the purposes of tracking progress, rather than effect any data movement itself.
     fn add_edge(&self, source: Source, target: Target);
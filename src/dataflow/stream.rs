@@ -9,6 +9,7 @@ use progress::nested::subgraph::{Source, Target};
 use communication::Push;
 use dataflow::Scope;
 use dataflow::channels::pushers::tee::TeeHelper;
+use dataflow::channels::pushers::Activate;
 use dataflow::channels::Bundle;
 
 // use dataflow::scopes::root::loggers::CHANNELS_Q;
@@ -32,6 +33,8 @@ impl<S: Scope, D> Stream<S, D> {
     ///
     /// The destination is described both by a `Target`, for progress tracking information, and a `P: Push` where the
     /// records should actually be sent. The identifier is unique to the edge and is used only for logging purposes.
+    ///
+    /// Records pushed to the destination activate its operator, so that it is scheduled to receive them.
     pub fn connect_to<P: Push<Bundle<S::Timestamp, D>>+'static>(&self, target: Target, pusher: P, identifier: usize) {
 
         let mut logging = self.scope().logging();
@@ -42,8 +45,12 @@ impl<S: Scope, D> Stream<S, D> {
             target: (target.index, target.port),
         }));
 
+        let mut path = self.scope.addr();
+        path.push(target.index);
+        let activator = self.scope.activator_for(&path[..]);
+
         self.scope.add_edge(self.name, target);
-        self.ports.add_pusher(pusher);
+        self.ports.add_pusher(Activate::new(pusher, activator));
     }
     /// Allocates a `Stream` from a supplied `Source` name and rendezvous point.
     pub fn new(source: Source, output: TeeHelper<S::Timestamp, D>, scope: S) -> Self {
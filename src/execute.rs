@@ -1,6 +1,6 @@
 //! Starts a timely dataflow execution from configuration information and per-worker logic.
 
-use communication::{initialize, initialize_from, Configuration, Allocator, allocator::AllocateBuilder, WorkerGuards};
+use communication::{initialize, initialize_from, Configuration, Config, Allocator, allocator::AllocateBuilder, WorkerGuards};
 use dataflow::scopes::Child;
 use worker::Worker;
 // use logging::{LoggerConfig, TimelyLogger};
@@ -69,7 +69,7 @@ where T: Send+'static,
 
 /// Executes a timely dataflow from a configuration and per-communicator logic.
 ///
-/// The `execute` method takes a `Configuration` and spins up some number of
+/// The `execute` method takes a `Configuration` (or a `Config`) and spins up some number of
 /// workers threads, each of which execute the supplied closure to construct
 /// and run a timely dataflow computation.
 ///
@@ -116,38 +116,37 @@ where T: Send+'static,
 /// // the extracted data should have data (0..10) thrice at timestamp 0.
 /// assert_eq!(recv.extract()[0].1, (0..30).map(|x| x / 3).collect::<Vec<_>>());
 /// ```
-pub fn execute<T, F>(mut config: Configuration, func: F) -> Result<WorkerGuards<T>,String>
+pub fn execute<C, T, F>(config: C, func: F) -> Result<WorkerGuards<T>,String>
 where
+    C: Into<Config>,
     T:Send+'static,
     F: Fn(&mut Worker<Allocator>)->T+Send+Sync+'static {
 
-    if let Configuration::Cluster(_,_,_,_, ref mut log_fn) = config {
+    let mut config = config.into();
 
-        *log_fn = Box::new(|events_setup| {
+    // If an environment variable is set, use it as the default communication logging.
+    if let Ok(addr) = ::std::env::var("TIMELY_COMM_LOG_ADDR") {
 
-            let mut result = None;
-            if let Ok(addr) = ::std::env::var("TIMELY_COMM_LOG_ADDR") {
+        config = config.logging(move |events_setup| {
 
-                use ::std::net::TcpStream;
-                use ::logging::BatchLogger;
-                use ::dataflow::operators::capture::EventWriter;
+            use ::std::net::TcpStream;
+            use ::logging::BatchLogger;
+            use ::dataflow::operators::capture::EventWriter;
 
-                eprintln!("enabled COMM logging to {}", addr);
+            eprintln!("enabled COMM logging to {}", addr);
 
-                if let Ok(stream) = TcpStream::connect(&addr) {
-                    let writer = EventWriter::new(stream);
-                    let mut logger = BatchLogger::new(writer);
-                    result = Some(::logging_core::Logger::new(
-                        ::std::time::Instant::now(),
-                        events_setup,
-                        move |time, data| logger.publish_batch(time, data)
-                    ));
-                }
-                else {
-                    panic!("Could not connect to communication log address: {:?}", addr);
-                }
+            if let Ok(stream) = TcpStream::connect(&addr) {
+                let writer = EventWriter::new(stream);
+                let mut logger = BatchLogger::new(writer);
+                Some(::logging_core::Logger::new(
+                    ::std::time::Instant::now(),
+                    events_setup,
+                    move |time, data| logger.publish_batch(time, data)
+                ))
+            }
+            else {
+                panic!("Could not connect to communication log address: {:?}", addr);
             }
-            result
         });
     }
 
@@ -202,8 +201,26 @@ where
 /// `-p, --process`: identity of this process; from 0 to n-1.
 ///
 /// `-h, --hostfile`: a text file whose lines are "hostname:port" in order of process identity.
-/// If not specified, `localhost` will be used, with port numbers increasing from 2101 (chosen
-/// arbitrarily).
+/// Lines of the form "unix:path" use a Unix domain socket at `path` instead, for processes on
+/// the same host. Each line may be followed by the number of workers in that process, when it
+/// differs from `-w`. If not specified, `localhost` will be used, with port numbers increasing
+/// from 2101 (chosen arbitrarily).
+///
+/// `--shared-memory`: a directory through which processes on the same host communicate using
+/// shared memory, instead of the addresses of `--hostfile`.
+///
+/// `--config`: a text file whose lines are `key = value`, using the names of long arguments.
+///
+/// Further arguments are described in the [`config`](../../timely_communication/config/index.html)
+/// module of `timely_communication`.
+///
+/// Each argument is also read from the environment variable `TIMELY_` followed by its long name,
+/// upper-cased with dashes replaced by underscores, as in `TIMELY_THREADS` for `--threads`. These
+/// variables apply even when no arguments are supplied, so a program run where they are set
+/// will use them. To ignore them, build the configuration with `Config::new().apply_args(args)`
+/// and pass it to `execute` instead.
+/// Arguments take precedence over a configuration file, which takes precedence over environment
+/// variables.
 ///
 /// # Examples
 ///
@@ -235,8 +252,8 @@ pub fn execute_from_args<I, T, F>(iter: I, func: F) -> Result<WorkerGuards<T>,St
     where I: Iterator<Item=String>,
           T:Send+'static,
           F: Fn(&mut Worker<Allocator>)->T+Send+Sync+'static, {
-    let configuration = try!(Configuration::from_args(iter));
-    execute(configuration, func)
+    let config = try!(Config::from_args(iter));
+    execute(config, func)
 }
 
 /// Executes a timely dataflow from supplied allocators and logging.
@@ -67,7 +67,7 @@ extern crate timely_logging;
 pub use execute::{execute, execute_from_args, example};
 pub use order::PartialOrder;
 
-pub use timely_communication::Configuration;
+pub use timely_communication::{Configuration, Config};
 
 /// Re-export of the `timely_communication` crate.
 pub mod communication {
@@ -89,6 +89,7 @@ pub mod progress;
 pub mod dataflow;
 pub mod synchronization;
 pub mod execute;
+pub mod scheduling;
 pub mod order;
 
 pub mod logging;
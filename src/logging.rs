@@ -6,8 +6,13 @@ pub type WorkerIdentifier = usize;
 pub type Logger<Event> = ::logging_core::Logger<Event, WorkerIdentifier>;
 /// Logger for timely dataflow system events.
 pub type TimelyLogger = Logger<TimelyEvent>;
+/// Logger for the progress updates exchanged by timely dataflow workers.
+pub type TimelyProgressLogger = Logger<TimelyProgressEvent>;
 
+use std::any::Any;
+use std::fmt::Debug;
 use std::time::Duration;
+use ::progress::Timestamp;
 use ::progress::timestamp::RootTimestamp;
 use ::progress::nested::product::Product;
 use dataflow::operators::capture::{Event, EventPusher};
@@ -70,6 +75,8 @@ pub struct ChannelsEvent {
 
 #[derive(Abomonation, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
 /// Send or receive of progress information.
+///
+/// The updates sent or received are logged, with their timestamps, as `TimelyProgressEvent`s.
 pub struct ProgressEvent {
     /// `true` if the event is a send, and `false` if it is a receive.
     pub is_send: bool,
@@ -81,10 +88,92 @@ pub struct ProgressEvent {
     pub seq_no: usize,
     /// Sequence of nested scope identifiers indicating the path from the root to this instance.
     pub addr: Vec<usize>,
-    /// List of message updates, containing Target descriptor, timestamp as string, and delta.
-    pub messages: Vec<(usize, usize, String, i64)>,
-    /// List of capability updates, containing Source descriptor, timestamp as string, and delta.
-    pub internal: Vec<(usize, usize, String, i64)>,
+}
+
+/// A timestamp of a progress update, which may be downcast to the timestamp type of its scope.
+pub trait ProgressEventTimestamp: Debug + Any + Send {
+    /// The timestamp, to be downcast to the timestamp type of its scope.
+    fn as_any(&self) -> &Any;
+}
+
+impl<T: Timestamp> ProgressEventTimestamp for T {
+    fn as_any(&self) -> &Any { self }
+}
+
+/// A list of progress updates, each containing an operator index, a port, a timestamp, and a delta.
+///
+/// The list is a `Vec<(usize, usize, T, i64)>` for the timestamp type `T` of the scope, to which
+/// it, or each of its timestamps, may be downcast.
+pub trait ProgressEventTimestampVec: Debug + Any + Send {
+    /// Iterates over the updates, with timestamps to be downcast to the timestamp type of the scope.
+    fn iter<'a>(&'a self) -> Box<Iterator<Item=(&'a usize, &'a usize, &'a ProgressEventTimestamp, &'a i64)>+'a>;
+    /// The list, to be downcast to a `Vec<(usize, usize, T, i64)>`.
+    fn as_any(&self) -> &Any;
+    /// Clones the list into a new box.
+    fn box_clone(&self) -> Box<ProgressEventTimestampVec>;
+}
+
+impl<T: Timestamp> ProgressEventTimestampVec for Vec<(usize, usize, T, i64)> {
+    fn iter<'a>(&'a self) -> Box<Iterator<Item=(&'a usize, &'a usize, &'a ProgressEventTimestamp, &'a i64)>+'a> {
+        Box::new(<[(usize, usize, T, i64)]>::iter(&self[..]).map(|&(ref index, ref port, ref time, ref delta)| {
+            (index, port, time as &ProgressEventTimestamp, delta)
+        }))
+    }
+    fn as_any(&self) -> &Any { self }
+    fn box_clone(&self) -> Box<ProgressEventTimestampVec> { Box::new(self.clone()) }
+}
+
+impl Clone for Box<ProgressEventTimestampVec> {
+    fn clone(&self) -> Self { self.box_clone() }
+}
+
+#[derive(Debug, Clone)]
+/// Send or receive of progress information, with the updates it carries.
+///
+/// These events are logged to the "timely/progress" logger, if one is registered, by scopes of
+/// all timestamp types. Their updates retain the timestamps of their scope, which analyses can
+/// recover by downcasting.
+///
+/// # Examples
+/// ```
+/// use timely::logging::TimelyProgressEvent;
+/// use timely::progress::timestamp::RootTimestamp;
+/// use timely::progress::nested::product::Product;
+/// use timely::dataflow::operators::{ToStream, Exchange, Probe};
+///
+/// timely::execute_from_args(::std::env::args(), |worker| {
+///     worker.log_register().insert::<TimelyProgressEvent,_>("timely/progress", |_time, data| {
+///         for &(_, _, ref event) in data.iter() {
+///             for (index, port, time, delta) in event.messages.iter() {
+///                 if let Some(time) = time.as_any().downcast_ref::<Product<RootTimestamp, u64>>() {
+///                     println!("{} messages at {:?} for input {} of operator {}", delta, time.inner, port, index);
+///                 }
+///             }
+///         }
+///     });
+///     let probe = worker.dataflow::<u64,_,_>(|scope| {
+///         (0..10).to_stream(scope).exchange(|x| *x).probe()
+///     });
+///     while !probe.done() {
+///         worker.step();
+///     }
+/// }).unwrap();
+/// ```
+pub struct TimelyProgressEvent {
+    /// `true` if the event is a send, and `false` if it is a receive.
+    pub is_send: bool,
+    /// Source worker index.
+    pub source: usize,
+    /// Communication channel identifier
+    pub channel: usize,
+    /// Message sequence number.
+    pub seq_no: usize,
+    /// Sequence of nested scope identifiers indicating the path from the root to this instance.
+    pub addr: Vec<usize>,
+    /// List of message updates, containing Target descriptor, timestamp, and delta.
+    pub messages: Box<ProgressEventTimestampVec>,
+    /// List of capability updates, containing Source descriptor, timestamp, and delta.
+    pub internal: Box<ProgressEventTimestampVec>,
 }
 
 #[derive(Abomonation, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
@@ -189,6 +278,17 @@ pub struct InputEvent {
     pub start_stop: StartStop,
 }
 
+#[derive(Abomonation, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+/// A dataflow whose frontier has not advanced, with none of its operators scheduled, for longer
+/// than the worker's stall timeout.
+pub struct StallEvent {
+    /// The index of the dataflow.
+    pub dataflow: usize,
+    /// The operators holding capabilities, each with its identifier and name as in its
+    /// `OperatesEvent`, and the earliest times at which it holds capabilities.
+    pub operators: Vec<(usize, String, Vec<String>)>,
+}
+
 #[derive(Debug, Clone, Abomonation, Hash, Eq, PartialEq, Ord, PartialOrd)]
 /// An event in a timely worker
 pub enum TimelyEvent {
@@ -216,6 +316,8 @@ pub enum TimelyEvent {
     /* 10 */ Input(InputEvent),
     /// Unstructured event.
     /* 11 */ Text(String),
+    /// Dataflow stall.
+    /* 12 */ Stall(StallEvent),
 }
 
 impl From<OperatesEvent> for TimelyEvent {
@@ -261,3 +363,7 @@ impl From<CommChannelsEvent> for TimelyEvent {
 impl From<InputEvent> for TimelyEvent {
     fn from(v: InputEvent) -> TimelyEvent { TimelyEvent::Input(v) }
 }
+
+impl From<StallEvent> for TimelyEvent {
+    fn from(v: StallEvent) -> TimelyEvent { TimelyEvent::Stall(v) }
+}
@@ -1,8 +1,13 @@
 //! Broadcasts progress information among workers.
 
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
 use progress::{ChangeBatch, Timestamp};
+use scheduling::Activations;
 use communication::{Message, Push, Pull};
-use logging::TimelyLogger as Logger;
+use logging::{TimelyLogger as Logger, TimelyProgressLogger as ProgressLogger};
 
 /// A list of progress updates corresponding to `((child_scope, [in/out]_port, timestamp), delta)`
 pub type ProgressVec<T> = Vec<((usize, usize, T), i64)>;
@@ -10,6 +15,51 @@ pub type ProgressVec<T> = Vec<((usize, usize, T), i64)>;
 /// message and internal updates
 pub type ProgressMsg<T> = Message<(usize, usize, ProgressVec<T>, ProgressVec<T>)>;
 
+/// How workers exchange progress updates.
+///
+/// In either mode, each worker receives the updates of every worker in batches that are
+/// consolidations of whole batches sent by one or more workers, and receives the batches of
+/// each worker in the order they were sent. This is what makes it safe for workers to act on
+/// the progress updates they have received, whatever has not yet arrived.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ProgressMode {
+    /// Each worker broadcasts its updates to all workers.
+    ///
+    /// The number of messages grows quadratically with the number of workers.
+    Flat,
+    /// Each worker sends its updates to the first worker of its process, which consolidates the
+    /// updates of its process and broadcasts them to the first workers of all processes. These
+    /// relay the updates they receive to the other workers of their processes.
+    ///
+    /// The number of messages between processes grows quadratically with the number of processes
+    /// rather than workers, at the cost of an additional hop within each process.
+    Hierarchical,
+}
+
+impl Default for ProgressMode {
+    fn default() -> Self { ProgressMode::Flat }
+}
+
+/// When workers send their progress updates to other workers.
+///
+/// Holding updates back allows them to be accumulated and consolidated into fewer, larger messages,
+/// at the cost of other workers learning of progress later. Updates are always sent as whole batches,
+/// and so holding them back delays, but does not otherwise change, the frontiers workers observe.
+/// Workers need not use the same policy, and a lone worker sends no updates to hold back.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ProgressBatching {
+    /// Updates are sent in the step of the scope in which they are produced.
+    Immediate,
+    /// Updates are held for up to the given number of steps of the scope, and sent in the last.
+    Steps(usize),
+    /// Updates are held until the given time has passed since the first of them was held.
+    Delay(Duration),
+}
+
+impl Default for ProgressBatching {
+    fn default() -> Self { ProgressBatching::Immediate }
+}
+
 /// Manages broadcasting of progress updates to and receiving updates from workers.
 pub struct Progcaster<T:Timestamp> {
     to_push: Option<ProgressMsg<T>>,
@@ -23,31 +73,93 @@ pub struct Progcaster<T:Timestamp> {
     addr: Vec<usize>,
     /// Communication channel identifier
     channel_identifier: usize,
+    /// Channels and roles of workers for `ProgressMode::Hierarchical`, in which `pushers` and
+    /// `puller` gather updates at the first worker of each process.
+    hierarchy: Option<Hierarchy<T>>,
+    /// Updates held back according to `batching`, and when and for how many steps they have been held.
+    batching: ProgressBatching,
+    held: Held<T>,
+    /// Activations through which the scope requests to be stepped again to send held updates.
+    activations: Rc<RefCell<Activations>>,
 
     logging: Option<Logger>,
+    progress_logging: Option<ProgressLogger>,
+}
+
+// Progress updates held back from other workers.
+struct Held<T: Timestamp> {
+    messages: ChangeBatch<(usize, usize, T)>,
+    internal: ChangeBatch<(usize, usize, T)>,
+    steps: usize,               // steps in which the updates have been held.
+    since: Option<Instant>,     // when the first of the updates was held.
+}
+
+// Channels through which the first workers of processes exchange progress updates, and relay them.
+struct Hierarchy<T: Timestamp> {
+    representative: usize,          // the first worker of this worker's process.
+    representatives: Vec<usize>,    // the first workers of all processes.
+    locals: Vec<usize>,             // the other workers of this worker's process.
+    exchange: Channel<T>,           // between the first workers of processes.
+    relay: Channel<T>,              // from the first worker of a process to its other workers.
+    messages: ChangeBatch<(usize, usize, T)>,   // gathered message updates, at a first worker.
+    internal: ChangeBatch<(usize, usize, T)>,   // gathered internal updates, at a first worker.
+}
+
+// The identifier and endpoints of an allocated channel.
+struct Channel<T: Timestamp> {
+    identifier: usize,
+    pushers: Vec<Box<Push<ProgressMsg<T>>>>,
+    puller: Box<Pull<ProgressMsg<T>>>,
 }
 
 impl<T:Timestamp+Send> Progcaster<T> {
     /// Creates a new `Progcaster` using a channel from the supplied worker.
+    ///
+    /// The `Progcaster` exchanges updates as indicated by the worker's `progress_mode`, and holds
+    /// them back as indicated by its `progress_batching`.
     pub fn new<A: ::worker::AsWorker>(worker: &mut A, path: &Vec<usize>, mut logging: Option<Logger>) -> Progcaster<T> {
 
-        let channel_identifier = worker.new_identifier();
-        let (pushers, puller) = worker.allocate(channel_identifier);
-        logging.as_mut().map(|l| l.log(::logging::CommChannelsEvent {
-            identifier: channel_identifier,
-            kind: ::logging::CommChannelKind::Progress,
-        }));
+        let channel = allocate_channel(worker, path, &mut logging);
+
+        let hierarchy = if worker.progress_mode() == ProgressMode::Hierarchical {
+            let index = worker.index();
+            let processes = worker.processes();
+            let process = processes.iter().find(|range| range.start <= index && index < range.end).expect("worker outside of all processes").clone();
+            Some(Hierarchy {
+                representative: process.start,
+                representatives: processes.iter().map(|range| range.start).collect(),
+                locals: process.filter(|&worker| worker != index).collect(),
+                exchange: allocate_channel(worker, path, &mut logging),
+                relay: allocate_channel(worker, path, &mut logging),
+                messages: ChangeBatch::new(),
+                internal: ChangeBatch::new(),
+            })
+        }
+        else {
+            None
+        };
+
         let worker_index = worker.index();
         let addr = path.clone();
         Progcaster {
             to_push: None,
-            pushers,
-            puller,
+            pushers: channel.pushers,
+            puller: channel.puller,
             source: worker_index,
             counter: 0,
             addr,
-            channel_identifier,
+            channel_identifier: channel.identifier,
+            hierarchy,
+            batching: worker.progress_batching(),
+            held: Held {
+                messages: ChangeBatch::new(),
+                internal: ChangeBatch::new(),
+                steps: 0,
+                since: None,
+            },
+            activations: worker.activations(),
             logging,
+            progress_logging: worker.log_register().get("timely/progress"),
         }
     }
 
@@ -64,42 +176,23 @@ impl<T:Timestamp+Send> Progcaster<T> {
             messages.compact();
             internal.compact();
 
-            if !messages.is_empty() || !internal.is_empty() {
+            if self.batching != ProgressBatching::Immediate {
+                self.hold(messages, internal);
+            }
 
-                self.logging.as_ref().map(|l| l.log(::logging::ProgressEvent {
-                    is_send: true,
-                    source: self.source,
-                    channel: self.channel_identifier,
-                    seq_no: self.counter,
-                    addr: self.addr.clone(),
-                    // TODO: fill with additional data
-                    messages: Vec::new(),
-                    internal: Vec::new(),
-                }));
+            if self.hierarchy.is_some() {
+                self.send_and_recv_hierarchical(messages, internal);
+                return;
+            }
 
-                for pusher in self.pushers.iter_mut() {
+            if !messages.is_empty() || !internal.is_empty() {
 
-                    // Attempt to re-use allocations, if possible.
-                    if let Some(tuple) = &mut self.to_push {
-                        let tuple = tuple.as_mut();
-                        tuple.0 = self.source;
-                        tuple.1 = self.counter;
-                        tuple.2.clear(); tuple.2.extend(messages.iter().cloned());
-                        tuple.3.clear(); tuple.3.extend(internal.iter().cloned());
-                    }
-                    // If we don't have an allocation ...
-                    if self.to_push.is_none() {
-                        self.to_push = Some(Message::from_typed((
-                            self.source,
-                            self.counter,
-                            messages.clone().into_inner(),
-                            internal.clone().into_inner(),
-                        )));
-                    }
+                log_progress(&self.logging, &self.progress_logging, &self.addr, true, self.source, self.counter, self.channel_identifier, messages.iter().as_slice(), internal.iter().as_slice());
 
+                for pusher in self.pushers.iter_mut() {
                     // TODO: This should probably use a broadcast channel, or somehow serialize only once.
                     //       It really shouldn't be doing all of this cloning, that's for sure.
-                    pusher.push(&mut self.to_push);
+                    push_progress(pusher, &mut self.to_push, self.source, self.counter, messages.iter().as_slice(), internal.iter().as_slice());
                 }
 
                 self.counter += 1;
@@ -109,37 +202,210 @@ impl<T:Timestamp+Send> Progcaster<T> {
             }
 
             // TODO : Could take ownership, and recycle / reuse for next broadcast ...
-            // while let Some((ref source, ref counter, ref mut recv_messages, ref mut recv_internal)) = *self.puller.pull() {
             while let Some(message) = self.puller.pull() {
+                log_progress(&self.logging, &self.progress_logging, &self.addr, false, message.0, message.1, self.channel_identifier, &message.2[..], &message.3[..]);
+                accumulate(message, messages, internal);
+            }
+        }
+    }
+
+    // Indicates that updates are held back, to be sent in a later call to `send_and_recv`.
+    fn holds_updates(&mut self) -> bool {
+        !self.held.messages.is_empty() || !self.held.internal.is_empty()
+    }
+
+    // Moves `messages` and `internal` into the held updates, and moves the held updates back if they
+    // are due to be sent. If they are not, requests that the scope be stepped again when they are.
+    fn hold(
+        &mut self,
+        messages: &mut ChangeBatch<(usize, usize, T)>,
+        internal: &mut ChangeBatch<(usize, usize, T)>)
+    {
+        messages.drain_into(&mut self.held.messages);
+        internal.drain_into(&mut self.held.internal);
+
+        if self.holds_updates() {
+            let since = *self.held.since.get_or_insert_with(Instant::now);
+            self.held.steps += 1;
+            let due = match self.batching {
+                ProgressBatching::Immediate => true,
+                ProgressBatching::Steps(steps) => self.held.steps >= steps,
+                ProgressBatching::Delay(delay) => since.elapsed() >= delay,
+            };
+            if due {
+                ::std::mem::swap(messages, &mut self.held.messages);
+                ::std::mem::swap(internal, &mut self.held.internal);
+                self.held.steps = 0;
+                self.held.since = None;
+            }
+            else {
+                match self.batching {
+                    ProgressBatching::Delay(delay) => self.activations.borrow_mut().activate_at(&self.addr[..], since + delay),
+                    _ => self.activations.borrow_mut().activate(&self.addr[..]),
+                }
+            }
+        }
+        else {
+            self.held.steps = 0;
+            self.held.since = None;
+        }
+    }
+
+    // Exchanges updates through the first worker of this worker's process.
+    fn send_and_recv_hierarchical(
+        &mut self,
+        messages: &mut ChangeBatch<(usize, usize, T)>,
+        internal: &mut ChangeBatch<(usize, usize, T)>)
+    {
+        let hierarchy = self.hierarchy.as_mut().expect("hierarchical exchange without channels");
+
+        if self.source == hierarchy.representative {
+
+            // Gather the updates of this worker and of the other workers of its process. Each batch
+            // gathered is complete, and the consolidated batch is sent all at once.
+            messages.drain_into(&mut hierarchy.messages);
+            internal.drain_into(&mut hierarchy.internal);
+            while let Some(message) = self.puller.pull() {
+                log_progress(&self.logging, &self.progress_logging, &self.addr, false, message.0, message.1, self.channel_identifier, &message.2[..], &message.3[..]);
+                accumulate(message, &mut hierarchy.messages, &mut hierarchy.internal);
+            }
+
+            if !hierarchy.messages.is_empty() || !hierarchy.internal.is_empty() {
 
-                let source = message.0;
-                let counter = message.1;
-                let recv_messages = &message.2;
-                let recv_internal = &message.3;
-
-                let addr = &mut self.addr;
-                let channel = self.channel_identifier;
-                self.logging.as_ref().map(|l| l.log(::logging::ProgressEvent {
-                    is_send: false,
-                    source: source,
-                    seq_no: counter,
-                    channel,
-                    addr: addr.clone(),
-                    // TODO: fill with additional data
-                    messages: Vec::new(),
-                    internal: Vec::new(),
-                }));
-
-                // We clone rather than drain to avoid deserialization.
-                for &(ref update, delta) in recv_messages.iter() {
-                    messages.update(update.clone(), delta);
+                log_progress(&self.logging, &self.progress_logging, &self.addr, true, self.source, self.counter, hierarchy.exchange.identifier, hierarchy.messages.iter().as_slice(), hierarchy.internal.iter().as_slice());
+
+                for &representative in hierarchy.representatives.iter() {
+                    let pusher = &mut hierarchy.exchange.pushers[representative];
+                    push_progress(pusher, &mut self.to_push, self.source, self.counter, hierarchy.messages.iter().as_slice(), hierarchy.internal.iter().as_slice());
                 }
 
-                // We clone rather than drain to avoid deserialization.
-                for &(ref update, delta) in recv_internal.iter() {
-                    internal.update(update.clone(), delta);
+                self.counter += 1;
+
+                hierarchy.messages.clear();
+                hierarchy.internal.clear();
+            }
+
+            // Receive the consolidated updates of all processes, including this one, and relay them
+            // in the order received to the other workers of this process.
+            while let Some(message) = hierarchy.exchange.puller.pull() {
+                log_progress(&self.logging, &self.progress_logging, &self.addr, false, message.0, message.1, hierarchy.exchange.identifier, &message.2[..], &message.3[..]);
+                for &local in hierarchy.locals.iter() {
+                    log_progress(&self.logging, &self.progress_logging, &self.addr, true, message.0, message.1, hierarchy.relay.identifier, &message.2[..], &message.3[..]);
+                    let pusher = &mut hierarchy.relay.pushers[local];
+                    push_progress(pusher, &mut self.to_push, message.0, message.1, &message.2[..], &message.3[..]);
                 }
+                accumulate(message, messages, internal);
+            }
+        }
+        else {
+
+            if !messages.is_empty() || !internal.is_empty() {
+
+                log_progress(&self.logging, &self.progress_logging, &self.addr, true, self.source, self.counter, self.channel_identifier, messages.iter().as_slice(), internal.iter().as_slice());
+
+                let pusher = &mut self.pushers[hierarchy.representative];
+                push_progress(pusher, &mut self.to_push, self.source, self.counter, messages.iter().as_slice(), internal.iter().as_slice());
+
+                self.counter += 1;
+
+                messages.clear();
+                internal.clear();
+            }
+
+            while let Some(message) = hierarchy.relay.puller.pull() {
+                log_progress(&self.logging, &self.progress_logging, &self.addr, false, message.0, message.1, hierarchy.relay.identifier, &message.2[..], &message.3[..]);
+                accumulate(message, messages, internal);
             }
         }
     }
 }
+
+// Allocates a channel for progress updates, whose arrivals activate the scope at `path`.
+fn allocate_channel<T: Timestamp, A: ::worker::AsWorker>(worker: &mut A, path: &Vec<usize>, logging: &mut Option<Logger>) -> Channel<T> {
+    let identifier = worker.new_identifier();
+    let (pushers, puller) = worker.allocate(identifier);
+    // Progress updates from other workers activate the scope they are for.
+    worker.bind_channel(identifier, &path[..]);
+    logging.as_mut().map(|l| l.log(::logging::CommChannelsEvent {
+        identifier,
+        kind: ::logging::CommChannelKind::Progress,
+    }));
+    Channel { identifier, pushers, puller }
+}
+
+// Pushes a progress message at `pusher`, re-using the allocation in `buffer` if possible.
+fn push_progress<T: Timestamp>(
+    pusher: &mut Box<Push<ProgressMsg<T>>>,
+    buffer: &mut Option<ProgressMsg<T>>,
+    source: usize,
+    counter: usize,
+    messages: &[((usize, usize, T), i64)],
+    internal: &[((usize, usize, T), i64)])
+{
+    // Attempt to re-use allocations, if possible.
+    if let Some(tuple) = buffer {
+        let tuple = tuple.as_mut();
+        tuple.0 = source;
+        tuple.1 = counter;
+        tuple.2.clear(); tuple.2.extend(messages.iter().cloned());
+        tuple.3.clear(); tuple.3.extend(internal.iter().cloned());
+    }
+    // If we don't have an allocation ...
+    if buffer.is_none() {
+        *buffer = Some(Message::from_typed((source, counter, messages.to_vec(), internal.to_vec())));
+    }
+
+    pusher.push(buffer);
+}
+
+// Accumulates the updates of a received progress message into `messages` and `internal`.
+fn accumulate<T: Timestamp>(
+    message: &ProgressMsg<T>,
+    messages: &mut ChangeBatch<(usize, usize, T)>,
+    internal: &mut ChangeBatch<(usize, usize, T)>)
+{
+    // We clone rather than drain to avoid deserialization.
+    for &(ref update, delta) in message.2.iter() {
+        messages.update(update.clone(), delta);
+    }
+
+    // We clone rather than drain to avoid deserialization.
+    for &(ref update, delta) in message.3.iter() {
+        internal.update(update.clone(), delta);
+    }
+}
+
+// Logs the sending or receipt of a progress message, and the updates it carries if progress is logged.
+fn log_progress<T: Timestamp>(
+    logging: &Option<Logger>,
+    progress_logging: &Option<ProgressLogger>,
+    addr: &Vec<usize>,
+    is_send: bool,
+    source: usize,
+    seq_no: usize,
+    channel: usize,
+    messages: &[((usize, usize, T), i64)],
+    internal: &[((usize, usize, T), i64)])
+{
+    logging.as_ref().map(|l| l.log(::logging::ProgressEvent {
+        is_send,
+        source,
+        seq_no,
+        channel,
+        addr: addr.clone(),
+    }));
+    progress_logging.as_ref().map(|l| {
+        let updates = |updates: &[((usize, usize, T), i64)]| {
+            updates.iter().map(|&((index, port, ref time), delta)| (index, port, time.clone(), delta)).collect::<Vec<_>>()
+        };
+        l.log(::logging::TimelyProgressEvent {
+            is_send,
+            source,
+            seq_no,
+            channel,
+            addr: addr.clone(),
+            messages: Box::new(updates(messages)),
+            internal: Box::new(updates(internal)),
+        })
+    });
+}
@@ -0,0 +1,51 @@
+//! Descriptions of the progress state of operators, for debugging stuck computations.
+//!
+//! Timestamps are formatted with `Debug`, as the operators of a worker have timestamps of
+//! different types. Counts of capabilities and messages are those known to the containing scope,
+//! which accumulates the progress updates of all workers.
+
+/// The progress state of an operator, as recorded by its containing scope.
+#[derive(Clone, Debug)]
+pub struct OperatorSnapshot {
+    /// The operator's worker-unique identifier, as in its `OperatesEvent`.
+    pub id: usize,
+    /// The operator's path from the worker.
+    pub address: Vec<usize>,
+    /// The operator's name.
+    pub name: String,
+    /// For each input, the frontier of times at which the input may yet receive data.
+    pub input_frontiers: Vec<Vec<String>>,
+    /// For each output, the earliest times for which capabilities are held, with their counts.
+    pub capabilities: Vec<Vec<(String, i64)>>,
+    /// For each input, the earliest times of messages not yet received, with their counts.
+    pub messages: Vec<Vec<(String, i64)>>,
+}
+
+/// A port of an operator.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Port {
+    /// An input port of the operator.
+    Input(usize),
+    /// An output port of the operator.
+    Output(usize),
+}
+
+/// A pointstamp that holds back the frontier of an operator's input.
+///
+/// A pointstamp at an output is a capability held by the operator, and one at an input is a
+/// count of messages not yet received by it. A pointstamp at an input of the scope containing the
+/// operator, reported with the scope's address, is the frontier of the data entering the scope,
+/// which is held back by pointstamps outside of the scope.
+#[derive(Clone, Debug)]
+pub struct Blocker {
+    /// The path from the worker of the operator at which the pointstamp is held.
+    pub address: Vec<usize>,
+    /// The operator's name.
+    pub name: String,
+    /// The port at which the pointstamp is held.
+    pub port: Port,
+    /// The time of the pointstamp.
+    pub time: String,
+    /// The count of the pointstamp.
+    pub count: i64,
+}
@@ -12,3 +12,4 @@ pub mod nested;
 pub mod timestamp;
 pub mod operate;
 pub mod broadcast;
+pub mod introspect;
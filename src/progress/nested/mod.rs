@@ -4,7 +4,6 @@ pub use self::subgraph::{Subgraph, SubgraphBuilder};
 pub use self::subgraph::{Source, Target};
 pub use self::summary::Summary;
 
-pub mod pointstamp_counter;
 pub mod summary;
 pub mod product;
 pub mod subgraph;
@@ -156,10 +156,40 @@ impl<T: Timestamp> Builder<T> {
 
     /// Compiles the current nodes and edges into immutable path summaries.
     ///
-    /// This method has the opportunity to perform some error checking that the path summaries
-    /// are valid, including references to undefined nodes and ports, as well as self-loops with
-    /// default summaries (a serious liveness issue).
+    /// # Panics
+    ///
+    /// Panics if the graph contains a cycle with a default summary, as reported by `try_summarize`.
     pub fn summarize(&mut self) -> Summary<T> {
+        match self.try_summarize() {
+            Ok(summary) => summary,
+            Err(targets) => panic!("Default summary found along self-loop: {:?}", targets),
+        }
+    }
+
+    /// Compiles the current nodes and edges into immutable path summaries, or returns the targets
+    /// on cycles whose path summary is the default summary.
+    ///
+    /// Such cycles are a serious liveness issue: a timestamp at any of their targets prevents
+    /// itself from ever advancing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use timely::progress::frontier::Antichain;
+    /// use timely::progress::nested::subgraph::{Source, Target};
+    /// use timely::progress::nested::reachability::Builder;
+    ///
+    /// // Two nodes connected in a cycle, neither of which advances timestamps.
+    /// let mut builder = Builder::<usize>::new();
+    /// builder.add_node(0, 1, 1, vec![vec![Antichain::from_elem(0)]]);
+    /// builder.add_node(1, 1, 1, vec![vec![Antichain::from_elem(0)]]);
+    /// builder.add_edge(Source { index: 0, port: 0}, Target { index: 1, port: 0} );
+    /// builder.add_edge(Source { index: 1, port: 0}, Target { index: 0, port: 0} );
+    ///
+    /// let targets = builder.try_summarize().err().unwrap();
+    /// assert_eq!(targets, vec![Target { index: 0, port: 0 }, Target { index: 1, port: 0 }]);
+    /// ```
+    pub fn try_summarize(&mut self) -> Result<Summary<T>, Vec<Target>> {
 
         // We maintain a list of new ((source, target), path_summary) entries whose implications
         // have not yet been fully explored. While such entries exist, we consider the next and
@@ -223,19 +253,20 @@ impl<T: Timestamp> Builder<T> {
         }
 
         // Test for trivial summaries along self-loops.
-        #[cfg(debug_assertions)]
-        {
-            for node in 0 .. target_target.len() {
-                for port in 0 .. target_target[node].len() {
-                    let this_target = Target { index: node, port };
-                    for &(ref target, ref summary) in target_target[node][port].iter() {
-                        if target == &this_target && summary.less_equal(&Default::default()) {
-                            panic!("Default summary found along self-loop: {:?}", target);
-                        }
+        let mut cycles = Vec::new();
+        for node in 0 .. target_target.len() {
+            for port in 0 .. target_target[node].len() {
+                let this_target = Target { index: node, port };
+                for &(ref target, ref summary) in target_target[node][port].iter() {
+                    if target == &this_target && summary.less_equal(&Default::default()) {
+                        cycles.push(this_target);
                     }
                 }
             }
         }
+        if !cycles.is_empty() {
+            return Err(cycles);
+        }
 
         // Incorporate trivial self-loops, as changes at a target do apply to the target.
         for index in 0 .. self.nodes.len() {
@@ -248,10 +279,10 @@ impl<T: Timestamp> Builder<T> {
             }
         }
 
-        Summary {
+        Ok(Summary {
             source_target,
             target_target,
-        }
+        })
     }
 }
 
@@ -64,6 +64,7 @@ use progress::Timestamp;
 use progress::nested::{Source, Target};
 use progress::ChangeBatch;
 
+use order::PartialOrder;
 use progress::frontier::{Antichain, MutableAntichain};
 use progress::timestamp::PathSummary;
 
@@ -90,7 +91,7 @@ use progress::timestamp::PathSummary;
 ///
 /// ```rust
 /// use timely::progress::frontier::Antichain;
-/// use timely::progress::nested::subgraph::{Source, Target};
+/// use timely::progress::nested::{Source, Target};
 /// use timely::progress::nested::reachability_neu::Builder;
 ///
 /// // allocate a new empty topology builder.
@@ -181,6 +182,154 @@ impl<T: Timestamp> Builder<T> {
     pub fn build(&self) -> Tracker<T> {
         Tracker::allocate_from(self)
     }
+
+    /// Compiles the current nodes and edges into path summaries between all pairs of locations,
+    /// or returns the targets on cycles whose path summary is the default summary.
+    ///
+    /// The `Tracker` does not need these summaries to propagate changes, but they describe the
+    /// reach of locations through the graph, for example from the inputs to the outputs of a scope.
+    /// Cycles with default summaries are a serious liveness issue: a timestamp at any of their
+    /// targets prevents itself from ever advancing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use timely::progress::frontier::Antichain;
+    /// use timely::progress::nested::{Source, Target};
+    /// use timely::progress::nested::reachability_neu::Builder;
+    ///
+    /// // Two nodes connected in a cycle, the second of which advances timestamps.
+    /// let mut builder = Builder::<usize>::new();
+    /// builder.add_node(0, 1, 1, vec![vec![Antichain::from_elem(0)]]);
+    /// builder.add_node(1, 1, 1, vec![vec![Antichain::from_elem(1)]]);
+    /// builder.add_edge(Source { index: 0, port: 0}, Target { index: 1, port: 0} );
+    /// builder.add_edge(Source { index: 1, port: 0}, Target { index: 0, port: 0} );
+    ///
+    /// let summary = builder.summarize().unwrap();
+    /// assert_eq!(summary.source_target[0][0], vec![
+    ///     (Target { index: 1, port: 0 }, Antichain::from_elem(0)),
+    ///     (Target { index: 0, port: 0 }, Antichain::from_elem(1)),
+    /// ]);
+    ///
+    /// // Without the advance, the cycle is reported.
+    /// builder.add_node(1, 1, 1, vec![vec![Antichain::from_elem(0)]]);
+    /// let targets = builder.summarize().err().unwrap();
+    /// assert_eq!(targets, vec![Target { index: 0, port: 0 }, Target { index: 1, port: 0 }]);
+    /// ```
+    pub fn summarize(&self) -> Result<Summary<T>, Vec<Target>> {
+
+        // Targets are indexed densely, to find their summaries in lists of reached targets.
+        let mut offsets = Vec::with_capacity(self.shape.len() + 1);
+        offsets.push(0);
+        for &(inputs, _) in self.shape.iter() {
+            let last = offsets[offsets.len() - 1];
+            offsets.push(last + inputs);
+        }
+        let mut positions = vec![None; offsets[self.shape.len()]];
+
+        // Explore the paths from each source in turn, extending each novel summary to a target
+        // through the node to its outputs, and along their edges to further targets.
+        let mut work = ::std::collections::VecDeque::<(Target, T::Summary)>::new();
+        let mut source_target = Vec::with_capacity(self.shape.len());
+        for (index, ports) in self.edges.iter().enumerate() {
+            let mut node = Vec::with_capacity(ports.len());
+            for targets in ports.iter() {
+                let mut reach = Vec::new();
+                work.extend(targets.iter().map(|&target| (target, Default::default())));
+                while let Some((target, summary)) = work.pop_front() {
+                    if add_summary(&mut reach, &mut positions[..], &offsets[..], target, &summary) {
+                        for (output, internal_summaries) in self.nodes[target.index][target.port].iter().enumerate() {
+                            for internal_summary in internal_summaries.elements().iter() {
+                                if let Some(new_summary) = summary.followed_by(internal_summary) {
+                                    for &new_target in self.edges[target.index][output].iter() {
+                                        work.push_back((new_target, new_summary.clone()));
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                clear_positions(&reach, &mut positions[..], &offsets[..]);
+                node.push(reach);
+            }
+            source_target.push(node);
+            debug_assert_eq!(source_target[index].len(), self.shape[index].1);
+        }
+
+        // Extend the summaries from each source by one connection from a target to the source, to
+        // yield summaries along non-empty paths from targets, in which to look for cycles.
+        let mut target_target = Vec::with_capacity(self.shape.len());
+        let mut cycles = Vec::new();
+        for (index, inputs) in self.nodes.iter().enumerate() {
+            let mut node = Vec::with_capacity(inputs.len());
+            for (input, outputs) in inputs.iter().enumerate() {
+                let mut reach = Vec::new();
+                for (output, internal_summaries) in outputs.iter().enumerate() {
+                    for internal_summary in internal_summaries.elements().iter() {
+                        for &(target, ref summaries) in source_target[index][output].iter() {
+                            for summary in summaries.elements().iter() {
+                                if let Some(summary) = internal_summary.followed_by(summary) {
+                                    add_summary(&mut reach, &mut positions[..], &offsets[..], target, &summary);
+                                }
+                            }
+                        }
+                    }
+                }
+                let this_target = Target { index, port: input };
+                if reach.iter().any(|&(target, ref summaries)| target == this_target && summaries.less_equal(&Default::default())) {
+                    cycles.push(this_target);
+                }
+                // Changes at a target do apply to the target.
+                add_summary(&mut reach, &mut positions[..], &offsets[..], this_target, &Default::default());
+                clear_positions(&reach, &mut positions[..], &offsets[..]);
+                node.push(reach);
+            }
+            target_target.push(node);
+        }
+
+        if cycles.is_empty() {
+            Ok(Summary { source_target, target_target })
+        }
+        else {
+            Err(cycles)
+        }
+    }
+}
+
+/// Path summaries between the locations of a graph, as computed by `Builder::summarize`.
+#[derive(Clone)]
+pub struct Summary<T: Timestamp> {
+    /// Compiled source-to-target reachability.
+    ///
+    /// Entry `source_target[node][port]` lists pairs of target and summaries that can be
+    /// reached from the (node, port) output port.
+    pub source_target: Vec<Vec<Vec<(Target, Antichain<T::Summary>)>>>,
+    /// Compiled target-to-target reachability.
+    ///
+    /// Entry `target_target[node][port]` lists pairs of target and summaries that can be
+    /// reached from the (node, port) input port, including the port itself.
+    pub target_target: Vec<Vec<Vec<(Target, Antichain<T::Summary>)>>>,
+}
+
+// Adds `summary` for `target` to `reach`, whose targets' positions are recorded in `positions` at
+// their offsets, and indicates whether the summary was novel.
+fn add_summary<S: PartialOrder+Eq+Clone>(reach: &mut Vec<(Target, Antichain<S>)>, positions: &mut [Option<usize>], offsets: &[usize], target: Target, summary: &S) -> bool {
+    let slot = offsets[target.index] + target.port;
+    match positions[slot] {
+        Some(position) => reach[position].1.insert(summary.clone()),
+        None => {
+            positions[slot] = Some(reach.len());
+            reach.push((target, Antichain::from_elem(summary.clone())));
+            true
+        },
+    }
+}
+
+// Forgets the positions of the targets in `reach`.
+fn clear_positions<S>(reach: &[(Target, Antichain<S>)], positions: &mut [Option<usize>], offsets: &[usize]) {
+    for &(target, _) in reach.iter() {
+        positions[offsets[target.index] + target.port] = None;
+    }
 }
 
 /// An interactive tracker of propagated reachability information.
@@ -218,6 +367,11 @@ pub struct Tracker<T:Timestamp> {
     /// Buffer of consequent changes.
     pushed_changes: ChangeBatch<(Target, T)>,
 
+    /// Changes to the frontiers of pointstamps at sources and targets, from which the consequences
+    /// of pointstamps elsewhere can be derived without exposure to transiently negative counts.
+    source_frontier_changes: ChangeBatch<(Source, T)>,
+    target_frontier_changes: ChangeBatch<(Target, T)>,
+
     /// Internal connections within hosted operators.
     ///
     /// Indexed by operator index, then input port, then output port. This is the
@@ -283,6 +437,8 @@ impl<T:Timestamp> Tracker<T> {
             target_changes: ChangeBatch::new(),
             target_worklist: BinaryHeap::new(),
             pushed_changes: ChangeBatch::new(),
+            source_frontier_changes: ChangeBatch::new(),
+            target_frontier_changes: ChangeBatch::new(),
             nodes: builder.nodes.clone(),
             edges: builder.edges.clone(),
             compiled,
@@ -295,7 +451,9 @@ impl<T:Timestamp> Tracker<T> {
         // Filter each target change through `self.targets`.
         for ((target, time), diff) in self.target_changes.drain() {
             let target_worklist = &mut self.target_worklist;
+            let frontier_changes = &mut self.target_frontier_changes;
             self.targets[target.index][target.port].update_iter_and(Some((time, diff)), |time, diff| {
+                frontier_changes.update((target, time.clone()), diff);
                 target_worklist.push(Reverse((time.clone(), target, diff)))
             })
         }
@@ -303,8 +461,10 @@ impl<T:Timestamp> Tracker<T> {
         // Filter each source change through `self.sources` and then along edges.
         for ((source, time), diff) in self.source_changes.drain() {
             let target_worklist = &mut self.target_worklist;
+            let frontier_changes = &mut self.source_frontier_changes;
             let edges = &self.edges[source.index][source.port];
             self.sources[source.index][source.port].update_iter_and(Some((time, diff)), |time, diff| {
+                frontier_changes.update((source, time.clone()), diff);
                 for &target in edges.iter() {
                     target_worklist.push(Reverse((time.clone(), target, diff)))
                 }
@@ -372,4 +532,42 @@ impl<T:Timestamp> Tracker<T> {
     pub fn pushed(&mut self) -> &mut ChangeBatch<(Target, T)> {
         &mut self.pushed_changes
     }
+
+    /// Changes to the frontiers of the pointstamps at sources, from propagations since last drained.
+    ///
+    /// Unlike the changes applied with `update_source`, these never reflect transiently negative
+    /// counts at a source, and may be used to derive the reach of the pointstamps elsewhere.
+    pub fn source_frontier_changes(&mut self) -> &mut ChangeBatch<(Source, T)> {
+        &mut self.source_frontier_changes
+    }
+
+    /// Changes to the frontiers of the pointstamps at targets, from propagations since last drained.
+    ///
+    /// Unlike the changes applied with `update_target`, these never reflect transiently negative
+    /// counts at a target, and may be used to derive the reach of the pointstamps elsewhere.
+    pub fn target_frontier_changes(&mut self) -> &mut ChangeBatch<(Target, T)> {
+        &mut self.target_frontier_changes
+    }
+
+    /// The pointstamps at the outputs of the node at `index`, as of the most recent propagation.
+    pub fn source(&self, index: usize) -> &[MutableAntichain<T>] {
+        &self.sources[index][..]
+    }
+
+    /// The pointstamps at the inputs of the node at `index`, as of the most recent propagation.
+    pub fn target(&self, index: usize) -> &[MutableAntichain<T>] {
+        &self.targets[index][..]
+    }
+
+    /// The frontiers implied at the inputs of the node at `index` by the pointstamps that can reach
+    /// them, as of the most recent propagation.
+    pub fn implied(&self, index: usize) -> &[MutableAntichain<T>] {
+        &self.pusheds[index][..]
+    }
+
+    /// Indicates if any pointstamps are tracked at any source or target, as of the most recent propagation.
+    pub fn tracking_anything(&self) -> bool {
+        self.sources.iter().any(|ports| ports.iter().any(|x| !x.is_empty())) ||
+        self.targets.iter().any(|ports| ports.iter().any(|x| !x.is_empty()))
+    }
 }
\ No newline at end of file
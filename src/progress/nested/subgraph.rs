@@ -3,17 +3,23 @@
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::default::Default;
+use std::any::Any;
+use std::collections::HashMap;
 
 use logging::TimelyLogger as Logger;
 
+use order::PartialOrder;
 use progress::frontier::{MutableAntichain, Antichain};
-use progress::{Timestamp, Operate};
+use progress::{Timestamp, PathSummary, Operate};
+use progress::introspect::{OperatorSnapshot, Blocker, Port};
 
 use progress::ChangeBatch;
 use progress::broadcast::Progcaster;
 use progress::nested::summary::Summary::{Local, Outer};
 use progress::nested::product::Product;
-use progress::nested::reachability;
+use progress::nested::reachability_neu;
+
+use scheduling::Activations;
 
 // IMPORTANT : by convention, a child identifier of zero is used to indicate inputs and outputs of
 // the Subgraph itself. An identifier greater than zero corresponds to an actual child, which can
@@ -64,6 +70,9 @@ pub struct SubgraphBuilder<TOuter: Timestamp, TInner: Timestamp> {
 
     edge_stash: Vec<(Source, Target)>,
 
+    // descriptions of the problems with nested scopes that could not be built.
+    errors: Vec<String>,
+
     // shared state written to by the datapath, counting records entering this subgraph instance.
     input_messages: Vec<Rc<RefCell<ChangeBatch<Product<TOuter, TInner>>>>>,
 
@@ -111,6 +120,7 @@ impl<TOuter: Timestamp, TInner: Timestamp> SubgraphBuilder<TOuter, TInner> {
             children,
             child_count:         1,
             edge_stash:          vec![],
+            errors:              Vec::new(),
 
             input_messages:      Default::default(),
             output_capabilities: Default::default(),
@@ -139,8 +149,41 @@ impl<TOuter: Timestamp, TInner: Timestamp> SubgraphBuilder<TOuter, TInner> {
         self.children.push(PerOperatorState::new(child, index, self.path.clone(), identifier, self.logging.clone()))
     }
 
+    /// Records that a nested scope could not be built, for the problem described by `error`.
+    ///
+    /// The nested scope is not added as a child, and the subgraph then reports `error` when built.
+    pub fn add_error(&mut self, error: String) {
+        self.errors.push(error);
+    }
+
     /// Now that initialization is complete, actually build a subgraph.
-    pub fn build<A: ::worker::AsWorker>(mut self, worker: &mut A) -> Subgraph<TOuter, TInner> {
+    ///
+    /// # Panics
+    ///
+    /// Panics if the graph of the subgraph is invalid, as described by `try_build`.
+    pub fn build<A: ::worker::AsWorker>(self, worker: &mut A) -> Subgraph<TOuter, TInner> {
+        match self.try_build(worker) {
+            Ok(subgraph) => subgraph,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Builds a subgraph, or describes the problems with its graph.
+    ///
+    /// The graph is invalid if an input of an operator is never connected, which is the case for
+    /// a loop variable whose handle is never passed to `connect_loop`, if the stream of a loop
+    /// variable is never used, if an output of the subgraph is never connected from within it, if
+    /// a cycle does not advance timestamps, or if a nested scope is invalid. Each of these would
+    /// leave the dataflow unable to make progress, or to produce data. The description of an
+    /// invalid graph also lists the outputs of operators that are never connected, which often
+    /// were meant to be; on their own these are valid, as their data are simply discarded.
+    pub fn try_build<A: ::worker::AsWorker>(mut self, worker: &mut A) -> Result<Subgraph<TOuter, TInner>, String> {
+
+        // Nested scopes that could not be built are missing from the children.
+        if !self.errors.is_empty() {
+            return Err(self.errors.join("; "));
+        }
+
         // at this point, the subgraph is frozen. we should initialize any internal state which
         // may have been determined after construction (e.g. the numbers of inputs and outputs).
         // we also need to determine what to return as a summary and initial capabilities, which
@@ -153,7 +196,7 @@ impl<TOuter: Timestamp, TInner: Timestamp> SubgraphBuilder<TOuter, TInner> {
         let inputs = self.input_messages.len();
         let outputs = self.output_capabilities.len();
 
-        let mut builder = reachability::Builder::new();
+        let mut builder = reachability_neu::Builder::new();
 
         // Child 0 has `inputs` outputs and `outputs` inputs, not yet connected.
         builder.add_node(0, outputs, inputs, vec![vec![Antichain::new(); inputs]; outputs]);
@@ -161,16 +204,97 @@ impl<TOuter: Timestamp, TInner: Timestamp> SubgraphBuilder<TOuter, TInner> {
             builder.add_node(index, child.inputs, child.outputs, child.gis_summary.clone());
         }
 
-        for (source, target) in self.edge_stash {
+        let mut connected = self.children.iter().map(|child| vec![false; child.inputs]).collect::<Vec<_>>();
+        for (source, target) in self.edge_stash.drain(..) {
             self.children[source.index].edges[source.port].push(target);
             builder.add_edge(source, target);
+            connected[target.index][target.port] = true;
+        }
+
+        // Describe the problems with the graph, naming operators by their names and addresses.
+        let mut problems = Vec::new();
+        for (index, ports) in connected.iter().enumerate() {
+            for (port, &connected) in ports.iter().enumerate() {
+                if !connected {
+                    problems.push(if index == 0 {
+                        format!("output {} of the scope is never connected", port)
+                    }
+                    else if self.children[index].feedback {
+                        format!("the handle of loop variable {} at {:?} is never passed to `connect_loop`", self.children[index].name, self.address(index))
+                    }
+                    else {
+                        format!("input {} of operator {} at {:?} is never connected", port, self.children[index].name, self.address(index))
+                    });
+                }
+            }
+        }
+        let mut dangling = Vec::new();
+        for (index, child) in self.children.iter().enumerate().skip(1) {
+            for (port, targets) in child.edges.iter().enumerate() {
+                if targets.is_empty() {
+                    if child.feedback {
+                        problems.push(format!("the stream of loop variable {} at {:?} is never used", child.name, self.address(index)));
+                    }
+                    else {
+                        dangling.push(format!("output {} of operator {} at {:?} is never connected", port, child.name, self.address(index)));
+                    }
+                }
+            }
         }
+        let summary = match builder.summarize() {
+            Ok(summary) => Some(summary),
+            Err(targets) => {
+                for target in targets {
+                    problems.push(format!("a cycle through input {} of operator {} at {:?} does not advance timestamps", target.port, self.children[target.index].name, self.address(target.index)));
+                }
+                None
+            }
+        };
+        let summary = match summary {
+            Some(summary) if problems.is_empty() => summary,
+            _ => {
+                problems.extend(dangling);
+                return Err(format!("invalid dataflow graph in scope {} at {:?}: {}", self.name, self.path, problems.join("; ")));
+            },
+        };
 
-        let tracker = reachability::Tracker::allocate_from(builder.summarize());
+        // The reach of each source and target of the children to the outputs of the subgraph, through
+        // which changes to their frontiers are reported as changes to the capabilities of the subgraph.
+        let reach = |locations: &[(Target, Antichain<<Product<TOuter, TInner> as Timestamp>::Summary>)]| {
+            locations.iter().filter(|&&(target, _)| target.index == 0).map(|&(target, ref summaries)| (target.port, summaries.clone())).collect::<Vec<_>>()
+        };
+        let mut source_reach = vec![Vec::new()];
+        let mut target_reach = vec![Vec::new()];
+        for index in 1 .. self.children.len() {
+            source_reach.push(summary.source_target[index].iter().map(|x| reach(x)).collect());
+            target_reach.push(summary.target_target[index].iter().map(|x| reach(x)).collect());
+        }
+
+        let tracker = builder.build();
+
+        // Children check their own reports, and the subgraph the counts of messages at their inputs
+        // if it runs on a single worker, as otherwise receipts may be reported before sends.
+        if worker.progress_checking() {
+            for index in 1 .. self.children.len() {
+                let address = self.address(index);
+                let child = &mut self.children[index];
+                child.checks = Some(ProgressChecks::new(address, child.local, &mut child.gis_capabilities));
+            }
+        }
+        let checked_messages = if worker.progress_checking() && worker.peers() == 1 { Some(HashMap::new()) } else { None };
 
         let progcaster = Progcaster::new(worker, &self.path, self.logging.clone());
 
-        Subgraph {
+        // Each child is scheduled at least once, whether or not it is otherwise activated.
+        let activations = worker.activations();
+        let mut path = self.path.clone();
+        for index in 1 .. self.children.len() {
+            path.push(index);
+            activations.borrow_mut().activate(&path[..]);
+            path.pop();
+        }
+
+        Ok(Subgraph {
             name: self.name,
             path: self.path,
             inputs: self.input_messages.len(),
@@ -186,9 +310,23 @@ impl<TOuter: Timestamp, TInner: Timestamp> SubgraphBuilder<TOuter, TInner> {
             final_pointstamp_internal: ChangeBatch::new(),
             progcaster,
 
-            pointstamp_builder: builder,
+            pointstamp_summary: summary,
             pointstamp_tracker: tracker,
-        }
+            source_reach,
+            target_reach,
+            output_changes: vec![ChangeBatch::new(); outputs],
+
+            activations,
+            activated: Vec::new(),
+            checked_messages,
+        })
+    }
+
+    // the path from the worker of the child at `index`.
+    fn address(&self, index: usize) -> Vec<usize> {
+        let mut address = self.path.clone();
+        address.push(index);
+        address
     }
 }
 
@@ -220,12 +358,25 @@ pub struct Subgraph<TOuter:Timestamp, TInner:Timestamp> {
     final_pointstamp_messages: ChangeBatch<(usize, usize, Product<TOuter, TInner>)>,
     final_pointstamp_internal: ChangeBatch<(usize, usize, Product<TOuter, TInner>)>,
 
-    // Graph structure and pointstamp tracker.
-    pointstamp_builder: reachability::Builder<Product<TOuter, TInner>>,
-    pointstamp_tracker: reachability::Tracker<Product<TOuter, TInner>>,
+    // Path summaries between locations, and pointstamp tracker.
+    pointstamp_summary: reachability_neu::Summary<Product<TOuter, TInner>>,
+    pointstamp_tracker: reachability_neu::Tracker<Product<TOuter, TInner>>,
+
+    // the reach of each child output and input to the subgraph outputs, indexed by child then port.
+    source_reach: Vec<Vec<Vec<(usize, Antichain<<Product<TOuter, TInner> as Timestamp>::Summary>)>>>,
+    target_reach: Vec<Vec<Vec<(usize, Antichain<<Product<TOuter, TInner> as Timestamp>::Summary>)>>>,
+    // changes to the implied capabilities at the subgraph outputs, not yet reported.
+    output_changes: Vec<ChangeBatch<Product<TOuter, TInner>>>,
 
     // channel / whatever used to communicate pointstamp updates to peers.
     progcaster: Progcaster<Product<TOuter, TInner>>,
+
+    // activations of the worker's operators, and the indices of activated children.
+    activations: Rc<RefCell<Activations>>,
+    activated: Vec<usize>,
+
+    // counts of messages at the inputs of children, when checking progress on a single worker.
+    checked_messages: Option<HashMap<(usize, usize, Product<TOuter, TInner>), i64>>,
 }
 
 
@@ -236,6 +387,97 @@ impl<TOuter: Timestamp, TInner: Timestamp> Operate<TOuter> for Subgraph<TOuter,
     fn inputs(&self)  -> usize { self.inputs }
     fn outputs(&self) -> usize { self.outputs }
 
+    fn snapshot_operators(&mut self, snapshots: &mut Vec<OperatorSnapshot>) {
+        for index in 1 .. self.children.len() {
+            let mut address = self.path.clone();
+            address.push(index);
+            let child = &mut self.children[index];
+            snapshots.push(OperatorSnapshot {
+                id: child.id,
+                address,
+                name: child.name.clone(),
+                input_frontiers: child.external.iter().map(|frontier| {
+                    frontier.frontier().iter().map(|time| format!("{:?}", time)).collect()
+                }).collect(),
+                capabilities: self.pointstamp_tracker.source(index).iter().map(describe_counts).collect(),
+                messages: self.pointstamp_tracker.target(index).iter().map(describe_counts).collect(),
+            });
+            if let Some(ref mut operator) = child.operator {
+                operator.snapshot_operators(snapshots);
+            }
+        }
+    }
+
+    fn find_blockers(&mut self, path: &[usize], time: &Any, blockers: &mut Vec<Blocker>) {
+
+        let depth = self.path.len();
+        if path.len() <= depth || path[.. depth] != self.path[..] || path[depth] >= self.children.len() {
+            return;
+        }
+
+        let index = path[depth];
+        if path.len() > depth + 1 {
+            if let Some(ref mut operator) = self.children[index].operator {
+                operator.find_blockers(path, time, blockers);
+            }
+        }
+        else if let Some(time) = time.downcast_ref::<Product<TOuter, TInner>>() {
+
+            let summary = &self.pointstamp_summary;
+
+            // Indicates that a pointstamp reaches an input of the operator at or before `time`.
+            let reaches = |targets: &[(Target, Antichain<<Product<TOuter, TInner> as Timestamp>::Summary>)], pointstamp: &Product<TOuter, TInner>| {
+                targets.iter().any(|&(target, ref summaries)| {
+                    target.index == index && summaries.elements().iter().any(|summary| {
+                        summary.results_in(pointstamp).map(|result| result.less_equal(time)).unwrap_or(false)
+                    })
+                })
+            };
+
+            // Pointstamps at child zero are at the inputs and outputs of this scope.
+            for node in 0 .. self.children.len() {
+                let (address, name) = if node == 0 {
+                    (self.path.clone(), self.name.clone())
+                }
+                else {
+                    let mut address = self.path.clone();
+                    address.push(node);
+                    (address, self.children[node].name.clone())
+                };
+                for port in 0 .. self.children[node].outputs {
+                    let pointstamps = counts(&self.pointstamp_tracker.source(node)[port]);
+                    let targets = &summary.source_target[node][port];
+                    for (pointstamp, count) in pointstamps {
+                        if reaches(targets, &pointstamp) {
+                            blockers.push(Blocker {
+                                address: address.clone(),
+                                name: name.clone(),
+                                port: if node == 0 { Port::Input(port) } else { Port::Output(port) },
+                                time: format!("{:?}", pointstamp),
+                                count,
+                            });
+                        }
+                    }
+                }
+                for port in 0 .. self.children[node].inputs {
+                    let pointstamps = counts(&self.pointstamp_tracker.target(node)[port]);
+                    let targets = &summary.target_target[node][port];
+                    for (pointstamp, count) in pointstamps {
+                        if reaches(targets, &pointstamp) {
+                            blockers.push(Blocker {
+                                address: address.clone(),
+                                name: name.clone(),
+                                port: if node == 0 { Port::Output(port) } else { Port::Input(port) },
+                                time: format!("{:?}", pointstamp),
+                                count,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     // produces connectivity summaries from inputs to outputs, and reports initial internal
     // capabilities on each of the outputs (projecting capabilities from contained scopes).
     fn get_internal_summary(&mut self) -> (Vec<Vec<Antichain<TOuter::Summary>>>, Vec<ChangeBatch<TOuter>>) {
@@ -244,36 +486,23 @@ impl<TOuter: Timestamp, TInner: Timestamp> Operate<TOuter> for Subgraph<TOuter,
         assert_eq!(self.children[0].outputs, self.inputs());
         assert_eq!(self.children[0].inputs, self.outputs());
 
-        // collect the initial capabilities of each child, to determine the initial capabilities
-        // of the subgraph.
-        for child in self.children.iter_mut() {
+        // Introduce the initial capabilities of each child as pointstamps, move their frontiers along
+        // paths to the output ports, and present them as initial capabilities for the subgraph operator
+        // (`initial_capabilities`). The frontiers they imply at the inputs of children are delivered
+        // along with those of the outside world's capabilities, in `set_external_summary`.
+        for child in self.children.iter_mut().skip(1) {
             for (output, capability) in child.gis_capabilities.iter_mut().enumerate() {
                 for &(ref time, value) in capability.iter() {
                     self.pointstamp_tracker.update_source(Source { index: child.index, port: output }, time.clone(), value);
                 }
             }
         }
-
-        // Move pointstamps along paths to arrive at output ports,
-        // (the only destination of interest at the moment).
         self.pointstamp_tracker.propagate_all();
-
-        // Capabilities from internal operators have been pushed, and we can now find capabilities
-        // for each output port at `self.pointstamps.pushed[0]`. We should promote them to their
-        // appropriate buffer (`self.output_capabilities`) and present them as initial capabilities
-        // for the subgraph operator (`initial_capabilities`).
+        self.record_output_changes();
         let mut initial_capabilities = vec![ChangeBatch::new(); self.outputs()];
-        for (o_port, capabilities) in self.pointstamp_tracker.pushed_mut(0).iter_mut().enumerate() {
-            let caps1 = capabilities.drain().map(|(time, diff)| (time.outer, diff));
-            self.output_capabilities[o_port].update_iter_and(caps1, |t, v| {
-                initial_capabilities[o_port].update(t.clone(), v);
-            });
-        }
+        self.report_output_changes(&mut initial_capabilities);
 
-        // done with the pointstamps, so we should clean up.
-        self.pointstamp_tracker.clear();
-
-        let summary = self.pointstamp_builder.summarize();
+        let summary = &self.pointstamp_summary;
 
         // Summarize the scope internals by looking for source_target_summaries from child 0
         // sources to child 0 targets. These summaries are only in terms of the outer timestamp.
@@ -320,67 +549,49 @@ impl<TOuter: Timestamp, TInner: Timestamp> Operate<TOuter> for Subgraph<TOuter,
         }
         self.children[0].gis_capabilities = new_capabilities;
 
-        // Install the new summary, summarize, and remove "unhelpful" summaries.
-        // Specifically, and crucially, we remove summaries from the outputs of child zero to the inputs of child
-        // zero. This prevents the subgraph from reporting the external world's capabilities back as capabilities
-        // held by the subgraph. We also remove summaries to nodes that do not require progress information.
-        // self.pointstamp_builder.add_node(0, self.outputs, self.inputs, new_summary);
-        let mut pointstamp_summaries = self.pointstamp_builder.summarize();
-        for summaries in pointstamp_summaries.target_target[0].iter_mut() { summaries.retain(|&(t, _)| t.index > 0); }
-        for summaries in pointstamp_summaries.source_target[0].iter_mut() { summaries.retain(|&(t, _)| t.index > 0); }
-        for child in 0 .. self.children.len() {
-            for summaries in pointstamp_summaries.target_target[child].iter_mut() { summaries.retain(|&(t,_)| self.children[t.index].notify); }
-            for summaries in pointstamp_summaries.source_target[child].iter_mut() { summaries.retain(|&(t,_)| self.children[t.index].notify); }
-        }
-
-        // Allocate the pointstamp tracker using the finalized topology.
-        self.pointstamp_tracker = reachability::Tracker::allocate_from(pointstamp_summaries.clone());
-
-        // Initialize all expressed capabilities as pointstamps, for propagation.
-        for child in self.children.iter_mut() {
-            for output in 0 .. child.outputs {
-                for &(ref time, value) in child.gis_capabilities[output].iter() {
-                    self.pointstamp_tracker.update_source(
-                        Source { index: child.index, port: output },
-                        time.clone(),
-                        value,
-                    )
-                }
+        // Initialize the capabilities of the outside world as pointstamps, for propagation. Those of the
+        // other children were introduced by `get_internal_summary`.
+        for output in 0 .. self.children[0].outputs {
+            for &(ref time, value) in self.children[0].gis_capabilities[output].iter() {
+                self.pointstamp_tracker.update_source(Source { index: 0, port: output }, time.clone(), value);
             }
         }
 
-        // Propagate pointstamps using the complete summary to determine initial frontiers for each child.
+        // Propagate pointstamps to determine initial frontiers for each child. Capabilities implied at
+        // child zero, the outputs of the subgraph, were reported by `get_internal_summary`, and those
+        // of the outside world are not among them.
         self.pointstamp_tracker.propagate_all();
+        self.record_output_changes();
+        let mut frontiers = self.children.iter().map(|child| vec![ChangeBatch::new(); child.inputs]).collect::<Vec<_>>();
+        for ((target, time), diff) in self.pointstamp_tracker.pushed().drain() {
+            if target.index > 0 && self.children[target.index].notify {
+                frontiers[target.index][target.port].update(time, diff);
+            }
+        }
+
+        // Summarize the subgraph for each child by the path summaries from the child's outputs to its
+        // inputs, omitting summaries to children that do not require progress information.
+        let pointstamp_summaries = &self.pointstamp_summary;
 
         // We now have enough information to call `set_external_summary` for each child.
-        for child in self.children.iter_mut() {
-
-            // // Titrate propagated capability changes through a MutableAntichain, and leave them in
-            // // the child's buffer for pending `external` updates to apply in its next `push_external`
-            // // call.
-            // let pushed_mut = self.pointstamp_tracker.pushed_mut(child.index);
-            // for input in 0..child.inputs {
-            //     let buffer = &mut child.external_buffer[input];
-            //     let iterator2 = pushed_mut[input].drain();
-            //     child.external[input].update_iter_and(iterator2, |t, v| { buffer.update(t.clone(), v); });
-            // }
-
-            // Summarize the subgraph by the path summaries from the child's output to its inputs.
+        for (child, frontier) in self.children.iter_mut().zip(frontiers.iter_mut()) {
+
             let mut summary = vec![vec![Antichain::new(); child.inputs]; child.outputs];
-            for output in 0..child.outputs {
-                for &(source, ref antichain) in &pointstamp_summaries.source_target[child.index][output] {
-                    if source.index == child.index {
-                        summary[output][source.port] = (*antichain).clone();
+            if child.notify {
+                for output in 0..child.outputs {
+                    for &(target, ref antichain) in &pointstamp_summaries.source_target[child.index][output] {
+                        if target.index == child.index {
+                            summary[output][target.port] = (*antichain).clone();
+                        }
                     }
                 }
             }
 
-            let child_index = child.index;
-            child.set_external_summary(summary, self.pointstamp_tracker.pushed_mut(child_index));
+            child.set_external_summary(summary, &mut frontier[..]);
         }
 
         // clean up after ourselves.
-        assert!(self.pointstamp_tracker.is_empty());
+        assert!(self.pointstamp_tracker.pushed().is_empty());
     }
 
     /// Receive changes in the external capabilities of the containing scope.
@@ -405,10 +616,8 @@ impl<TOuter: Timestamp, TInner: Timestamp> Operate<TOuter> for Subgraph<TOuter,
             }
         }
 
-        // This update should reflect messages reported in `produced`, so we remove them now.
-        // It would be better if the parent explicitly reported how many were consumed, much
-        // like how we report to the parent how many input records were consumed.
-        self.pointstamp_tracker.target(0).iter_mut().for_each(|x| x.clear());
+        // Messages reported in `produced` are not tracked at child zero, as the parent tracks them
+        // from the moment they are reported, so there is nothing to remove here.
     }
 
     /// Report changes in messages and capabilities for the subgraph and its peers.
@@ -488,23 +697,54 @@ impl<TOuter: Timestamp, TInner: Timestamp> Operate<TOuter> for Subgraph<TOuter,
             }
         }
 
-        // Demultiplex `self.final_` into `self.pointstamp_tracker`. Updates to message counts for
-        // inputs to child zero are also deposited in `produced`.
+        // Demultiplex `self.final_` into `self.pointstamp_tracker`. Updates to message counts for inputs
+        // to child zero are deposited in `produced` instead, as from this moment the parent tracks them.
         for ((index, input, time), delta) in self.final_pointstamp_messages.drain() {
-            if index == 0 { produced[input].update(time.outer.clone(), delta); }
-            self.pointstamp_tracker.update_target(Target { index, port: input }, time, delta);
+            if index == 0 {
+                produced[input].update(time.outer, delta);
+            }
+            else {
+                if let Some(ref mut counts) = self.checked_messages {
+                    check_messages(counts, &self.children[index].name, &self.path, index, input, time.clone(), delta);
+                }
+                self.pointstamp_tracker.update_target(Target { index, port: input }, time, delta);
+            }
         }
         for ((index, output, time), delta) in self.final_pointstamp_internal.drain() {
             self.pointstamp_tracker.update_source(Source { index, port: output }, time, delta);
         }
 
-        // Step 4. Propagate pointstamp updates to inform each source about changes in their frontiers.
+        // Step 4. Propagate pointstamp updates to inform each target about changes in its frontier, and
+        //         record changes in the frontiers of inputs of children that require progress information,
+        //         and in the capabilities implied at our outputs.
         self.pointstamp_tracker.propagate_all();
+        self.record_output_changes();
+        for ((target, time), diff) in self.pointstamp_tracker.pushed().drain() {
+            let child = &mut self.children[target.index];
+            if target.index > 0 && child.notify {
+                child.pushed_buffer[target.port].update(time, diff);
+            }
+        }
+
+        // Step 5. Provide each activated child, and each child whose input frontiers may have changed,
+        //         with updated frontier information and an opportunity to execute. Children are
+        //         activated by data arriving on their inputs, or by requests through an `Activator`.
+        {
+            let activated = &mut self.activated;
+            self.activations.borrow().for_extensions(&self.path[..], |index| activated.push(index));
+        }
+        let mut position = 0;
 
-        // Step 5. Provide each child with updated frontier information and an opportunity to execute.
         let mut any_child_active = false;
         for (index, child) in self.children.iter_mut().enumerate().skip(1) {
 
+            // Indices are activated in increasing order, possibly including the subgraph's own index zero.
+            let mut scheduled = false;
+            while position < self.activated.len() && self.activated[position] <= index {
+                scheduled = scheduled || self.activated[position] == index;
+                position += 1;
+            }
+
             // NOTE: It is *hugely* important that at this moment the pointstamp updates reflect any
             //       and all messages counts produced by the child, as this call will signal that they
             //       have been acknowledged by this `Subgraph`. We could make this more explicit, but
@@ -535,36 +775,124 @@ impl<TOuter: Timestamp, TInner: Timestamp> Operate<TOuter> for Subgraph<TOuter,
             //     message_buffer,
             //     internal_buffer);
 
-            let (targets, sources, pushed) = self.pointstamp_tracker.node_state(index);
+            // Children that are neither activated nor informed of progress are not visited.
+            if !scheduled && child.pushed_buffer.iter_mut().all(|x| x.is_empty()) {
+                any_child_active = any_child_active || child.active;
+                continue;
+            }
 
             let child_active = child.exchange_progress(
-                pushed,
-                targets,
-                sources,
+                scheduled,
+                self.pointstamp_tracker.target(index),
+                self.pointstamp_tracker.source(index),
                 message_buffer,
                 internal_buffer);
 
             any_child_active = any_child_active || child_active;
         }
+        self.activated.clear();
 
-        // Step 6. Child zero's frontier information are reported as capabilities via `internal`.
-        for (output, pointstamps) in self.pointstamp_tracker.pushed_mut(0).iter_mut().enumerate() {
-            let iterator = pointstamps.drain().map(|(time, diff)| (time.outer, diff));
-            self.output_capabilities[output].update_iter_and(iterator, |t, v| {
-                internal[output].update(t.clone(), v);
-            });
-        }
+        // Step 6. Changes to the capabilities implied at our outputs are reported via `internal`.
+        self.report_output_changes(internal);
 
         // This does not *need* to be true, in that we hope that it is possible to execute correctly
         // even when we leave some pointstamp data behind. In the current implementation, where we
         // propagate all updates and then process each child, all updates should be consumed.
-        debug_assert!(self.pointstamp_tracker.is_empty());
+        debug_assert!(self.pointstamp_tracker.pushed().is_empty());
+
+        // Updates from children are only exchanged in the next call, which we must request.
+        if self.has_updates() {
+            self.activations.borrow_mut().activate(&self.path[..]);
+        }
 
         // Report activity if any child does, or our pointstamp tracker is tracking something.
         any_child_active || self.pointstamp_tracker.tracking_anything()
     }
 }
 
+impl<TOuter: Timestamp, TInner: Timestamp> Subgraph<TOuter, TInner> {
+    /// The number of operators in the subgraph, each nested subgraph counting as one operator.
+    pub fn operators(&self) -> usize {
+        self.children.len() - 1
+    }
+
+    /// The times at which the operators of the subgraph may yet produce data.
+    ///
+    /// This is the lower bound of the frontiers at the inputs of the operators, which their probes
+    /// observe, and at the outputs of the subgraph, together with the capabilities held by operators
+    /// whose outputs are not connected, as of the most recent call to `pull_internal_progress`.
+    pub fn frontier(&mut self) -> Antichain<Product<TOuter, TInner>> {
+        let mut frontier = Antichain::new();
+        for index in 0 .. self.children.len() {
+            for antichain in self.pointstamp_tracker.implied(index).iter() {
+                for time in antichain.frontier().iter() { frontier.insert(time.clone()); }
+            }
+            for antichain in self.pointstamp_tracker.source(index).iter() {
+                for time in antichain.frontier().iter() { frontier.insert(time.clone()); }
+            }
+        }
+        frontier
+    }
+
+    // Records the changes to the capabilities implied at the outputs by changes to the frontiers of the
+    // pointstamps of children, as of the most recent propagation. Counts are not used directly, as
+    // those at a location may be transiently negative, for example when an operator reports consuming
+    // a message before the nested scope that sent it reports producing it, and must not cancel the
+    // pointstamps at other locations.
+    fn record_output_changes(&mut self) {
+        for ((source, time), diff) in self.pointstamp_tracker.source_frontier_changes().drain() {
+            if source.index > 0 {
+                update_reach(&self.source_reach[source.index][source.port], &time, diff, &mut self.output_changes);
+            }
+        }
+        for ((target, time), diff) in self.pointstamp_tracker.target_frontier_changes().drain() {
+            if target.index > 0 {
+                update_reach(&self.target_reach[target.index][target.port], &time, diff, &mut self.output_changes);
+            }
+        }
+    }
+
+    // Applies changes to the capabilities implied at the outputs, reporting changes to their frontiers.
+    fn report_output_changes(&mut self, reports: &mut [ChangeBatch<TOuter>]) {
+        for (output, changes) in self.output_changes.iter_mut().enumerate() {
+            let report = &mut reports[output];
+            let iterator = changes.drain().map(|(time, diff)| (time.outer, diff));
+            self.output_capabilities[output].update_iter_and(iterator, |t, v| {
+                report.update(t.clone(), v);
+            });
+        }
+    }
+
+    /// Indicates whether any pointstamp updates remain to be exchanged or applied.
+    fn has_updates(&mut self) -> bool {
+        !self.local_pointstamp_messages.is_empty() ||
+        !self.local_pointstamp_internal.is_empty() ||
+        !self.final_pointstamp_messages.is_empty() ||
+        !self.final_pointstamp_internal.is_empty()
+    }
+}
+
+// Records the changes to the capabilities at the subgraph outputs implied by a change to the frontier
+// at a location whose reach to the outputs is `reach`.
+fn update_reach<T: Timestamp>(reach: &[(usize, Antichain<T::Summary>)], time: &T, diff: i64, changes: &mut [ChangeBatch<T>]) {
+    for &(output, ref summaries) in reach.iter() {
+        for summary in summaries.elements().iter() {
+            if let Some(time) = summary.results_in(time) {
+                changes[output].update(time, diff);
+            }
+        }
+    }
+}
+
+// The times in the frontier of `antichain`, with their counts.
+fn counts<T: Timestamp>(antichain: &MutableAntichain<T>) -> Vec<(T, i64)> {
+    antichain.frontier().iter().map(|time| (time.clone(), antichain.count_for(time))).collect()
+}
+
+// The times in the frontier of `antichain`, with their counts, formatted for introspection.
+fn describe_counts<T: Timestamp>(antichain: &MutableAntichain<T>) -> Vec<(String, i64)> {
+    counts(antichain).into_iter().map(|(time, count)| (format!("{:?}", time), count)).collect()
+}
 
 struct PerOperatorState<T: Timestamp> {
 
@@ -575,11 +903,12 @@ struct PerOperatorState<T: Timestamp> {
 
     local: bool,        // indicates whether the operator will exchange data or not
     notify: bool,
+    feedback: bool,     // indicates that the operator is the feedback operator of a loop variable.
 
     inputs: usize,      // number of inputs to the operator
     outputs: usize,     // number of outputs from the operator
 
-    recently_active: bool,
+    active: bool,       // the operator reported unfinished work when last run.
 
     operator: Option<Box<Operate<T>>>,
 
@@ -592,10 +921,13 @@ struct PerOperatorState<T: Timestamp> {
     produced_buffer: Vec<ChangeBatch<T>>, // per-output: temp buffer used for pull_internal_progress.
 
     external_buffer: Vec<ChangeBatch<T>>, // per-input: temp buffer used for push_external_progress.
+    pushed_buffer: Vec<ChangeBatch<T>>,   // per-input: changes to implied input frontiers, to filter through `external`.
 
     gis_capabilities: Vec<ChangeBatch<T>>,
     gis_summary: Vec<Vec<Antichain<T::Summary>>>,   // cached result from get_internal_summary.
 
+    checks: Option<ProgressChecks<T>>,  // validates reported progress, if the worker checks progress.
+
     logging: Option<Logger>,
 }
 
@@ -605,6 +937,7 @@ impl<T: Timestamp> PerOperatorState<T> {
         self.inputs += 1;
         self.external.push(Default::default());
         self.external_buffer.push(ChangeBatch::new());
+        self.pushed_buffer.push(ChangeBatch::new());
         self.consumed_buffer.push(ChangeBatch::new());
     }
     fn add_output(&mut self) {
@@ -626,12 +959,14 @@ impl<T: Timestamp> PerOperatorState<T> {
             inputs:     0,
             outputs:    0,
 
-            recently_active: true,
+            active:     true,
             notify:     true,
+            feedback:   false,
 
             edges: Vec::new(),
             external: Vec::new(),
             external_buffer: Vec::new(),
+            pushed_buffer: Vec::new(),
             consumed_buffer: Vec::new(),
             internal_buffer: Vec::new(),
             produced_buffer: Vec::new(),
@@ -640,6 +975,8 @@ impl<T: Timestamp> PerOperatorState<T> {
 
             gis_capabilities: Vec::new(),
             gis_summary: Vec::new(),
+
+            checks: None,
         }
     }
 
@@ -649,6 +986,7 @@ impl<T: Timestamp> PerOperatorState<T> {
         let inputs = scope.inputs();
         let outputs = scope.outputs();
         let notify = scope.notify_me();
+        let feedback = scope.feedback();
 
         let (gis_summary, gis_capabilities) = scope.get_internal_summary();
 
@@ -665,12 +1003,14 @@ impl<T: Timestamp> PerOperatorState<T> {
             outputs,
             edges:              vec![vec![]; outputs],
 
-            recently_active:    true,
+            active:             true,
             notify,
+            feedback,
 
             external:           vec![Default::default(); inputs],
 
             external_buffer:    vec![ChangeBatch::new(); inputs],
+            pushed_buffer:      vec![ChangeBatch::new(); inputs],
 
             consumed_buffer:    vec![ChangeBatch::new(); inputs],
             internal_buffer:    vec![ChangeBatch::new(); outputs],
@@ -680,6 +1020,8 @@ impl<T: Timestamp> PerOperatorState<T> {
 
             gis_capabilities,
             gis_summary,
+
+            checks: None,
         }
     }
 
@@ -712,8 +1054,8 @@ impl<T: Timestamp> PerOperatorState<T> {
 
     pub fn exchange_progress(
         &mut self,
-        external_progress: &mut [ChangeBatch<T>],       // changes to external capabilities on operator inputs.
-        _outstanding_messages: &[MutableAntichain<T>],   // the reported outstanding messages to the operator.
+        activated: bool,                                // indicates that the operator has been activated.
+        _outstanding_messages: &[MutableAntichain<T>],  // the reported outstanding messages to the operator.
         internal_capabilities: &[MutableAntichain<T>],  // the reported internal capabilities of the operator.
         pointstamp_messages: &mut ChangeBatch<(usize, usize, T)>,
         pointstamp_internal: &mut ChangeBatch<(usize, usize, T)>,
@@ -723,55 +1065,29 @@ impl<T: Timestamp> PerOperatorState<T> {
 
             // We must filter the changes through a `MutableAntichain` to determine discrete changes in the
             // input capabilities, given all pre-existing updates accepted and communicated.
-            for (input, updates) in external_progress.iter_mut().enumerate() {
+            for (input, updates) in self.pushed_buffer.iter_mut().enumerate() {
                 let buffer = &mut self.external_buffer[input];
                 self.external[input].update_iter_and(updates.drain(), |time, val| {
                     buffer.update(time.clone(), val);
                 });
             }
 
-            // At this point we can assemble several signals to determine if we can possibly not schedule
-            // the operator. At the moment we take what I hope is a conservative approach in which any of
-            // the following will require an operator to be rescheduled:
+            // An operator is scheduled if it has been activated, or if there are post-filter changes to its
+            // input frontiers and `self.notify` is true. Operators are activated by data arriving on their
+            // inputs, which includes data whose progress information has not yet arrived, and otherwise by
+            // requests made through an `Activator`.
             //
-            //   1. There are any post-filter progress changes to communicate and self.notify is true, or
-            //   2. The operator performed progress updates in its last execution or reported activity, or
-            //   3. There exist outstanding input messages on any input, or
-            //   4. There exist held internal capabilities on any output.
+            // Frontier changes are important because any operator could respond arbitrarily to them, with
+            // the most obvious example being the `probe` operator. Not invoking this call on a probe operator
+            // can spin-block the computation, which is clearly a disaster.
             //
-            // The first reason is important because any operator could respond arbitrarily to progress
-            // updates, with the most obvious example being the `probe` operator. Not invoking this call
-            // on a probe operator can currently spin-block the computation, which is clearly a disaster.
-            //
-            // The second reason is due to the implicit communication that calling `push_external_progress`
-            // indicates a receipt of sent messages. Even with no change in capabilities, if they are empty
-            // and the messages are now received this can unblock an operator. Furthermore, if an operator
-            // reports that it is active despite the absence of messages and capabilities, then we must
-            // reschedule it due to a lack of insight as to whether it can run or not (consider: subgraphs
-            // with internal messages or capabilities).
-            //
-            // The third reason is that the operator could plausibly receive a input data. If there are no
-            // outstanding input messages, then while the operator *could* receive input data, the progress
-            // information announcing the message's existence hasn't arrived yet, but soon will. It is safe
-            // to await this progress information.
-            //
-            // The fourth reason is that operators holding capabilities can decide to exert or drop them for
-            // any reason, perhaps just based on the number of times they have been called. In the absence
-            // of any restriction on what would unblock them, we need to continually poll them.
-            // NOTE: We could consider changing this to: operators that may unblock arbitrarily must express
-            // activity, removing "holds capability" as a reason to schedule an operator. This may be fairly
-            // easy to get wrong (for the operator implementor) and we should be careful here.
+            // Operators holding capabilities are not scheduled on that account alone. An operator that could
+            // make progress without new data or frontier changes, for example by polling a source outside of
+            // timely, must activate itself when it wants to be scheduled again.
 
             let any_progress_updates = self.external_buffer.iter_mut().any(|buffer| !buffer.is_empty()) && self.notify;
-            let _was_recently_active = self.recently_active;
-            let _outstanding_messages = _outstanding_messages.iter().any(|chain| !chain.is_empty());
-            let _held_capabilities = internal_capabilities.iter().any(|chain| !chain.is_empty());
 
-            // TODO: This is reasonable, in principle, but `_outstanding_messages` determined from pointstamps
-            //       alone leaves us in a weird state should progress messages get blocked by non-execution of
-            //       e.g. the exchange operator in the exchange.rs example.
-
-            if any_progress_updates || _was_recently_active || _outstanding_messages || _held_capabilities
+            if activated || any_progress_updates
             {
 
                 let self_id = self.id;  // avoid capturing `self` in logging closures.
@@ -791,7 +1107,6 @@ impl<T: Timestamp> PerOperatorState<T> {
                     println!("External progress updates not consumed by {:?}", self.name);
                 }
                 debug_assert!(!self.external_buffer.iter_mut().any(|x| !x.is_empty()));
-                debug_assert!(external_progress.iter_mut().all(|x| x.is_empty()));
 
                 self.logging.as_mut().map(|l| l.log(::logging::ScheduleEvent {
                     id: self_id, start_stop: ::logging::StartStop::Start
@@ -808,6 +1123,10 @@ impl<T: Timestamp> PerOperatorState<T> {
                         &mut self.produced_buffer[..],
                     );
 
+                if let Some(ref mut checks) = self.checks {
+                    checks.validate(&self.name, &mut self.consumed_buffer, &mut self.internal_buffer, &mut self.produced_buffer);
+                }
+
                 // Scan reported changes, propagate as appropriate.
                 let mut did_work = false;
                 for output in 0 .. self.outputs {
@@ -830,8 +1149,8 @@ impl<T: Timestamp> PerOperatorState<T> {
                     }
                 }
 
-                // The operator was recently active if it did anything, or reports activity.
-                self.recently_active = did_work || internal_activity;
+                // The operator remains active as long as it reports activity.
+                self.active = internal_activity;
 
                 self.logging.as_mut().map(|l|
                     l.log(::logging::ScheduleEvent {
@@ -842,60 +1161,24 @@ impl<T: Timestamp> PerOperatorState<T> {
                 internal_activity
             }
             else {
-                // Active operators should always be scheduled, and should re-assert their activity if
-                // they want to be scheduled again. If we are here, it is because the operator declined
-                // to express activity explicitly.
-                false
+                // The operator has not run, and its reported activity is unchanged.
+                self.active
             }
         }
         else {
 
             // If the operator is closed and we are reporting progress at it, something has surely gone wrong.
-            if !external_progress.iter_mut().all(|x| x.is_empty()) {
+            if !self.pushed_buffer.iter_mut().all(|x| x.is_empty()) {
                 println!("Operator prematurely shut down: {}", self.name);
                 println!("  {:?}", self.notify);
-                println!("  {:?}", external_progress);
+                println!("  {:?}", self.pushed_buffer);
             }
-            assert!(external_progress.iter_mut().all(|x| x.is_empty()));
+            assert!(self.pushed_buffer.iter_mut().all(|x| x.is_empty()));
 
             // A closed operator shouldn't keep anything open.
             false
         };
 
-        // DEBUG: test validity of updates.
-        // TODO: This test is overly pessimistic for current implementations, which may report they acquire
-        // capabilities based on the receipt of messages they cannot express (e.g. messages internal to a
-        // subgraph). This suggests a weakness in the progress tracking protocol, that it is hard to validate
-        // locally, which we could aim to improve.
-        // #[cfg(debug_assertions)]
-        // {
-        //     // 1. each increment to self.internal_buffer needs to correspond to a positive self.consumed_buffer
-        //     for index in 0 .. self.internal_buffer.len() {
-        //         for change in self.internal_buffer[index].iter() {
-        //             if change.1 > 0 {
-        //                 let consumed = self.consumed_buffer.iter_mut().any(|x| x.iter().any(|y| y.1 > 0 && y.0.less_equal(&change.0)));
-        //                 let internal = self.internal_capabilities[index].less_equal(&change.0);
-        //                 if !consumed && !internal {
-        //                     panic!("Progress error; internal {:?}: {:?}", self.name, change);
-        //                 }
-        //             }
-        //         }
-        //     }
-        //     // 2. each produced message must correspond to a held capability or consumed message
-        //     for index in 0 .. self.produced_buffer.len() {
-        //         for change in self.produced_buffer[index].iter() {
-        //             if change.1 > 0 {
-        //                 let consumed = self.consumed_buffer.iter_mut().any(|x| x.iter().any(|y| y.1 > 0 && y.0.less_equal(&change.0)));
-        //                 let internal = self.internal_capabilities[index].less_equal(&change.0);
-        //                 if !consumed && !internal {
-        //                     panic!("Progress error; produced {:?}: {:?}", self.name, change);
-        //                 }
-        //             }
-        //         }
-        //     }
-        // }
-        // DEBUG: end validity test.
-
         // We can shut down the operator if several conditions are met.
         //
         // We look for operators that (i) still exist, (ii) report no activity, (iii) will no longer
@@ -911,3 +1194,81 @@ impl<T: Timestamp> PerOperatorState<T> {
         active
     }
 }
+
+// Validates the progress reported by a child, naming it and the offending times in panics.
+struct ProgressChecks<T: Timestamp> {
+    address: Vec<usize>,            // the path from the worker to the child.
+    local: bool,                    // the child reports only its own updates, rather than exchanged updates.
+    held: Vec<HashMap<T, i64>>,     // per-output: the counts of capabilities held by the child.
+}
+
+impl<T: Timestamp> ProgressChecks<T> {
+
+    fn new(address: Vec<usize>, local: bool, capabilities: &mut [ChangeBatch<T>]) -> Self {
+        let held = capabilities.iter_mut().map(|batch| batch.iter().cloned().collect()).collect();
+        ProgressChecks { address, local, held }
+    }
+
+    // Checks that capabilities are acquired and messages produced only at times the child holds a
+    // capability for, or has just consumed a message at, and that it drops only capabilities it holds.
+    //
+    // The reports of children that are not local are assembled from the reports of other workers,
+    // and only the counts of their capabilities can be checked.
+    fn validate(&mut self, name: &str, consumed: &mut [ChangeBatch<T>], internal: &mut [ChangeBatch<T>], produced: &mut [ChangeBatch<T>]) {
+
+        if self.local {
+            for output in 0 .. self.held.len() {
+                let held = &self.held[output];
+                let acquired = internal[output].iter().filter(|&&(_, delta)| delta > 0).map(|&(ref time, _)| time.clone()).collect::<Vec<_>>();
+                let mut covered = |time: &T, acquiring: bool| {
+                    held.iter().any(|(held, &count)| count > 0 && held.less_equal(time)) ||
+                    (!acquiring && acquired.iter().any(|acquired| acquired.less_equal(time))) ||
+                    consumed.iter_mut().any(|batch| batch.iter().any(|&(ref consumed, delta)| delta > 0 && consumed.less_equal(time)))
+                };
+                for time in acquired.iter() {
+                    if !covered(time, true) {
+                        panic!("progress check failed: operator {} at {:?} acquired a capability at {:?} for output {} without holding a capability or consuming a message at or before that time", name, self.address, time, output);
+                    }
+                }
+                for &(ref time, delta) in produced[output].iter() {
+                    if delta > 0 && !covered(time, false) {
+                        panic!("progress check failed: operator {} at {:?} sent messages at {:?} from output {} without holding a capability or consuming a message at or before that time", name, self.address, time, output);
+                    }
+                }
+            }
+        }
+
+        for output in 0 .. self.held.len() {
+            for &(ref time, delta) in internal[output].iter() {
+                let count = {
+                    let count = self.held[output].entry(time.clone()).or_insert(0);
+                    *count += delta;
+                    *count
+                };
+                if count < 0 {
+                    panic!("progress check failed: operator {} at {:?} dropped a capability at {:?} for output {} that it did not hold", name, self.address, time, output);
+                }
+                if count == 0 {
+                    self.held[output].remove(time);
+                }
+            }
+        }
+    }
+}
+
+// Updates the count of messages at input `port` of the child at `index`, checking that it does not
+// become negative, which would mean the child consumed messages that were never sent to it.
+fn check_messages<T: Timestamp>(counts: &mut HashMap<(usize, usize, T), i64>, name: &str, path: &[usize], index: usize, port: usize, time: T, delta: i64) {
+    let key = (index, port, time);
+    let count = {
+        let count = counts.entry(key.clone()).or_insert(0);
+        *count += delta;
+        *count
+    };
+    if count < 0 {
+        panic!("progress check failed: operator {} at {:?} consumed messages at {:?} on input {} that were never sent to it", name, [path, &[index]].concat(), key.2, port);
+    }
+    if count == 0 {
+        counts.remove(&key);
+    }
+}
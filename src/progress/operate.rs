@@ -1,8 +1,10 @@
 //! Methods which describe an operators topology, and the progress it makes.
 
 use std::default::Default;
+use std::any::Any;
 
 use progress::{Timestamp, ChangeBatch, Antichain};
+use progress::introspect::{OperatorSnapshot, Blocker};
 
 
 /// Methods for describing an operators topology, and the progress it makes.
@@ -82,7 +84,9 @@ pub trait Operate<T: Timestamp> {
     /// Retrieves a summary of progress statements internal to the operator.
     ///
     /// Returns a bool indicating if there is any unreported work remaining (e.g. work that doesn't
-    /// project on an output).
+    /// project on an output). This keeps the operator's dataflow alive, but does not cause the
+    /// operator to be scheduled again; operators that want to be rescheduled should request it
+    /// through an `Activator`.
     ///
     /// Note: not "internal to the operator and its peer group". The operator instance should only
     /// report progress performed by its own instance. The parent scope will figure out what to do
@@ -98,4 +102,21 @@ pub trait Operate<T: Timestamp> {
 
     /// Indicates of whether the operator requires `push_external_progress` information or not.
     fn notify_me(&self) -> bool { true }
+
+    /// Indicates that the operator is the feedback operator of a loop variable, whose input is
+    /// connected by `connect_loop`.
+    fn feedback(&self) -> bool { false }
+
+    /// Appends snapshots of the progress state of the operators within the operator.
+    ///
+    /// Scopes report each of their operators, and the operators within those; other operators
+    /// contain none, which the default implementation reflects.
+    fn snapshot_operators(&mut self, _snapshots: &mut Vec<OperatorSnapshot>) { }
+
+    /// Appends the pointstamps that hold the frontier at the inputs of the operator at `path` at
+    /// or before `time`.
+    ///
+    /// The operator at `path` must be within this operator, and `time` must have the timestamp
+    /// type of the scope containing it; otherwise nothing is appended.
+    fn find_blockers(&mut self, _path: &[usize], _time: &Any, _blockers: &mut Vec<Blocker>) { }
 }
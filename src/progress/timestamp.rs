@@ -13,6 +13,33 @@ use progress::nested::product::Product;
 use abomonation::Abomonation;
 
 /// A composite trait for types that serve as timestamps in timely dataflow.
+///
+/// Structs whose fields are timestamps can derive `Timestamp` and `PartialOrder` with the macros
+/// of the `timely_derive` crate. They are then ordered as the product of their fields, and their
+/// path summaries, named after the struct with a `Summary` suffix, summarize each field.
+///
+/// # Examples
+/// ```
+/// extern crate timely;
+/// extern crate abomonation;
+/// #[macro_use] extern crate abomonation_derive;
+/// #[macro_use] extern crate timely_derive;
+///
+/// use timely::order::PartialOrder;
+/// use timely::progress::timestamp::PathSummary;
+///
+/// #[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, PartialOrder, Timestamp)]
+/// pub struct Time { event: u64, version: u32 }
+///
+/// fn main() {
+///     let time = Time { event: 3, version: 1 };
+///     assert!(time.less_equal(&Time { event: 4, version: 1 }));
+///     assert!(!time.less_equal(&Time { event: 4, version: 0 }));
+///
+///     let summary = TimeSummary { event: 0, version: 1 };
+///     assert_eq!(summary.results_in(&time), Some(Time { event: 3, version: 2 }));
+/// }
+/// ```
 pub trait Timestamp: Clone+Eq+PartialOrder+Default+Debug+Send+Any+Abomonation+Hash+Ord {
     /// A type summarizing action on a timestamp along a dataflow path.
     type Summary : PathSummary<Self> + 'static;
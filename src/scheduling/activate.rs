@@ -0,0 +1,232 @@
+//! Activation of operators, as a request that they be scheduled.
+//!
+//! Operators are identified by their path, the sequence of scope-local indices leading from the
+//! worker to the operator. A scope schedules those of its children whose paths, or the paths of
+//! whose descendants, were activated before the current step began. Operators may also request
+//! activation at a future instant, which the worker's timers deliver once the instant has passed.
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::time::{Duration, Instant};
+
+/// Paths of operators activated for scheduling.
+///
+/// Activations requested during a step are held back until `advance` is called, at the start of
+/// the next step, after which they are visible through `for_extensions`. Activations requested
+/// for a future instant are held in a queue of timers, and become pending in the first call to
+/// `advance` once their instant has passed.
+///
+/// # Examples
+/// ```
+/// use timely::scheduling::Activations;
+///
+/// let mut activations = Activations::new();
+/// activations.activate(&[0, 1, 3]);
+/// activations.activate(&[0, 1, 2, 5]);
+/// activations.activate(&[0, 1, 3]);
+/// activations.activate(&[0, 4]);
+///
+/// // activations are not visible until advanced.
+/// let mut indices = Vec::new();
+/// activations.for_extensions(&[0, 1], |index| indices.push(index));
+/// assert!(indices.is_empty());
+///
+/// activations.advance();
+/// activations.for_extensions(&[0, 1], |index| indices.push(index));
+/// assert_eq!(indices, vec![2, 3]);
+///
+/// // timers are delivered once their instant has passed.
+/// let now = ::std::time::Instant::now();
+/// activations.activate_at(&[0, 1, 6], now);
+/// activations.activate_at(&[0, 1, 7], now + ::std::time::Duration::from_secs(60));
+/// activations.advance();
+/// indices.clear();
+/// activations.for_extensions(&[0, 1], |index| indices.push(index));
+/// assert_eq!(indices, vec![6]);
+/// assert!(activations.empty_for().unwrap() > ::std::time::Duration::from_secs(30));
+/// ```
+#[derive(Default)]
+pub struct Activations {
+    bounds: Vec<(usize, usize)>,    // pending activations, as ranges of `slices`.
+    slices: Vec<usize>,             // concatenated paths of pending activations.
+    current: Vec<(usize, usize)>,   // sorted, distinct activations, as ranges of `current_slices`.
+    current_slices: Vec<usize>,     // concatenated paths of current activations.
+    timers: BinaryHeap<Reverse<(Instant, Vec<usize>)>>,  // delayed activations, earliest first.
+}
+
+impl Activations {
+
+    /// Allocates a new empty set of activations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Activates the operator at `path`.
+    #[inline]
+    pub fn activate(&mut self, path: &[usize]) {
+        let lower = self.slices.len();
+        self.slices.extend_from_slice(path);
+        self.bounds.push((lower, self.slices.len()));
+    }
+
+    /// Activates the operator at `path` once `instant` has passed.
+    pub fn activate_at(&mut self, path: &[usize], instant: Instant) {
+        self.timers.push(Reverse((instant, path.to_vec())));
+    }
+
+    /// Activates the operator at `path` once `delay` has elapsed.
+    ///
+    /// A zero `delay` activates the operator immediately, as with `activate`.
+    pub fn activate_after(&mut self, path: &[usize], delay: Duration) {
+        if delay == Duration::new(0, 0) {
+            self.activate(path);
+        }
+        else {
+            self.activate_at(path, Instant::now() + delay);
+        }
+    }
+
+    /// Discards the pending activations and timers of the operator at `path` and its descendants.
+    ///
+    /// Activations already made current by `advance` are unaffected.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use timely::scheduling::Activations;
+    ///
+    /// let mut activations = Activations::new();
+    /// activations.activate(&[0, 1, 3]);
+    /// activations.activate(&[0, 2]);
+    /// activations.activate_after(&[0, 1, 4], Duration::from_secs(60));
+    ///
+    /// activations.discard(&[0, 1]);
+    /// activations.advance();
+    /// let mut indices = Vec::new();
+    /// activations.for_extensions(&[0], |index| indices.push(index));
+    /// assert_eq!(indices, vec![2]);
+    /// assert_eq!(activations.empty_for(), None);
+    /// ```
+    pub fn discard(&mut self, path: &[usize]) {
+        let slices = &self.slices;
+        self.bounds.retain(|&(lower, upper)| !slices[lower .. upper].starts_with(path));
+        if self.timers.iter().any(|timer| ((timer.0).1).starts_with(path)) {
+            let timers = ::std::mem::replace(&mut self.timers, BinaryHeap::new());
+            self.timers = timers.into_iter().filter(|timer| !((timer.0).1).starts_with(path)).collect();
+        }
+    }
+
+    /// Makes pending activations current, discarding the previously current activations.
+    ///
+    /// Timers whose instants have passed are first made pending.
+    pub fn advance(&mut self) {
+
+        if !self.timers.is_empty() {
+            let now = Instant::now();
+            while self.timers.peek().map(|timer| (timer.0).0 <= now).unwrap_or(false) {
+                let Reverse((_instant, path)) = self.timers.pop().expect("peeked timer absent");
+                self.activate(&path[..]);
+            }
+        }
+
+        let slices = &self.slices;
+        self.bounds.sort_by(|x, y| slices[x.0 .. x.1].cmp(&slices[y.0 .. y.1]));
+        self.bounds.dedup_by(|x, y| slices[x.0 .. x.1] == slices[y.0 .. y.1]);
+
+        self.current.clear();
+        self.current_slices.clear();
+        for &(lower, upper) in self.bounds.iter() {
+            let start = self.current_slices.len();
+            self.current_slices.extend_from_slice(&slices[lower .. upper]);
+            self.current.push((start, self.current_slices.len()));
+        }
+
+        self.bounds.clear();
+        self.slices.clear();
+    }
+
+    /// Calls `action` with each distinct index following `path` in a current activation.
+    ///
+    /// The indices identify the children of the scope at `path` that were activated, either
+    /// themselves or through one of their descendants, and are reported in increasing order.
+    pub fn for_extensions<F: FnMut(usize)>(&self, path: &[usize], mut action: F) {
+
+        let slices = &self.current_slices;
+        let position =
+        self.current
+            .binary_search_by(|x| slices[x.0 .. x.1].cmp(path))
+            .unwrap_or_else(|x| x);
+
+        let mut previous = None;
+        for &(lower, upper) in self.current[position ..].iter() {
+            let slice = &slices[lower .. upper];
+            if !slice.starts_with(path) { break; }
+            if slice.len() > path.len() && previous != Some(slice[path.len()]) {
+                previous = Some(slice[path.len()]);
+                action(slice[path.len()]);
+            }
+        }
+    }
+
+    /// Indicates that no activations are pending.
+    ///
+    /// Timers whose instants have not yet been reached by a call to `advance` are not pending.
+    pub fn is_idle(&self) -> bool {
+        self.bounds.is_empty()
+    }
+
+    /// The time until an activation will be pending, or `None` if no activations are pending or
+    /// scheduled.
+    ///
+    /// This is zero if activations are pending, or if a timer is due, and otherwise the time
+    /// remaining until the earliest timer is due.
+    pub fn empty_for(&self) -> Option<Duration> {
+        if !self.bounds.is_empty() {
+            Some(Duration::new(0, 0))
+        }
+        else {
+            self.timers.peek().map(|timer| {
+                let now = Instant::now();
+                let instant = (timer.0).0;
+                if instant > now { instant - now } else { Duration::new(0, 0) }
+            })
+        }
+    }
+}
+
+/// A handle through which an operator can request that it be scheduled.
+///
+/// Activators may be obtained in operator constructors, and moved into the operator logic so
+/// that it can request to be run again, for example when it has more output to produce.
+#[derive(Clone)]
+pub struct Activator {
+    path: Vec<usize>,
+    queue: Rc<RefCell<Activations>>,
+}
+
+impl Activator {
+    /// Creates a new activator for the operator at `path`.
+    pub fn new(path: &[usize], queue: Rc<RefCell<Activations>>) -> Self {
+        Activator {
+            path: path.to_vec(),
+            queue,
+        }
+    }
+
+    /// Requests that the operator be scheduled in the worker's next step.
+    pub fn activate(&self) {
+        self.queue.borrow_mut().activate(&self.path[..]);
+    }
+
+    /// Requests that the operator be scheduled in the first step after `delay` has elapsed.
+    pub fn activate_after(&self, delay: Duration) {
+        self.queue.borrow_mut().activate_after(&self.path[..], delay);
+    }
+
+    /// Requests that the operator be scheduled in the first step after `instant` has passed.
+    pub fn activate_at(&self, instant: Instant) {
+        self.queue.borrow_mut().activate_at(&self.path[..], instant);
+    }
+}
@@ -0,0 +1,5 @@
+//! Types to request and track the scheduling of operators.
+
+pub use self::activate::{Activations, Activator};
+
+pub mod activate;
@@ -2,14 +2,16 @@
 
 use std::rc::Rc;
 use std::cell::RefCell;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use std::collections::VecDeque;
 
 use ::{communication::Allocate, ExchangeData};
 use worker::Worker;
+use dataflow::Scope;
 use dataflow::channels::pact::Exchange;
 use dataflow::operators::generic::operator::source;
 use dataflow::operators::generic::operator::Operator;
+use scheduling::Activator;
 
 /// Orders elements inserted across all workers.
 ///
@@ -18,6 +20,7 @@ use dataflow::operators::generic::operator::Operator;
 pub struct Sequencer<T> {
     send: Rc<RefCell<VecDeque<T>>>, // proposed items.
     recv: Rc<RefCell<VecDeque<T>>>, // sequenced items.
+    activator: Activator,           // activates the source of proposed items.
 }
 
 impl<T: Ord+ExchangeData> Sequencer<T> {
@@ -27,6 +30,8 @@ impl<T: Ord+ExchangeData> Sequencer<T> {
     /// The `timer` instant is used to synchronize the workers, who use this
     /// elapsed time as their timestamp. Elements are ordered by this time,
     /// and cannot be made visible until all workers have reached the time.
+    /// Each worker advances its time when it proposes elements, and otherwise
+    /// every millisecond.
     pub fn new<A: Allocate>(worker: &mut Worker<A>, timer: Instant) -> Self {
 
         let send: Rc<RefCell<VecDeque<T>>> = Rc::new(RefCell::new(VecDeque::new()));
@@ -35,18 +40,26 @@ impl<T: Ord+ExchangeData> Sequencer<T> {
         let recv_weak = Rc::downgrade(&recv);
 
         // build a dataflow used to serialize and circulate commands
-        worker.dataflow(move |dataflow| {
+        let activator = worker.dataflow(move |dataflow| {
 
             let peers = dataflow.peers();
             let mut recvd = Vec::new();
             let mut vector = Vec::new();
 
+            let scope = dataflow.clone();
+            let mut activator = None;
+
             // a source that attempts to pull from `recv` and produce commands for everyone
-            source(dataflow, "SequenceInput", move |capability| {
+            let stream = source(dataflow, "SequenceInput", |capability, info| {
 
                 // so we can drop, if input queue vanishes.
                 let mut capability = Some(capability);
 
+                // the operator advances its capability with the elapsed time, and so must be
+                // rescheduled periodically as long as it holds the capability.
+                let source_activator = scope.activator_for(&info.address[..]);
+                activator = Some(source_activator.clone());
+
                 // closure broadcasts any commands it grabs.
                 move |output| {
 
@@ -68,13 +81,16 @@ impl<T: Ord+ExchangeData> Sequencer<T> {
                                 session.give((worker_index, element.clone()));
                             }
                         }
+
+                        source_activator.activate_after(Duration::from_millis(1));
                     }
                     else {
                         capability = None;
                     }
                 }
-            })
-            .sink(
+            });
+
+            stream.sink(
                 Exchange::new(|x: &(usize, T)| x.0 as u64),
                 "SequenceOutput",
                 move |input| {
@@ -98,14 +114,17 @@ impl<T: Ord+ExchangeData> Sequencer<T> {
                     }
                 }
             );
+
+            activator.expect("source constructor not invoked")
         });
 
-        Sequencer { send, recv, }
+        Sequencer { send, recv, activator }
     }
 
     /// Adds an element to the shared log.
     pub fn push(&mut self, element: T) {
         self.send.borrow_mut().push_back(element);
+        self.activator.activate();
     }
 
     /// Reads the next element from the shared log.
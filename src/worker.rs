@@ -3,11 +3,21 @@
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::any::Any;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
 
 use progress::timestamp::RootTimestamp;
-use progress::{Timestamp, Operate, SubgraphBuilder};
+use progress::nested::product::Product;
+use progress::frontier::Antichain;
+use progress::{Timestamp, Operate, Subgraph, SubgraphBuilder};
+use progress::introspect::{OperatorSnapshot, Blocker};
+use progress::broadcast::{ProgressMode, ProgressBatching};
 use communication::{Allocate, Data, Push, Pull};
 use dataflow::scopes::Child;
+use dataflow::ProbeHandle;
+use scheduling::Activations;
 
 /// A `Worker` is the entry point to a timely dataflow computation. It wraps a `Allocate`,
 /// and has a list of dataflows that it manages.
@@ -17,6 +27,14 @@ pub struct Worker<A: Allocate> {
     dataflows: Rc<RefCell<Vec<Wrapper>>>,
     dataflow_counter: Rc<RefCell<usize>>,
     logging: Rc<RefCell<::logging_core::Registry<::logging::WorkerIdentifier>>>,
+    activations: Rc<RefCell<Activations>>,
+    paths: Rc<RefCell<HashMap<usize, Vec<usize>>>>,
+    events: Rc<RefCell<Vec<usize>>>,
+    drops: Rc<RefCell<DropRequests>>,
+    stall_timeout: Rc<RefCell<Option<Duration>>>,
+    progress_mode: Rc<RefCell<ProgressMode>>,
+    progress_batching: Rc<RefCell<ProgressBatching>>,
+    progress_checking: Rc<RefCell<bool>>,
 }
 
 /// Methods provided by the root Worker.
@@ -29,6 +47,17 @@ pub trait AsWorker: Allocate {
     fn log_register(&self) -> ::std::cell::RefMut<::logging_core::Registry<::logging::WorkerIdentifier>>;
     /// Provides access to the timely logging stream.
     fn logging(&self) -> Option<::logging::TimelyLogger> { self.log_register().get("timely") }
+    /// Provides access to the activations of the worker's operators.
+    fn activations(&self) -> Rc<RefCell<Activations>>;
+    /// Records that data arriving from other workers on channel `identifier` are read by the
+    /// operator at `path`, which is then activated.
+    fn bind_channel(&mut self, identifier: usize, path: &[usize]);
+    /// How dataflows under construction exchange progress updates among workers.
+    fn progress_mode(&self) -> ProgressMode;
+    /// When dataflows under construction send progress updates to other workers.
+    fn progress_batching(&self) -> ProgressBatching;
+    /// Indicates that dataflows under construction validate the progress their operators report.
+    fn progress_checking(&self) -> bool;
 }
 
 impl<A: Allocate> AsWorker for Worker<A> {
@@ -36,38 +65,110 @@ impl<A: Allocate> AsWorker for Worker<A> {
     fn log_register(&self) -> ::std::cell::RefMut<::logging_core::Registry<::logging::WorkerIdentifier>> {
         self.log_register()
     }
+    fn activations(&self) -> Rc<RefCell<Activations>> { self.activations.clone() }
+    fn bind_channel(&mut self, identifier: usize, path: &[usize]) {
+        self.paths.borrow_mut().insert(identifier, path.to_vec());
+    }
+    fn progress_mode(&self) -> ProgressMode { *self.progress_mode.borrow() }
+    fn progress_batching(&self) -> ProgressBatching { *self.progress_batching.borrow() }
+    fn progress_checking(&self) -> bool { *self.progress_checking.borrow() }
 }
 
 impl<A: Allocate> Worker<A> {
     /// Allocates a new `Worker` bound to a channel allocator.
-    pub fn new(c: A) -> Worker<A> {
-        let now = ::std::time::Instant::now();
+    pub fn new(mut c: A) -> Worker<A> {
+        let now = Instant::now();
         let index = c.index();
+        // Channel zero carries requests to drop dataflows; dataflows use identifiers from one on.
+        let drops = DropRequests::new(&mut c, 0);
         Worker {
             allocator: Rc::new(RefCell::new(c)),
-            identifiers: Rc::new(RefCell::new(0)),
+            identifiers: Rc::new(RefCell::new(1)),
             dataflows: Rc::new(RefCell::new(Vec::new())),
             dataflow_counter: Rc::new(RefCell::new(0)),
             logging: Rc::new(RefCell::new(::logging_core::Registry::new(now, index))),
+            activations: Rc::new(RefCell::new(Activations::new())),
+            paths: Rc::new(RefCell::new(HashMap::new())),
+            events: Rc::new(RefCell::new(Vec::new())),
+            drops: Rc::new(RefCell::new(drops)),
+            stall_timeout: Rc::new(RefCell::new(None)),
+            progress_mode: Rc::new(RefCell::new(ProgressMode::Flat)),
+            progress_batching: Rc::new(RefCell::new(ProgressBatching::Immediate)),
+            progress_checking: Rc::new(RefCell::new(false)),
         }
     }
 
     /// Performs one step of the computation.
     ///
-    /// A step gives each dataflow operator a chance to run, and is the
-    /// main way to ensure that a computation proceeds.
+    /// A step gives each activated dataflow operator a chance to run, and is the
+    /// main way to ensure that a computation proceeds. Operators are activated by
+    /// data arriving on their inputs, by changes to their input frontiers, and by
+    /// requests made through an `Activator`, in the preceding step or since then,
+    /// including requests for activation at instants that have now passed.
+    /// Dataflows that have completed are dropped, and the resources of their
+    /// channels released.
+    ///
+    /// If the allocator is backpressured, because data for other workers is queued beyond its
+    /// budget, operators are not scheduled; the step only moves data, and reports the worker as
+    /// active so that callers continue to step.
     pub fn step(&mut self) -> bool {
 
         self.allocator.borrow_mut().pre_work();
 
-        let mut active = false;
-        for dataflow in self.dataflows.borrow_mut().iter_mut() {
-            let sub_active = dataflow.step();
-            active = active || sub_active;
+        // Drop the dataflows that other workers have requested be dropped.
+        let requests = self.drops.borrow_mut().receive();
+        for index in requests {
+            self.drop_local(index);
         }
 
-        // discard completed dataflows.
-        self.dataflows.borrow_mut().retain(|dataflow| dataflow.active());
+        // Activate the operators reading from channels that received data.
+        {
+            let mut events = self.events.borrow_mut();
+            self.allocator.borrow_mut().drain_events(&mut events);
+            let paths = self.paths.borrow();
+            let mut activations = self.activations.borrow_mut();
+            for channel in events.drain(..) {
+                // Channels of dataflows not yet built have no path, and their operators are
+                // activated once built.
+                if let Some(path) = paths.get(&channel) {
+                    activations.activate(&path[..]);
+                }
+            }
+        }
+
+        let backpressured = self.allocator.borrow().backpressured();
+
+        let mut active = backpressured;
+        if !backpressured {
+            self.activations.borrow_mut().advance();
+
+            // Note which dataflows have activated operators, for `installed_dataflows`.
+            let mut scheduled = Vec::new();
+            let worker = self.allocator.borrow().index();
+            self.activations.borrow().for_extensions(&[worker], |index| scheduled.push(index));
+
+            for dataflow in self.dataflows.borrow_mut().iter_mut() {
+                dataflow.scheduled = scheduled.contains(&dataflow.index);
+                let sub_active = dataflow.step();
+                active = active || sub_active;
+            }
+
+            // discard completed dataflows, and release their channels.
+            let completed = {
+                let mut dataflows = self.dataflows.borrow_mut();
+                let (active, completed): (Vec<_>, Vec<_>) = dataflows.drain(..).partition(|dataflow| dataflow.active());
+                *dataflows = active;
+                completed
+            };
+            for dataflow in completed.iter() {
+                self.release_dataflow(dataflow.index, dataflow.identifiers.clone());
+            }
+
+            let stall_timeout = *self.stall_timeout.borrow();
+            if let Some(timeout) = stall_timeout {
+                self.check_stalls(timeout);
+            }
+        }
 
         // TODO(andreal) do we want to flush logs here?
         self.logging.borrow_mut().flush();
@@ -76,6 +177,162 @@ impl<A: Allocate> Worker<A> {
 
         active
     }
+
+    /// Performs one step of the computation, first parking the thread if the worker is idle.
+    ///
+    /// The worker is idle if no operator has been activated since its previous step. An idle
+    /// worker parks until data arrive for it, until the next timer requested through an
+    /// `Activator` is due, or until `duration` elapses if it is not `None`, and then steps.
+    /// Capabilities dropped outside of operators and input handles are not noticed by the
+    /// worker, which should then be driven with a timeout.
+    ///
+    /// Other threads can wake the worker by calling `unpark` on its `std::thread::Thread`, for
+    /// example after queueing data for it to introduce through an input handle.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Probe};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///     let probe = worker.dataflow::<u64,_,_>(|scope| {
+    ///         (0..10).to_stream(scope).probe()
+    ///     });
+    ///     while !probe.done() {
+    ///         worker.step_or_park(Some(::std::time::Duration::from_millis(10)));
+    ///     }
+    /// }).unwrap();
+    /// ```
+    pub fn step_or_park(&mut self, duration: Option<Duration>) -> bool {
+        if !self.allocator.borrow().backpressured() {
+            // park for at most the time until the next activation, if any is scheduled.
+            let mut delay = match (duration, self.activations.borrow().empty_for()) {
+                (Some(x), Some(y)) => Some(::std::cmp::min(x, y)),
+                (x, None) => x,
+                (None, y) => y,
+            };
+            // wake up to check for stalled dataflows.
+            if let Some(timeout) = *self.stall_timeout.borrow() {
+                delay = Some(delay.map(|delay| ::std::cmp::min(delay, timeout)).unwrap_or(timeout));
+            }
+            if delay != Some(Duration::new(0, 0)) {
+                self.allocator.borrow().await_events(delay);
+            }
+        }
+        self.step()
+    }
+
+    /// Sets the time after which a dataflow is reported as stalled, or disables reports if `None`.
+    ///
+    /// A dataflow is stalled if its frontier has not advanced and none of its operators have been
+    /// scheduled in the worker's steps for `timeout`. A stalled dataflow is reported once, until
+    /// it next makes progress, to standard error and as a `StallEvent` to the "timely" logger,
+    /// listing the operators holding capabilities and the earliest times at which they hold them.
+    /// Stalls are checked for as the worker steps, and `step_or_park` parks for at most `timeout`.
+    ///
+    /// Operators that activate themselves, for example to poll sources outside of timely, are
+    /// scheduled and so prevent their dataflows from being reported as stalled.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use timely::dataflow::operators::{ToStream, Probe};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///     worker.set_stall_timeout(Some(Duration::from_secs(10)));
+    ///     let probe = worker.dataflow::<u64,_,_>(|scope| {
+    ///         (0..10).to_stream(scope).probe()
+    ///     });
+    ///     while !probe.done() {
+    ///         worker.step_or_park(None);
+    ///     }
+    /// }).unwrap();
+    /// ```
+    pub fn set_stall_timeout(&mut self, timeout: Option<Duration>) {
+        *self.stall_timeout.borrow_mut() = timeout;
+    }
+
+    /// Sets how subsequently constructed dataflows exchange progress updates among workers.
+    ///
+    /// By default each worker broadcasts its updates to all workers, which for many workers is
+    /// most of the traffic between processes. In `ProgressMode::Hierarchical` the workers of each
+    /// process consolidate their updates and exchange them with other processes only once. All
+    /// workers must use the same mode for each dataflow.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::progress::broadcast::ProgressMode;
+    /// use timely::dataflow::operators::{ToStream, Exchange, Probe};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///     worker.set_progress_mode(ProgressMode::Hierarchical);
+    ///     let probe = worker.dataflow::<u64,_,_>(|scope| {
+    ///         (0..10).to_stream(scope).exchange(|x| *x).probe()
+    ///     });
+    ///     while !probe.done() {
+    ///         worker.step();
+    ///     }
+    /// }).unwrap();
+    /// ```
+    pub fn set_progress_mode(&mut self, mode: ProgressMode) {
+        *self.progress_mode.borrow_mut() = mode;
+    }
+
+    /// Sets when subsequently constructed dataflows send progress updates to other workers.
+    ///
+    /// By default updates are sent as soon as they are produced, which for dataflows that perform
+    /// many small steps means many small messages. Holding updates back for some steps or some time
+    /// lets them consolidate into fewer messages, at the cost of delaying the progress other workers
+    /// observe. Setting the policy before constructing each dataflow selects it per dataflow.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use timely::progress::broadcast::ProgressBatching;
+    /// use timely::dataflow::operators::{ToStream, Exchange, Probe};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///     worker.set_progress_batching(ProgressBatching::Delay(Duration::from_millis(1)));
+    ///     let probe = worker.dataflow::<u64,_,_>(|scope| {
+    ///         (0..10).to_stream(scope).exchange(|x| *x).probe()
+    ///     });
+    ///     worker.set_progress_batching(ProgressBatching::Immediate);
+    ///     while !probe.done() {
+    ///         worker.step();
+    ///     }
+    /// }).unwrap();
+    /// ```
+    pub fn set_progress_batching(&mut self, batching: ProgressBatching) {
+        *self.progress_batching.borrow_mut() = batching;
+    }
+
+    /// Sets whether subsequently constructed dataflows validate the progress their operators report.
+    ///
+    /// Operators that misuse capabilities otherwise show up as frontiers that never advance, or as
+    /// panics far from their cause. When checking, a dataflow panics, naming the operator and the
+    /// time, as soon as an operator drops a capability it does not hold, or acquires a capability
+    /// or sends messages at a time it holds no capability for and has consumed no message at. On a
+    /// single worker, dataflows also check that operators consume only messages sent to them.
+    ///
+    /// Checking keeps a copy of the capabilities each operator holds, and is meant for debugging.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Exchange, Probe};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///     worker.set_progress_checking(true);
+    ///     let probe = worker.dataflow::<u64,_,_>(|scope| {
+    ///         (0..10).to_stream(scope).exchange(|x| *x).probe()
+    ///     });
+    ///     while !probe.done() {
+    ///         worker.step();
+    ///     }
+    /// }).unwrap();
+    /// ```
+    pub fn set_progress_checking(&mut self, checking: bool) {
+        *self.progress_checking.borrow_mut() = checking;
+    }
+
     /// Calls `self.step()` as long as `func` evaluates to true.
     pub fn step_while<F: FnMut()->bool>(&mut self, mut func: F) {
         while func() { self.step(); }
@@ -102,19 +359,117 @@ impl<A: Allocate> Worker<A> {
         self.dataflow_using(Box::new(()), |_, child| func(child))
     }
 
+    /// Construct a new dataflow with a name.
+    ///
+    /// The name identifies the dataflow in logs and in the descriptions returned by
+    /// `installed_dataflows`; dataflows constructed by `dataflow` are named "Dataflow".
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///     worker.dataflow_named::<u64,_,_>("Numbers", |scope| {
+    ///         (0..10).to_stream(scope)
+    ///                .inspect(|x| println!("seen: {:?}", x));
+    ///     });
+    ///     assert_eq!(worker.installed_dataflows()[0].name, "Numbers");
+    /// }).unwrap();
+    /// ```
+    pub fn dataflow_named<T: Timestamp, R, F:FnOnce(&mut Child<Self, T>)->R>(&mut self, name: &str, func: F) -> R {
+        self.dataflow_core(name, Box::new(()), |_, child| func(child))
+    }
+
     /// Construct a new dataflow binding resources that are released only after the dataflow is dropped.
     ///
     /// This method is designed to allow the dataflow builder to use certain resources that are then stashed
     /// with the dataflow until it has completed running. Once complete, the resources are dropped. The most
     /// common use of this method at present is with loading shared libraries, where the library is important
     /// for building the dataflow, and must be kept around until after the dataflow has completed operation.
-    pub fn dataflow_using<T: Timestamp, R, F:FnOnce(&mut V, &mut Child<Self, T>)->R, V: Any+'static>(&mut self, mut resources: V, func: F) -> R {
+    pub fn dataflow_using<T: Timestamp, R, F:FnOnce(&mut V, &mut Child<Self, T>)->R, V: Any+'static>(&mut self, resources: V, func: F) -> R {
+        self.dataflow_core("Dataflow", resources, func)
+    }
+
+    /// Construct a new dataflow with a name, binding resources that are released only after the dataflow is dropped.
+    pub fn dataflow_core<T: Timestamp, R, F:FnOnce(&mut V, &mut Child<Self, T>)->R, V: Any+'static>(&mut self, name: &str, resources: V, func: F) -> R {
+        self.dataflow_indexed_core(name, resources, func).1
+    }
+
+    /// Construct a new dataflow, returning its index along with the result of `func`.
+    ///
+    /// Each worker assigns indices to its dataflows in the order it constructs them, and so workers
+    /// that construct the same dataflows in the same order agree on their indices. The index
+    /// identifies the dataflow to `drop_dataflow` and in the descriptions returned by
+    /// `installed_dataflows`.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///     let (index, ()) = worker.dataflow_indexed::<u64,_,_>(|scope| {
+    ///         (0..10).to_stream(scope)
+    ///                .inspect(|x| println!("seen: {:?}", x));
+    ///     });
+    ///     assert_eq!(worker.installed_dataflows()[0].index, index);
+    /// }).unwrap();
+    /// ```
+    pub fn dataflow_indexed<T: Timestamp, R, F:FnOnce(&mut Child<Self, T>)->R>(&mut self, func: F) -> (usize, R) {
+        self.dataflow_indexed_core("Dataflow", Box::new(()), |_, child| func(child))
+    }
+
+    /// Construct a new dataflow with a name, binding resources that are released only after the
+    /// dataflow is dropped, and return its index along with the result of `func`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the graph of the dataflow is invalid, as described by `try_dataflow`.
+    pub fn dataflow_indexed_core<T: Timestamp, R, F:FnOnce(&mut V, &mut Child<Self, T>)->R, V: Any+'static>(&mut self, name: &str, resources: V, func: F) -> (usize, R) {
+        match self.build_dataflow(name, resources, func) {
+            Ok(result) => result,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Construct a new dataflow, or describe the problems with its graph.
+    ///
+    /// The graph of a dataflow, or of a scope within it, is invalid if an input of an operator is
+    /// never connected, for example the handle of a loop variable that is not passed to
+    /// `connect_loop`, if the stream of a loop variable is never used, or if a cycle does not
+    /// advance timestamps. Such a dataflow could not make progress, and is not installed; instead
+    /// the error names the operators involved by their names and addresses. Methods that construct
+    /// dataflows without returning errors panic with the same description.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{LoopVariable, ToStream, Concat, Inspect};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///     let error = worker.try_dataflow::<u64,_,_>(|scope| {
+    ///         // the handle is never passed to `connect_loop`.
+    ///         let (_handle, cycle) = scope.loop_variable::<u64>(10, 1);
+    ///         (0..10).to_stream(scope)
+    ///                .concat(&cycle)
+    ///                .inspect(|x| println!("seen: {:?}", x));
+    ///     }).err().expect("invalid dataflow constructed");
+    ///     assert!(error.contains("is never passed to `connect_loop`"));
+    ///     assert!(worker.installed_dataflows().is_empty());
+    /// }).unwrap();
+    /// ```
+    pub fn try_dataflow<T: Timestamp, R, F:FnOnce(&mut Child<Self, T>)->R>(&mut self, func: F) -> Result<R, String> {
+        self.build_dataflow("Dataflow", Box::new(()), |_, child| func(child)).map(|(_index, result)| result)
+    }
+
+    // constructs and installs a dataflow, returning its index, unless its graph is invalid.
+    fn build_dataflow<T: Timestamp, R, F:FnOnce(&mut V, &mut Child<Self, T>)->R, V: Any+'static>(&mut self, name: &str, mut resources: V, func: F) -> Result<(usize, R), String> {
 
         let addr = vec![self.allocator.borrow().index()];
         let dataflow_index = self.allocate_dataflow_index();
+        let first_identifier = *self.identifiers.borrow();
 
         let mut logging = self.logging.borrow_mut().get("timely");
-        let subscope = SubgraphBuilder::new_from(dataflow_index, addr, logging.clone());
+        let mut subscope = SubgraphBuilder::new_from(dataflow_index, addr, logging.clone());
+        subscope.name = name.to_owned();
         let subscope = RefCell::new(subscope);
 
         let result = {
@@ -128,22 +483,285 @@ impl<A: Allocate> Worker<A> {
 
         logging.as_mut().map(|l| l.flush());
 
-        let mut operator = subscope.into_inner().build(self);
+        let built = subscope.into_inner().try_build(self);
+
+        // Identifiers allocated while building the dataflow, including those of its channels.
+        let identifiers = first_identifier .. *self.identifiers.borrow();
+
+        let mut operator = match built {
+            Ok(operator) => operator,
+            Err(error) => {
+                // Release what the dataflow acquired, as though it were dropped.
+                self.drops.borrow_mut().take_pending(dataflow_index);
+                self.release_dataflow(dataflow_index, identifiers);
+                return Err(error);
+            },
+        };
 
         operator.get_internal_summary();
         operator.set_external_summary(Vec::new(), &mut []);
 
         let wrapper = Wrapper {
-            _index: dataflow_index,
+            index: dataflow_index,
+            name: name.to_owned(),
+            scheduled: false,
+            frontier: Box::new(()),
+            progress_at: Instant::now(),
+            stall_reported: false,
+            identifiers,
             operate: Some(Box::new(operator)),
             resources: Some(Box::new(resources)),
         };
         self.dataflows.borrow_mut().push(wrapper);
 
-        result
+        // Another worker may have requested the dataflow be dropped before we installed it.
+        if self.drops.borrow_mut().take_pending(dataflow_index) {
+            self.drop_local(dataflow_index);
+        }
+
+        Ok((dataflow_index, result))
 
     }
 
+    /// Describes the dataflows installed in this worker, which have neither completed nor been
+    /// dropped, in the order they were constructed.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::InputHandle;
+    /// use timely::dataflow::operators::{Input, Exchange, Probe};
+    /// use timely::progress::frontier::Antichain;
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     let mut input = InputHandle::new();
+    ///     let probe = worker.dataflow_named::<u64,_,_>("Exchange", |scope| {
+    ///         scope.input_from(&mut input)
+    ///              .exchange(|x| *x)
+    ///              .probe()
+    ///     });
+    ///
+    ///     input.send(0);
+    ///     input.advance_to(3);
+    ///     while probe.less_than(input.time()) {
+    ///         worker.step();
+    ///     }
+    ///
+    ///     let dataflows = worker.installed_dataflows();
+    ///     assert_eq!(dataflows.len(), 1);
+    ///     assert_eq!(dataflows[0].name, "Exchange");
+    ///     assert_eq!(dataflows[0].operators, 3);
+    ///     assert_eq!(dataflows[0].frontier::<u64>(), Some(&Antichain::from_elem(3)));
+    /// }).unwrap();
+    /// ```
+    pub fn installed_dataflows(&self) -> Vec<DataflowInfo> {
+        self.dataflows.borrow_mut().iter_mut().filter_map(|dataflow| {
+            let index = dataflow.index;
+            let name = dataflow.name.clone();
+            let scheduled = dataflow.scheduled;
+            dataflow.operate.as_mut().map(|operate| DataflowInfo {
+                index,
+                name,
+                operators: operate.operators(),
+                scheduled,
+                frontier: operate.frontier(),
+                formatted: operate.formatted_frontier(),
+            })
+        }).collect()
+    }
+
+    /// Snapshots the progress state of each operator of the dataflows installed in this worker.
+    ///
+    /// Each snapshot describes an operator at its path from the worker, including the operators
+    /// within nested scopes, with the frontiers of its inputs, the capabilities it holds, and the
+    /// messages sent to it but not yet received. The state is that recorded by the operator's
+    /// scope in the worker's most recent step.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///     worker.dataflow::<u64,_,_>(|scope| {
+    ///         (0..10).to_stream(scope)
+    ///                .inspect(|x| println!("seen: {:?}", x));
+    ///     });
+    ///     for snapshot in worker.operator_snapshots() {
+    ///         println!("{:?} {}: {:?}", snapshot.address, snapshot.name, snapshot.capabilities);
+    ///     }
+    /// }).unwrap();
+    /// ```
+    pub fn operator_snapshots(&self) -> Vec<OperatorSnapshot> {
+        let mut snapshots = Vec::new();
+        for dataflow in self.dataflows.borrow_mut().iter_mut() {
+            if let Some(ref mut operate) = dataflow.operate {
+                operate.snapshot_operators(&mut snapshots);
+            }
+        }
+        snapshots
+    }
+
+    /// Explains why the frontier of `probe` has not passed `time`.
+    ///
+    /// The result lists the pointstamps, capabilities held by operators and messages not yet
+    /// received, that hold the frontier of the probe at or before `time`, as recorded by the
+    /// probe's scope in the worker's most recent step. Pointstamps may be held by other workers.
+    /// If the frontier of the probe is held back by data entering its scope, the pointstamps at
+    /// the inputs of the scope are listed; those outside of the scope are not.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::InputHandle;
+    /// use timely::dataflow::operators::{Input, Exchange, Probe};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     let mut input = InputHandle::new();
+    ///     let probe = worker.dataflow::<u64,_,_>(|scope| {
+    ///         scope.input_from(&mut input)
+    ///              .exchange(|x| *x)
+    ///              .probe()
+    ///     });
+    ///
+    ///     input.send(0);
+    ///     input.advance_to(1);
+    ///     while probe.less_than(input.time()) {
+    ///         worker.step();
+    ///     }
+    ///
+    ///     // the input holds a capability for the time it has advanced to.
+    ///     let blockers = worker.probe_blockers(&probe, input.time());
+    ///     assert!(blockers.iter().any(|blocker| blocker.name == "Input"));
+    /// }).unwrap();
+    /// ```
+    pub fn probe_blockers<T: Timestamp>(&self, probe: &ProbeHandle<T>, time: &T) -> Vec<Blocker> {
+        let mut blockers = Vec::new();
+        for address in probe.addresses() {
+            for dataflow in self.dataflows.borrow_mut().iter_mut() {
+                if address.get(1) == Some(&dataflow.index) {
+                    if let Some(ref mut operate) = dataflow.operate {
+                        operate.find_blockers(&address[..], time, &mut blockers);
+                    }
+                }
+            }
+        }
+        blockers
+    }
+
+    /// Drops the dataflow with index `index` at all workers, stopping its operators.
+    ///
+    /// The dataflow is dropped at this worker immediately, and at each other worker in its first
+    /// step after the request arrives, or once it constructs the dataflow if it has not yet done so.
+    /// Dropping a dataflow drops its operators, with any capabilities they hold, and the resources
+    /// bound with it, discards activations its operators have requested, and releases its channels.
+    /// Data sent to the dataflow, for example through an input handle, is discarded. Any worker that
+    /// has constructed a dataflow may drop it, and requests to drop dataflows that have already
+    /// completed or been dropped, or that this worker has not constructed, are ignored.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::InputHandle;
+    /// use timely::dataflow::operators::{Input, Exchange, Probe};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     let mut input = InputHandle::new();
+    ///     let (index, probe) = worker.dataflow_indexed::<u64,_,_>(|scope| {
+    ///         scope.input_from(&mut input)
+    ///              .exchange(|x| *x)
+    ///              .probe()
+    ///     });
+    ///
+    ///     input.send(0);
+    ///     worker.step();
+    ///
+    ///     // the input is never closed, but the dataflow is dropped.
+    ///     if worker.index() == 0 {
+    ///         worker.drop_dataflow(index);
+    ///     }
+    ///     while !worker.installed_dataflows().is_empty() {
+    ///         worker.step();
+    ///     }
+    /// }).unwrap();
+    /// ```
+    pub fn drop_dataflow(&mut self, index: usize) {
+        if index < *self.dataflow_counter.borrow() {
+            let worker = self.index();
+            self.drops.borrow_mut().broadcast(worker, index);
+            self.drop_local(index);
+        }
+    }
+
+    // reports dataflows whose frontiers have not advanced, with no operator scheduled, for `timeout`.
+    fn check_stalls(&mut self, timeout: Duration) {
+        let now = Instant::now();
+        let mut stalled = Vec::new();
+        for dataflow in self.dataflows.borrow_mut().iter_mut() {
+            if let Some(ref mut operate) = dataflow.operate {
+                if operate.frontier_changed(&mut dataflow.frontier) || dataflow.scheduled {
+                    dataflow.progress_at = now;
+                    dataflow.stall_reported = false;
+                }
+                else if !dataflow.stall_reported && now.duration_since(dataflow.progress_at) >= timeout {
+                    dataflow.stall_reported = true;
+                    stalled.push((dataflow.index, dataflow.name.clone(), operate.formatted_frontier(), now.duration_since(dataflow.progress_at)));
+                }
+            }
+        }
+
+        for (index, name, frontier, elapsed) in stalled {
+            let operators = self.operator_snapshots().into_iter().filter(|snapshot| {
+                snapshot.address.get(1) == Some(&index) && snapshot.capabilities.iter().any(|times| !times.is_empty())
+            }).map(|snapshot| {
+                let mut times = Vec::new();
+                for &(ref time, _count) in snapshot.capabilities.iter().flat_map(|times| times.iter()) {
+                    if !times.contains(time) { times.push(time.clone()); }
+                }
+                (snapshot.id, snapshot.name, snapshot.address, times)
+            }).collect::<Vec<_>>();
+
+            eprintln!("timely: worker {}: dataflow {} ({:?}) has not advanced from frontier {:?} for {:?}",
+                      self.index(), index, name, frontier, elapsed);
+            for &(id, ref name, ref address, ref times) in operators.iter() {
+                eprintln!("timely:     operator {} ({:?}, id {}) holds capabilities at {:?}", name, address, id, times);
+            }
+
+            if let Some(logger) = self.logging.borrow_mut().get::<::logging::TimelyEvent>("timely") {
+                logger.log(::logging::StallEvent {
+                    dataflow: index,
+                    operators: operators.into_iter().map(|(id, name, _address, times)| (id, name, times)).collect(),
+                });
+            }
+        }
+    }
+
+    // drops the dataflow at this worker, or records the request if it is not yet constructed.
+    fn drop_local(&mut self, index: usize) {
+        if index >= *self.dataflow_counter.borrow() {
+            self.drops.borrow_mut().defer(index);
+        }
+        else {
+            let position = self.dataflows.borrow().iter().position(|dataflow| dataflow.index == index);
+            if let Some(position) = position {
+                let dataflow = self.dataflows.borrow_mut().remove(position);
+                self.release_dataflow(dataflow.index, dataflow.identifiers.clone());
+            }
+        }
+    }
+
+    // releases the channels of a dataflow that has completed or been dropped, and discards the
+    // activations its operators requested.
+    fn release_dataflow(&self, index: usize, identifiers: Range<usize>) {
+        let mut allocator = self.allocator.borrow_mut();
+        let mut paths = self.paths.borrow_mut();
+        for identifier in identifiers {
+            allocator.release(identifier);
+            paths.remove(&identifier);
+        }
+        let worker = allocator.index();
+        self.activations.borrow_mut().discard(&[worker, index]);
+    }
+
     // sane way to get new dataflow identifiers; used to be self.dataflows.len(). =/
     fn allocate_dataflow_index(&mut self) -> usize {
         *self.dataflow_counter.borrow_mut() += 1;
@@ -156,9 +774,14 @@ use communication::Message;
 impl<A: Allocate> Allocate for Worker<A> {
     fn index(&self) -> usize { self.allocator.borrow().index() }
     fn peers(&self) -> usize { self.allocator.borrow().peers() }
+    fn processes(&self) -> Vec<Range<usize>> { self.allocator.borrow().processes() }
     fn allocate<D: Data>(&mut self, identifier: usize) -> (Vec<Box<Push<Message<D>>>>, Box<Pull<Message<D>>>) {
         self.allocator.borrow_mut().allocate(identifier)
     }
+    fn backpressured(&self) -> bool { self.allocator.borrow().backpressured() }
+    fn release(&mut self, identifier: usize) { self.allocator.borrow_mut().release(identifier) }
+    fn drain_events(&mut self, channels: &mut Vec<usize>) { self.allocator.borrow_mut().drain_events(channels) }
+    fn await_events(&self, duration: Option<Duration>) { self.allocator.borrow().await_events(duration) }
 }
 
 impl<A: Allocate> Clone for Worker<A> {
@@ -169,13 +792,152 @@ impl<A: Allocate> Clone for Worker<A> {
             dataflows: self.dataflows.clone(),
             dataflow_counter: self.dataflow_counter.clone(),
             logging: self.logging.clone(),
+            activations: self.activations.clone(),
+            paths: self.paths.clone(),
+            events: self.events.clone(),
+            drops: self.drops.clone(),
+            stall_timeout: self.stall_timeout.clone(),
+            progress_mode: self.progress_mode.clone(),
+            progress_batching: self.progress_batching.clone(),
+            progress_checking: self.progress_checking.clone(),
+        }
+    }
+}
+
+// Requests to drop dataflows, exchanged among all workers on a dedicated channel.
+struct DropRequests {
+    pushers: Vec<Box<Push<Message<usize>>>>,   // sends requests to each worker.
+    puller: Box<Pull<Message<usize>>>,          // receives requests from other workers.
+    pending: Vec<usize>,                        // distinct requested dataflows not yet constructed.
+}
+
+impl DropRequests {
+    fn new<A: Allocate>(allocator: &mut A, identifier: usize) -> Self {
+        let (pushers, puller) = allocator.allocate(identifier);
+        DropRequests {
+            pushers,
+            puller,
+            pending: Vec::new(),
+        }
+    }
+    // sends a request to drop dataflow `index` to each worker other than `worker`.
+    fn broadcast(&mut self, worker: usize, index: usize) {
+        for (target, pusher) in self.pushers.iter_mut().enumerate() {
+            if target != worker {
+                pusher.push(&mut Some(Message::from_typed(index)));
+                pusher.push(&mut None);
+            }
+        }
+    }
+    // receives the indices of dataflows other workers have requested be dropped.
+    fn receive(&mut self) -> Vec<usize> {
+        let mut indices = Vec::new();
+        while let Some(message) = self.puller.pull() {
+            indices.push(**message);
         }
+        indices
+    }
+    // records a request to drop dataflow `index` once it is constructed, which several workers may make.
+    fn defer(&mut self, index: usize) {
+        if !self.pending.contains(&index) {
+            self.pending.push(index);
+        }
+    }
+    // removes a pending request to drop dataflow `index`, indicating whether there was one.
+    fn take_pending(&mut self, index: usize) -> bool {
+        let count = self.pending.len();
+        self.pending.retain(|&pending| pending != index);
+        count > self.pending.len()
+    }
+}
+
+/// A description of a dataflow installed in a worker.
+#[derive(Clone)]
+pub struct DataflowInfo {
+    /// The index of the dataflow, in the order the worker constructed its dataflows.
+    pub index: usize,
+    /// The name of the dataflow.
+    pub name: String,
+    /// The number of operators in the dataflow, each nested scope counting as one operator.
+    pub operators: usize,
+    /// Indicates that operators of the dataflow were scheduled in the worker's most recent step.
+    pub scheduled: bool,
+    frontier: Rc<Any>,          // the `Antichain<T>` of the dataflow's timestamp type `T`.
+    formatted: Vec<String>,     // the frontier, each time formatted with `Debug`.
+}
+
+impl DataflowInfo {
+    /// The times at which the dataflow's operators may yet produce data, if its timestamp type is
+    /// `T`, and otherwise `None`.
+    ///
+    /// This is the lower bound of the frontiers at the inputs of the dataflow's operators, which
+    /// probes of the dataflow observe, and of the capabilities its operators hold, as of the
+    /// worker's most recent step. Once it has advanced beyond a time, the dataflow can produce no
+    /// more data at that time.
+    pub fn frontier<T: Timestamp>(&self) -> Option<&Antichain<T>> {
+        self.frontier.downcast_ref()
+    }
+}
+
+impl Debug for DataflowInfo {
+    fn fmt(&self, f: &mut Formatter) -> ::std::fmt::Result {
+        f.debug_struct("DataflowInfo")
+            .field("index", &self.index)
+            .field("name", &self.name)
+            .field("operators", &self.operators)
+            .field("scheduled", &self.scheduled)
+            .field("frontier", &self.formatted)
+            .finish()
+    }
+}
+
+// The root scope of a dataflow, as seen by the worker.
+trait Dataflow: Operate<RootTimestamp> {
+    // the number of operators in the dataflow.
+    fn operators(&self) -> usize;
+    // the frontier of times at which operators may yet produce data, as an `Antichain<T>`.
+    fn frontier(&mut self) -> Rc<Any>;
+    // the same frontier, each time formatted with `Debug`.
+    fn formatted_frontier(&mut self) -> Vec<String>;
+    // whether the frontier differs from `last`, the frontier at an earlier call, which it then replaces.
+    fn frontier_changed(&mut self, last: &mut Box<Any>) -> bool;
+}
+
+impl<T: Timestamp> Dataflow for Subgraph<RootTimestamp, T> {
+    fn operators(&self) -> usize { Subgraph::operators(self) }
+    fn frontier(&mut self) -> Rc<Any> {
+        let mut frontier = Antichain::new();
+        for time in Subgraph::frontier(self).elements().iter() {
+            frontier.insert(time.inner.clone());
+        }
+        Rc::new(frontier)
+    }
+    fn formatted_frontier(&mut self) -> Vec<String> {
+        Subgraph::frontier(self).elements().iter().map(|time| format!("{:?}", time.inner)).collect()
+    }
+    fn frontier_changed(&mut self, last: &mut Box<Any>) -> bool {
+        let frontier = Subgraph::frontier(self);
+        let changed = match last.downcast_ref::<Antichain<Product<RootTimestamp, T>>>() {
+            Some(last) => {
+                last.elements().len() != frontier.elements().len() ||
+                frontier.elements().iter().any(|time| !last.elements().contains(time))
+            },
+            None => true,
+        };
+        if changed { *last = Box::new(frontier); }
+        changed
     }
 }
 
 struct Wrapper {
-    _index: usize,
-    operate: Option<Box<Operate<RootTimestamp>>>,
+    index: usize,
+    name: String,
+    scheduled: bool,            // operators were scheduled in the most recent step.
+    frontier: Box<Any>,         // the frontier as last observed by `check_stalls`.
+    progress_at: Instant,       // when the dataflow was last scheduled or its frontier changed.
+    stall_reported: bool,       // the dataflow has been reported as stalled since then.
+    identifiers: ::std::ops::Range<usize>,
+    operate: Option<Box<Dataflow>>,
     resources: Option<Box<Any>>,
 }
 
@@ -194,7 +956,7 @@ impl Wrapper {
 
 impl Drop for Wrapper {
     fn drop(&mut self) {
-        // println!("dropping dataflow {:?}", self._index);
+        // println!("dropping dataflow {:?}", self.index);
         // ensure drop order
         self.operate = None;
         self.resources = None;
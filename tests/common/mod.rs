@@ -0,0 +1,14 @@
+//! Support shared by tests that run multiple processes.
+
+#![allow(dead_code)]
+
+use std::net::TcpListener;
+
+/// Addresses for `processes` processes on this host, at ports that were free when chosen.
+///
+/// The ports are released before the processes bind them, and so may be taken in between, but
+/// unlike fixed ports they do not collide with tests running in parallel.
+pub fn free_addresses(processes: usize) -> Vec<String> {
+    let listeners = (0 .. processes).map(|_| TcpListener::bind("127.0.0.1:0").expect("failed to bind a free port")).collect::<Vec<_>>();
+    listeners.iter().map(|listener| format!("127.0.0.1:{}", listener.local_addr().unwrap().port())).collect()
+}
@@ -0,0 +1,86 @@
+extern crate timely;
+extern crate abomonation;
+#[macro_use] extern crate abomonation_derive;
+#[macro_use] extern crate timely_derive;
+
+use timely::order::PartialOrder;
+use timely::progress::timestamp::{PathSummary, RootTimestamp};
+use timely::dataflow::InputHandle;
+use timely::dataflow::operators::{Input, Exchange, Probe, Inspect, Filter, Map, Concat, LoopVariable, ConnectLoop};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, PartialOrder, Timestamp)]
+struct Time {
+    event: u64,
+    version: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, PartialOrder, Timestamp)]
+struct Pair(u64, Time);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, PartialOrder, Timestamp)]
+struct Versioned<T> {
+    time: T,
+    version: usize,
+}
+
+#[test]
+fn product_order() {
+    let time = Time { event: 3, version: 1 };
+    assert!(time.less_equal(&time));
+    assert!(time.less_equal(&Time { event: 4, version: 2 }));
+    assert!(!time.less_equal(&Time { event: 4, version: 0 }));
+    assert!(!Time { event: 4, version: 0 }.less_equal(&time));
+    assert!(time.less_than(&Time { event: 3, version: 2 }));
+
+    assert!(Pair(0, time.clone()).less_equal(&Pair(1, Time { event: 3, version: 1 })));
+    assert!(!Pair(0, time.clone()).less_equal(&Pair(1, Time { event: 2, version: 1 })));
+
+    assert!(Versioned { time: 1u64, version: 0 }.less_equal(&Versioned { time: 2, version: 0 }));
+    assert!(!Versioned { time: 1u64, version: 1 }.less_equal(&Versioned { time: 2, version: 0 }));
+}
+
+#[test]
+fn fieldwise_summaries() {
+    let time = Time { event: 3, version: 1 };
+    let summary = TimeSummary { event: 2, version: 0 };
+    assert_eq!(summary.results_in(&time), Some(Time { event: 5, version: 1 }));
+    assert_eq!(TimeSummary { event: u64::max_value(), version: 0 }.results_in(&time), None);
+
+    let other = TimeSummary { event: 0, version: 3 };
+    assert_eq!(summary.followed_by(&other), Some(TimeSummary { event: 2, version: 3 }));
+    assert!(TimeSummary::default().less_equal(&summary));
+    assert!(!summary.less_equal(&other));
+
+    let pair = PairSummary(1, TimeSummary { event: 0, version: 1 });
+    assert_eq!(pair.results_in(&Pair(0, time.clone())), Some(Pair(1, Time { event: 3, version: 2 })));
+
+    let versioned = VersionedSummary { time: 1u64, version: 2 };
+    assert_eq!(versioned.results_in(&Versioned { time: 5u64, version: 0 }), Some(Versioned { time: 6, version: 2 }));
+}
+
+#[test]
+fn derived_timestamp_dataflow() {
+    let results = timely::execute(timely::Config::new().threads(2), |worker| {
+
+        let mut input = InputHandle::new();
+        let probe = worker.dataflow::<Time,_,_>(|scope| {
+            // Each record is retracted in later versions of its event, until it reaches zero.
+            let (handle, cycle) = scope.loop_variable(Time { event: 0, version: 10 }, TimeSummary { event: 0, version: 1 });
+            let stream = scope.input_from(&mut input).concat(&cycle);
+            stream.map(|x: u64| x / 2).filter(|x| *x > 0).exchange(|x| *x).connect_loop(handle);
+            stream.inspect_batch(|time, data| assert!(time.inner.version <= 10 && !data.is_empty()))
+                  .probe()
+        });
+
+        for event in 0 .. 5 {
+            input.send(1 << event);
+            input.advance_to(Time { event: event + 1, version: 0 });
+            let frontier = RootTimestamp::new(Time { event: event + 1, version: 0 });
+            while probe.less_than(&frontier) { worker.step(); }
+            assert!(!probe.less_than(&frontier));
+        }
+
+    }).unwrap().join();
+
+    for result in results { result.unwrap(); }
+}
@@ -0,0 +1,112 @@
+extern crate timely;
+
+mod common;
+
+use std::time::Duration;
+
+use timely::Config;
+use timely::dataflow::InputHandle;
+use timely::dataflow::operators::{Input, Exchange, Probe};
+use timely::dataflow::operators::generic::builder_rc::OperatorBuilder;
+use timely::worker::AsWorker;
+
+#[test] fn drop_dataflow_1w() { drop_dataflow_helper(Config::new().threads(1)); }
+#[test] fn drop_dataflow_3w() { drop_dataflow_helper(Config::new().threads(3)); }
+
+#[test]
+fn drop_dataflow_2p() {
+    let addresses = common::free_addresses(2);
+    let processes = (0 .. 2).map(|process| {
+        let config = Config::new().threads(2).process(process).addresses(addresses.clone());
+        ::std::thread::spawn(move || drop_dataflow_helper(config))
+    }).collect::<Vec<_>>();
+    for process in processes {
+        process.join().unwrap();
+    }
+}
+
+// Drops a dataflow whose input is never closed from the last worker, and then checks that the
+// workers go on to construct and complete another dataflow.
+fn drop_dataflow_helper(config: Config) {
+    timely::execute(config, |worker| {
+
+        let mut input = InputHandle::new();
+        let (index, ()) = worker.dataflow_indexed::<u64,_,_>(|scope| {
+            scope.input_from(&mut input)
+                 .exchange(|x: &u64| *x)
+                 .probe();
+        });
+
+        for value in 0 .. 100 { input.send(value); }
+        input.advance_to(1);
+        for _ in 0 .. 10 { worker.step(); }
+
+        if worker.index() + 1 == worker.peers() {
+            worker.drop_dataflow(index);
+        }
+        while !worker.installed_dataflows().is_empty() {
+            worker.step();
+        }
+
+        // Data for the dropped dataflow is discarded.
+        input.send(100);
+        input.advance_to(2);
+
+        let mut input = InputHandle::new();
+        let probe = worker.dataflow::<u64,_,_>(|scope| {
+            scope.input_from(&mut input)
+                 .exchange(|x: &u64| *x)
+                 .probe()
+        });
+        for value in 0 .. 100 { input.send(value); }
+        input.close();
+        while !probe.done() { worker.step(); }
+
+    }).unwrap().join().into_iter().for_each(|result| result.unwrap());
+}
+
+// Drops a dataflow whose operator holds a capability and requests to be scheduled once a minute
+// has passed, and checks that its timer does not outlive it.
+#[test]
+fn drop_discards_timers() {
+    timely::execute(Config::new(), |worker| {
+
+        let (index, ()) = worker.dataflow_indexed::<u64,_,_>(|scope| {
+            let mut builder = OperatorBuilder::new("Timer".to_owned(), scope.clone());
+            let (_output, _stream) = builder.new_output::<u64>();
+            let activator = builder.activator();
+            builder.build(move |capabilities| {
+                activator.activate_after(Duration::from_secs(60));
+                move |_frontiers| { let _held = &capabilities; }
+            });
+        });
+
+        for _ in 0 .. 10 { worker.step(); }
+        assert!(worker.activations().borrow().empty_for().is_some());
+
+        worker.drop_dataflow(index);
+        assert!(worker.installed_dataflows().is_empty());
+        assert_eq!(worker.activations().borrow().empty_for(), None);
+
+    }).unwrap().join().into_iter().for_each(|result| result.unwrap());
+}
+
+// Checks that a worker's request to drop a dataflow it has not yet constructed is ignored.
+#[test]
+fn drop_unconstructed_ignored() {
+    timely::execute(Config::new(), |worker| {
+
+        worker.drop_dataflow(0);
+
+        let mut input = InputHandle::new();
+        let probe = worker.dataflow::<u64,_,_>(|scope| {
+            scope.input_from(&mut input)
+                 .exchange(|x: &u64| *x)
+                 .probe()
+        });
+        for value in 0 .. 100 { input.send(value); }
+        input.close();
+        while !probe.done() { worker.step(); }
+
+    }).unwrap().join().into_iter().for_each(|result| result.unwrap());
+}
@@ -0,0 +1,75 @@
+extern crate timely;
+
+use timely::Config;
+use timely::dataflow::InputHandle;
+use timely::dataflow::operators::{Input, Exchange, Probe};
+use timely::progress::frontier::Antichain;
+
+// Checks that the frontiers of installed dataflows follow their probes, are available only at
+// their timestamp types, and that completed dataflows are no longer described.
+#[test]
+fn installed_frontiers() {
+    timely::execute(Config::new().threads(2), |worker| {
+
+        let mut input = InputHandle::<u64, u64>::new();
+        let probe = worker.dataflow_named::<u64,_,_>("Numbers", |scope| {
+            scope.input_from(&mut input)
+                 .exchange(|x| *x)
+                 .probe()
+        });
+        let mut other = InputHandle::<u32, u32>::new();
+        worker.dataflow_named::<u32,_,_>("Other", |scope| {
+            scope.input_from(&mut other)
+                 .probe();
+        });
+
+        input.send(0);
+        input.advance_to(3);
+        other.advance_to(5);
+        while probe.less_than(input.time()) { worker.step(); }
+
+        let dataflows = worker.installed_dataflows();
+        assert_eq!(dataflows.len(), 2);
+        assert_eq!(dataflows[0].name, "Numbers");
+        assert_eq!(dataflows[0].frontier::<u64>(), Some(&Antichain::from_elem(3)));
+        assert_eq!(dataflows[0].frontier::<u32>(), None);
+        assert_eq!(dataflows[1].name, "Other");
+        assert_eq!(dataflows[1].frontier::<u64>(), None);
+        assert!(format!("{:?}", dataflows[1]).contains("Other"));
+
+        input.close();
+        while !probe.done() { worker.step(); }
+        while worker.installed_dataflows().len() > 1 { worker.step(); }
+        assert_eq!(worker.installed_dataflows()[0].name, "Other");
+
+        other.close();
+        while !worker.installed_dataflows().is_empty() { worker.step(); }
+
+    }).unwrap().join().into_iter().for_each(|result| result.unwrap());
+}
+
+// Checks that a dataflow is reported as scheduled in the step after data arrive for it, and not
+// once it is idle.
+#[test]
+fn installed_scheduled() {
+    timely::execute(Config::new(), |worker| {
+
+        let mut input = InputHandle::<u64, u64>::new();
+        let probe = worker.dataflow::<u64,_,_>(|scope| {
+            scope.input_from(&mut input)
+                 .probe()
+        });
+        for _ in 0 .. 10 { worker.step(); }
+        assert!(!worker.installed_dataflows()[0].scheduled);
+
+        input.send(0);
+        input.advance_to(1);
+        worker.step();
+        assert!(worker.installed_dataflows()[0].scheduled);
+
+        while probe.less_than(input.time()) { worker.step(); }
+        for _ in 0 .. 10 { worker.step(); }
+        assert!(!worker.installed_dataflows()[0].scheduled);
+
+    }).unwrap().join().into_iter().for_each(|result| result.unwrap());
+}
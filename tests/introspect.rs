@@ -0,0 +1,51 @@
+extern crate timely;
+
+use timely::Config;
+use timely::dataflow::{InputHandle, ProbeHandle, Scope};
+use timely::dataflow::operators::{Input, Enter, Leave, Map, Probe};
+use timely::progress::introspect::Port;
+use timely::progress::nested::product::Product;
+use timely::progress::timestamp::RootTimestamp;
+
+// Checks that operators within nested scopes are described, and that the capability held by an
+// input that is not advanced is found to block probes both outside and inside of a nested scope.
+#[test]
+fn introspect_stuck_input() {
+    timely::execute(Config::new(), |worker| {
+
+        let mut input = InputHandle::new();
+        let mut inner = ProbeHandle::new();
+        let outer = worker.dataflow::<u64,_,_>(|scope| {
+            let stream = scope.input_from(&mut input);
+            scope.scoped::<u64,_,_>(|subscope| {
+                stream.enter(subscope)
+                      .map(|x: u64| x + 1)
+                      .probe_with(&mut inner)
+                      .leave()
+            })
+            .probe()
+        });
+
+        input.send(0);
+        for _ in 0 .. 10 { worker.step(); }
+
+        let snapshots = worker.operator_snapshots();
+        assert!(snapshots.iter().any(|s| s.name == "Map" && s.address.len() == 4));
+        let input_snapshot = snapshots.iter().find(|s| s.name == "Input").unwrap();
+        assert_eq!(input_snapshot.capabilities, vec![vec![("(Root, 0)".to_owned(), 1)]]);
+
+        let blockers = worker.probe_blockers(&outer, &RootTimestamp::new(0));
+        assert!(blockers.iter().any(|b| b.name == "Input" && b.port == Port::Output(0)));
+        assert!(worker.probe_blockers(&outer, &RootTimestamp::new(5)).len() > 0);
+
+        // within the scope, the input is seen through the scope's own input.
+        let time = Product::new(RootTimestamp::new(0), 0);
+        let blockers = worker.probe_blockers(&inner, &time);
+        assert!(blockers.iter().any(|b| b.name == "Subgraph" && b.port == Port::Input(0)));
+
+        input.close();
+        while !outer.done() { worker.step(); }
+        assert!(worker.probe_blockers(&outer, &RootTimestamp::new(0)).is_empty());
+
+    }).unwrap();
+}
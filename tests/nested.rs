@@ -0,0 +1,73 @@
+extern crate timely;
+
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use timely::Config;
+use timely::dataflow::{InputHandle, ProbeHandle, Scope};
+use timely::dataflow::operators::{Input, Exchange, Inspect, Probe, Map, Filter, Concat};
+use timely::dataflow::operators::{LoopVariable, ConnectLoop, Enter, Leave};
+
+#[test] fn nested_loops_1w() { nested_loops_helper(Config::new()); }
+#[test] fn nested_loops_2w() { nested_loops_helper(Config::new().threads(2)); }
+
+// Feeds rounds of records through loops nested three deep, each of which halves its records and
+// returns those not yet zero to its start for one more iteration. Records leave a nested scope before
+// it reports producing them, and may be consumed by the loop around it first. Checks that the probe
+// does not pass a round before all of its records are seen, and that all of them are seen.
+fn nested_loops_helper(config: Config) {
+    let results = timely::execute(config, |worker| {
+        let rounds = 20;
+        let mut input = InputHandle::new();
+        let mut probe = ProbeHandle::new();
+        let counts = Rc::new(RefCell::new(vec![0; rounds as usize]));
+        let counts2 = counts.clone();
+        let probe2 = probe.clone();
+        worker.dataflow::<u64,_,_>(|scope| {
+            let stream = scope.input_from(&mut input);
+            scope.scoped::<u64,_,_>(|outer| {
+                let (handle, cycle) = outer.loop_variable::<u64>(2, 1);
+                let stream = stream.enter(outer).concat(&cycle);
+                let stream = outer.scoped::<u64,_,_>(|middle| {
+                    let (handle, cycle) = middle.loop_variable::<u64>(2, 1);
+                    let stream = stream.enter(middle).concat(&cycle);
+                    let stream = middle.scoped::<u64,_,_>(|inner| {
+                        let (handle, cycle) = inner.loop_variable::<u64>(2, 1);
+                        let stream = stream.enter(inner)
+                                           .concat(&cycle)
+                                           .exchange(|x: &u64| *x)
+                                           .map(|x| x / 2);
+                        stream.filter(|x| *x > 0).connect_loop(handle);
+                        stream.leave()
+                    });
+                    stream.filter(|x| *x > 0).connect_loop(handle);
+                    stream.leave()
+                });
+                stream.filter(|x| *x > 0).connect_loop(handle);
+                stream.leave()
+            })
+            .inspect_batch(move |time, data| {
+                assert!(probe2.less_equal(time), "probe passed {:?}", time);
+                counts2.borrow_mut()[time.inner as usize] += data.len();
+            })
+            .probe_with(&mut probe);
+        });
+
+        for round in 0 .. rounds {
+            if worker.index() == 0 { input.send(round); }
+            input.advance_to(round + 1);
+            while probe.less_than(input.time()) { worker.step(); }
+        }
+        let counts = counts.borrow().clone();
+        counts
+    }).unwrap().join();
+
+    // The counts of records seen at each round, summed across workers.
+    let mut totals = vec![0; 20];
+    for result in results {
+        for (total, count) in totals.iter_mut().zip(result.unwrap()) {
+            *total += count;
+        }
+    }
+    assert_eq!(totals, vec![1, 1, 4, 4, 13, 13, 13, 13, 37, 37, 37, 37, 37, 37, 37, 37, 97, 97, 97, 97]);
+}
@@ -0,0 +1,123 @@
+extern crate timely;
+
+mod common;
+
+use std::rc::Rc;
+use std::cell::Cell;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use timely::Config;
+use timely::dataflow::InputHandle;
+use timely::dataflow::operators::{Input, Exchange, Inspect, Probe};
+
+// Bounds the time parked workers wait in these tests, which must be woken well before.
+const PARK: Duration = Duration::from_secs(60);
+
+// Worker 0 parks until data that the last worker sends after a delay arrive, and reports how many
+// steps it took and how long it waited.
+fn park_until_peer_sends(config: Config) -> Vec<(usize, Duration)> {
+    timely::execute(config, |worker| {
+        let received = Rc::new(Cell::new(0));
+        let received2 = received.clone();
+        let mut input = InputHandle::<u64, u64>::new();
+        let probe = worker.dataflow::<u64,_,_>(|scope| {
+            scope.input_from(&mut input)
+                 .exchange(|_| 0)
+                 .inspect(move |_| received2.set(received2.get() + 1))
+                 .probe()
+        });
+
+        let last = worker.peers() - 1;
+        let start = Instant::now();
+        let mut steps = 0;
+        if worker.index() == last {
+            // Give the receiving worker time to park.
+            thread::sleep(Duration::from_millis(500));
+            input.send(7);
+        }
+        else {
+            // Let the dataflow settle, and the worker become idle.
+            for _ in 0 .. 10 { worker.step(); }
+        }
+        input.close();
+
+        if worker.index() == 0 {
+            while received.get() == 0 {
+                worker.step_or_park(Some(PARK));
+                steps += 1;
+            }
+        }
+        let waited = start.elapsed();
+        while !probe.done() { worker.step_or_park(Some(PARK)); }
+        (steps, waited)
+    }).unwrap().join().into_iter().map(|result| result.unwrap()).collect()
+}
+
+fn assert_woken(steps: usize, waited: Duration) {
+    // A spinning worker steps many times while it waits, and a worker woken only by its timeout
+    // waits for the whole timeout.
+    assert!(steps < 100, "worker stepped {} times while waiting", steps);
+    assert!(waited < PARK / 4, "worker waited {:?}", waited);
+}
+
+#[test]
+fn wakes_on_data_from_thread() {
+    let results = park_until_peer_sends(Config::new().threads(2));
+    assert_woken(results[0].0, results[0].1);
+}
+
+#[test]
+fn wakes_on_data_from_process() {
+    let addresses = common::free_addresses(2);
+    let processes = (0 .. 2).map(|process| {
+        let config = Config::new().process(process).addresses(addresses.clone());
+        thread::spawn(move || park_until_peer_sends(config))
+    }).collect::<Vec<_>>();
+    let results: Vec<_> = processes.into_iter().flat_map(|process| process.join().unwrap()).collect();
+    assert_woken(results[0].0, results[0].1);
+}
+
+// Another thread queues data for the worker and unparks it, and the worker introduces the data
+// through an input handle.
+#[test]
+fn wakes_on_unpark() {
+    timely::execute(Config::new(), |worker| {
+        let received = Rc::new(Cell::new(0));
+        let received2 = received.clone();
+        let mut input = InputHandle::<u64, u64>::new();
+        let probe = worker.dataflow::<u64,_,_>(|scope| {
+            scope.input_from(&mut input)
+                 .inspect(move |x| received2.set(received2.get() + *x))
+                 .probe()
+        });
+        for _ in 0 .. 10 { worker.step(); }
+
+        let (send, recv) = mpsc::channel();
+        let handle = thread::current();
+        let other = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(500));
+            send.send(5u64).unwrap();
+            handle.unpark();
+        });
+
+        let start = Instant::now();
+        let mut steps = 0;
+        while received.get() == 0 {
+            worker.step_or_park(Some(PARK));
+            steps += 1;
+            while let Ok(value) = recv.try_recv() {
+                input.send(value);
+                let next = input.epoch() + 1;
+                input.advance_to(next);
+            }
+        }
+        assert_woken(steps, start.elapsed());
+        assert_eq!(received.get(), 5);
+
+        other.join().unwrap();
+        input.close();
+        while !probe.done() { worker.step_or_park(Some(PARK)); }
+    }).unwrap().join().into_iter().for_each(|result| { result.unwrap(); });
+}
@@ -0,0 +1,95 @@
+extern crate timely;
+
+mod common;
+
+use std::env;
+use std::process::Command;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use timely::Config;
+use timely::communication::WorkerError;
+use timely::dataflow::InputHandle;
+use timely::dataflow::operators::{Input, Exchange, Probe};
+use timely::progress::timestamp::RootTimestamp;
+
+// Set in the environment of the second process, to the name of the test it runs and the
+// addresses of both processes, or the directory through which they share memory.
+const PEER_TEST: &str = "PEER_FAILURE_TEST";
+const PEER_SETTING: &str = "PEER_FAILURE_SETTING";
+
+// Exchanges rounds of records between the processes until a worker fails, counting rounds.
+fn exchange_rounds(config: Config, rounds: Arc<AtomicUsize>) -> Vec<Result<(), WorkerError>> {
+    timely::execute(config, move |worker| {
+        let mut input = InputHandle::new();
+        let probe = worker.dataflow::<u64,_,_>(|scope| scope.input_from(&mut input).exchange(|x| *x).probe());
+        for round in 0 .. {
+            for value in 0 .. 100 { input.send(value); }
+            input.advance_to(round + 1);
+            while probe.less_than(&RootTimestamp::new(round + 1)) { worker.step(); }
+            rounds.fetch_add(1, Ordering::SeqCst);
+        }
+    }).unwrap().join()
+}
+
+// Runs the test `name` as process 0, running the second process as a child that is killed once
+// the processes have exchanged some rounds, and checks that the failure is reported. The setting
+// is passed to `config` to configure each process.
+fn check_peer_failure(name: &str, setting: String, config: fn(usize, &str) -> Config) {
+
+    // Run as the second process, until killed.
+    if env::var(PEER_TEST).ok().as_ref().map(|test| &test[..]) == Some(name) {
+        let setting = env::var(PEER_SETTING).unwrap();
+        exchange_rounds(config(1, &setting), Arc::new(AtomicUsize::new(0)));
+        return;
+    }
+
+    let mut peer = Command::new(env::current_exe().unwrap())
+        .args(&[name, "--exact", "--nocapture"])
+        .env(PEER_TEST, name)
+        .env(PEER_SETTING, &setting)
+        .spawn()
+        .unwrap();
+
+    let rounds = Arc::new(AtomicUsize::new(0));
+    let rounds2 = rounds.clone();
+    let config = config(0, &setting);
+    let survivor = ::std::thread::spawn(move || exchange_rounds(config, rounds2));
+
+    // Kill the second process once the processes have exchanged some rounds.
+    while rounds.load(Ordering::SeqCst) < 20 { ::std::thread::sleep(Duration::from_millis(1)); }
+    peer.kill().unwrap();
+    peer.wait().unwrap();
+
+    for result in survivor.join().unwrap() {
+        match result {
+            Err(WorkerError::PeerFailure(failure)) => {
+                assert_eq!(failure.process, 0);
+                assert_eq!(failure.remote, 1);
+            },
+            other => panic!("expected a peer failure, found {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn peer_failure_reported() {
+    check_peer_failure("peer_failure_reported", common::free_addresses(2).join(","), |process, addresses| {
+        let addresses = addresses.split(',').map(|address| address.to_owned()).collect();
+        Config::new().threads(2).process(process).addresses(addresses)
+    });
+}
+
+#[test]
+fn peer_failure_reported_shared_memory() {
+    let directory = env::temp_dir().join(format!("timely-peer-failure-{}", ::std::process::id()));
+    if env::var(PEER_TEST).is_err() {
+        let _ = ::std::fs::remove_dir_all(&directory);
+        ::std::fs::create_dir_all(&directory).unwrap();
+    }
+    check_peer_failure("peer_failure_reported_shared_memory", directory.to_str().unwrap().to_owned(), |process, directory| {
+        Config::new().threads(2).process(process).processes(2).shared_memory(directory)
+    });
+    ::std::fs::remove_dir_all(&directory).unwrap();
+}
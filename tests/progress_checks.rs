@@ -0,0 +1,103 @@
+extern crate timely;
+
+use timely::Config;
+use timely::dataflow::Scope;
+use timely::dataflow::operators::{ToStream, Exchange, Probe, Map, Filter, Concat, LoopVariable, ConnectLoop};
+use timely::dataflow::operators::generic::builder_raw::OperatorBuilder;
+use timely::dataflow::channels::pact::Pipeline;
+use timely::progress::ChangeBatch;
+use timely::progress::timestamp::RootTimestamp;
+
+// Runs a dataflow checking progress at a single worker, returning the error that stopped it.
+fn check_error<F: Fn(&mut timely::worker::Worker<timely::communication::allocator::Generic>)+Send+Sync+'static>(run: F) -> String {
+    let mut results = timely::execute(Config::new(), move |worker| {
+        worker.set_progress_checking(true);
+        run(worker)
+    }).unwrap().join();
+    match results.pop() {
+        Some(Err(error)) => error.to_string(),
+        _ => panic!("dataflow ran without error"),
+    }
+}
+
+// Builds at `scope` an operator that reports `reports` in its first steps, one per step, activating
+// itself until it has reported all of them.
+fn misuse<G: Scope<Timestamp=::timely::progress::nested::product::Product<RootTimestamp, u64>>>(scope: &mut G, input: bool, reports: Vec<(&'static str, u64, i64)>) {
+    let mut builder = OperatorBuilder::new("Misuse".to_owned(), scope.clone());
+    if input {
+        let stream = (0 .. 1u64).to_stream(scope);
+        builder.new_input(&stream, Pipeline);
+    }
+    let (_output, _stream) = builder.new_output::<u64>();
+    let activator = builder.activator();
+    let mut reports = reports.into_iter();
+    builder.build(
+        |frontiers: &mut [ChangeBatch<_>]| { for frontier in frontiers.iter_mut() { frontier.clear(); } },
+        move |consumed, internal, produced| {
+            if let Some((kind, time, delta)) = reports.next() {
+                match kind {
+                    "consumed" => consumed[0].update(RootTimestamp::new(time), delta),
+                    "internal" => internal[0].update(RootTimestamp::new(time), delta),
+                    "produced" => produced[0].update(RootTimestamp::new(time), delta),
+                    _ => panic!("unknown report {}", kind),
+                }
+                activator.activate();
+            }
+            true
+        }
+    );
+}
+
+#[test]
+fn drop_capability_not_held() {
+    let error = check_error(|worker| {
+        worker.dataflow::<u64,_,_>(|scope| misuse(scope, false, vec![("internal", 5, -1)]));
+        for _ in 0 .. 10 { worker.step(); }
+    });
+    assert!(error.contains("progress check failed: operator Misuse at [0, 0, 1] dropped a capability at"), "{}", error);
+    assert!(error.contains("5) for output 0 that it did not hold"), "{}", error);
+}
+
+#[test]
+fn send_without_capability() {
+    let error = check_error(|worker| {
+        worker.dataflow::<u64,_,_>(|scope| misuse(scope, false, vec![("internal", 0, -1), ("produced", 3, 1)]));
+        for _ in 0 .. 10 { worker.step(); }
+    });
+    assert!(error.contains("progress check failed: operator Misuse at [0, 0, 1] sent messages at"), "{}", error);
+    assert!(error.contains("3) from output 0 without holding a capability"), "{}", error);
+}
+
+#[test]
+fn acquire_without_capability() {
+    let error = check_error(|worker| {
+        worker.dataflow::<u64,_,_>(|scope| misuse(scope, false, vec![("internal", 0, -1), ("internal", 4, 1)]));
+        for _ in 0 .. 10 { worker.step(); }
+    });
+    assert!(error.contains("progress check failed: operator Misuse at [0, 0, 1] acquired a capability at"), "{}", error);
+    assert!(error.contains("4) for output 0 without holding a capability"), "{}", error);
+}
+
+#[test]
+fn consume_messages_never_sent() {
+    let error = check_error(|worker| {
+        worker.dataflow::<u64,_,_>(|scope| misuse(scope, true, vec![("consumed", 7, 1)]));
+        for _ in 0 .. 10 { worker.step(); }
+    });
+    assert!(error.contains("progress check failed: operator Misuse at [0, 0, 1] consumed messages at"), "{}", error);
+    assert!(error.contains("7) on input 0 that were never sent to it"), "{}", error);
+}
+
+#[test]
+fn checked_loop_2w() {
+    timely::execute(Config::new().threads(2), |worker| {
+        worker.set_progress_checking(true);
+        let probe = worker.dataflow::<u64,_,_>(|scope| {
+            let (handle, cycle) = scope.loop_variable(100, 1);
+            let results = (0 .. 1000u64).to_stream(scope).concat(&cycle).map(|x| x / 2).exchange(|x| *x);
+            results.filter(|x| *x > 0).connect_loop(handle);
+            results.probe()
+        });
+        while !probe.done() { worker.step(); }
+    }).unwrap().join().into_iter().for_each(|result| { result.unwrap(); });
+}
@@ -0,0 +1,52 @@
+extern crate timely;
+
+use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+
+use timely::Config;
+use timely::logging::TimelyProgressEvent;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+use timely::dataflow::operators::{ToStream, Exchange, Probe};
+
+#[test]
+fn progress_logging_timestamps() {
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let events2 = events.clone();
+
+    timely::execute(Config::new().threads(2), move |worker| {
+        let events = events2.clone();
+        let index = worker.index();
+        worker.log_register().insert::<TimelyProgressEvent,_>("timely/progress", move |_time, data| {
+            let mut events = events.lock().unwrap();
+            for (_, _, event) in data.drain(..) {
+                events.push((index, event));
+            }
+        });
+        let probe = worker.dataflow::<u64,_,_>(|scope| {
+            (0 .. 100).to_stream(scope).exchange(|x| *x).probe()
+        });
+        while !probe.done() { worker.step(); }
+    }).unwrap();
+
+    let events = events.lock().unwrap();
+    assert!(events.iter().any(|&(_, ref event)| event.is_send));
+
+    // Each worker receives updates that account for every message sent and received.
+    let mut counts = HashMap::new();
+    for &(worker, ref event) in events.iter() {
+        let messages = event.messages.as_any().downcast_ref::<Vec<(usize, usize, Product<RootTimestamp, u64>, i64)>>().expect("unexpected timestamp type");
+        assert_eq!(messages.len(), event.messages.iter().count());
+        if !event.is_send {
+            for &(index, port, ref time, delta) in messages.iter() {
+                *counts.entry((worker, index, port, time.clone())).or_insert(0) += delta;
+            }
+        }
+        for (_index, _port, time, _delta) in event.internal.iter() {
+            assert!(time.as_any().downcast_ref::<Product<RootTimestamp, u64>>().is_some());
+        }
+    }
+    assert!(counts.keys().any(|&(_, _, _, ref time)| time.inner == 0));
+    assert!(counts.values().all(|&count| count == 0));
+}
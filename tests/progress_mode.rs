@@ -0,0 +1,110 @@
+extern crate timely;
+
+mod common;
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use timely::Config;
+use timely::progress::broadcast::{ProgressMode, ProgressBatching};
+use timely::progress::timestamp::RootTimestamp;
+use timely::dataflow::{InputHandle, Scope};
+use timely::dataflow::operators::{Input, Exchange, Probe, Map, Filter, Concat, Inspect};
+use timely::dataflow::operators::{LoopVariable, ConnectLoop, Enter, Leave};
+
+#[test]
+fn hierarchical_3w() {
+    let flat = frontiers_helper(Config::new().threads(3), ProgressMode::Flat, ProgressBatching::Immediate);
+    let hierarchical = frontiers_helper(Config::new().threads(3), ProgressMode::Hierarchical, ProgressBatching::Immediate);
+    assert_eq!(flat, hierarchical);
+}
+
+#[test]
+fn hierarchical_2p() {
+    let flat = frontiers_cluster(ProgressMode::Flat, ProgressBatching::Immediate);
+    let hierarchical = frontiers_cluster(ProgressMode::Hierarchical, ProgressBatching::Immediate);
+    assert_eq!(flat, hierarchical);
+}
+
+#[test]
+fn batching_3w() {
+    let immediate = frontiers_helper(Config::new().threads(3), ProgressMode::Flat, ProgressBatching::Immediate);
+    let steps = frontiers_helper(Config::new().threads(3), ProgressMode::Flat, ProgressBatching::Steps(4));
+    let delay = frontiers_helper(Config::new().threads(3), ProgressMode::Flat, ProgressBatching::Delay(Duration::from_millis(1)));
+    assert_eq!(immediate, steps);
+    assert_eq!(immediate, delay);
+}
+
+#[test]
+fn batching_2p() {
+    let immediate = frontiers_cluster(ProgressMode::Flat, ProgressBatching::Immediate);
+    let steps = frontiers_cluster(ProgressMode::Hierarchical, ProgressBatching::Steps(4));
+    let delay = frontiers_cluster(ProgressMode::Flat, ProgressBatching::Delay(Duration::from_millis(1)));
+    assert_eq!(immediate, steps);
+    assert_eq!(immediate, delay);
+}
+
+// Runs two processes of two workers each, returning the results of all workers in order.
+fn frontiers_cluster(mode: ProgressMode, batching: ProgressBatching) -> Vec<(Vec<Vec<u64>>, Vec<(u64, usize)>)> {
+    let addresses = common::free_addresses(2);
+    let processes = (0 .. 2).map(|process| {
+        let config = Config::new().threads(2).process(process).addresses(addresses.clone());
+        ::std::thread::spawn(move || frontiers_helper(config, mode, batching))
+    }).collect::<Vec<_>>();
+    processes.into_iter().flat_map(|process| process.join().unwrap()).collect()
+}
+
+// Feeds rounds of records through a loop with an exchange, recording for each worker the frontiers
+// at its probe once each round completes, and the counts of output records at each round. Each count
+// is checked to be complete once the probe reports the round complete.
+fn frontiers_helper(config: Config, mode: ProgressMode, batching: ProgressBatching) -> Vec<(Vec<Vec<u64>>, Vec<(u64, usize)>)> {
+    timely::execute(config, move |worker| {
+
+        worker.set_progress_mode(mode);
+        worker.set_progress_batching(batching);
+
+        let counts = Rc::new(RefCell::new(HashMap::new()));
+        let counts2 = counts.clone();
+
+        let mut input = InputHandle::new();
+        let probe = worker.dataflow::<u64,_,_>(|scope| {
+            let stream = scope.input_from(&mut input);
+            scope.scoped::<u64,_,_>(|inner| {
+                let (handle, cycle) = inner.loop_variable(u64::max_value(), 1);
+                let results = stream.enter(inner).concat(&cycle).map(|x: u64| x / 2).exchange(|x| *x);
+                results.filter(|x| *x > 0).connect_loop(handle);
+                results.leave()
+            })
+            .inspect_batch(move |time, data| {
+                *counts2.borrow_mut().entry(time.inner).or_insert(0) += data.len();
+            })
+            .probe()
+        });
+
+        let mut frontiers = Vec::new();
+        let mut completed = Vec::new();
+        for round in 0 .. 10u64 {
+            if worker.index() == 0 {
+                for value in 0 .. 100 { input.send(value * (round + 1)); }
+            }
+            input.advance_to(round + 1);
+            while probe.less_than(&RootTimestamp::new(round + 1)) {
+                worker.step();
+            }
+            frontiers.push(probe.with_frontier(|frontier| frontier.iter().map(|time| time.inner).collect::<Vec<_>>()));
+            completed.push((round, *counts.borrow().get(&round).unwrap_or(&0)));
+        }
+        input.close();
+        while !probe.done() { worker.step(); }
+
+        // Rounds reported complete should have received all of their records.
+        for &(round, count) in completed.iter() {
+            assert_eq!(count, *counts.borrow().get(&round).unwrap_or(&0));
+        }
+
+        (frontiers, completed)
+
+    }).unwrap().join().into_iter().map(|result| result.unwrap()).collect()
+}
@@ -0,0 +1,64 @@
+extern crate timely;
+
+mod common;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use timely::Config;
+use timely::communication::allocator::Generic;
+use timely::dataflow::{InputHandle, ProbeHandle};
+use timely::dataflow::operators::{Input, Exchange, Probe};
+use timely::progress::nested::product::Product;
+use timely::progress::timestamp::RootTimestamp;
+use timely::worker::Worker;
+
+// Builds a dataflow moving the records of `input` to worker 0.
+fn to_first(worker: &mut Worker<Generic>, input: &mut InputHandle<u64, u64>) -> ProbeHandle<Product<RootTimestamp, u64>> {
+    worker.dataflow(|scope| {
+        scope.input_from(input)
+             .exchange(|_| 0)
+             .probe()
+    })
+}
+
+// Process 1 builds both of its dataflows at once, and sends a burst of records on the second, many
+// times the queue budget, while process 0 awaits the completion of the first before building the
+// second. Process 0 must go on receiving the progress updates of the first.
+#[test]
+fn burst_for_unbuilt_dataflow() {
+    let addresses = common::free_addresses(2);
+    let processes = (0 .. 2).map(|process| {
+        let config = Config::new().process(process).addresses(addresses.clone()).queue_budget(1 << 10);
+        thread::spawn(move || timely::execute(config, |worker| {
+            let mut first = InputHandle::new();
+            let mut second = InputHandle::new();
+            let probe1 = to_first(worker, &mut first);
+            let mut probe2 = None;
+            if worker.index() == 1 {
+                probe2 = Some(to_first(worker, &mut second));
+                for record in 0 .. 100000 { second.send(record); }
+                second.advance_to(1);
+                for _ in 0 .. 100 { worker.step(); }
+            }
+
+            first.send(0);
+            first.close();
+            let deadline = Instant::now() + Duration::from_secs(60);
+            while !probe1.done() {
+                assert!(Instant::now() < deadline, "worker {} did not complete the first dataflow", worker.index());
+                worker.step();
+            }
+
+            let probe2 = probe2.unwrap_or_else(|| to_first(worker, &mut second));
+            second.close();
+            while !probe2.done() { worker.step(); }
+        }).unwrap().join())
+    }).collect::<Vec<_>>();
+
+    for process in processes {
+        for result in process.join().unwrap() {
+            result.unwrap();
+        }
+    }
+}
@@ -0,0 +1,82 @@
+extern crate timely;
+
+mod common;
+
+use std::alloc::{GlobalAlloc, System, Layout};
+use std::sync::{Arc, Barrier};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use timely::Config;
+use timely::dataflow::InputHandle;
+use timely::dataflow::operators::{Input, Exchange, Probe};
+
+// Counts the bytes currently allocated by the test process.
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+// Builds and completes many short dataflows in each of two processes, and asserts that memory
+// in use does not grow with their number.
+#[test]
+fn release_completed_dataflows() {
+
+    let addresses = common::free_addresses(2);
+    let barrier = Arc::new(Barrier::new(4));
+
+    let processes = (0 .. 2).map(|process| {
+        let config = Config::new().threads(2).process(process).addresses(addresses.clone()).buffer_shift(10);
+        let barrier = barrier.clone();
+        ::std::thread::spawn(move || {
+            timely::execute(config, move |worker| {
+                let mut run = |rounds: usize| {
+                    for round in 0 .. rounds {
+                        let mut input = InputHandle::new();
+                        let probe = worker.dataflow::<u64,_,_>(|scope| {
+                            scope.input_from(&mut input)
+                                 .exchange(|x: &u64| *x)
+                                 .probe()
+                        });
+                        for value in 0 .. 10 { input.send(value + round as u64); }
+                        input.close();
+                        while !probe.done() { worker.step(); }
+                        // Continue until the dataflow is dropped.
+                        while worker.step() { }
+                    }
+                };
+                // Measure while all workers are between dataflows.
+                let measure = || {
+                    barrier.wait();
+                    let allocated = ALLOCATED.load(Ordering::SeqCst);
+                    barrier.wait();
+                    allocated
+                };
+                run(10);
+                let before = measure();
+                run(100);
+                let after = measure();
+                (before, after)
+            }).unwrap().join()
+        })
+    }).collect::<Vec<_>>();
+
+    for process in processes {
+        for result in process.join().unwrap() {
+            let (before, after) = result.unwrap();
+            assert!(after < before + (1 << 16), "memory grew from {} to {} bytes", before, after);
+        }
+    }
+}
@@ -0,0 +1,68 @@
+extern crate timely;
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::path::PathBuf;
+
+use timely::Config;
+use timely::dataflow::InputHandle;
+use timely::dataflow::operators::{Input, Exchange, Inspect, Probe};
+use timely::progress::timestamp::RootTimestamp;
+
+#[test]
+fn shared_memory_2p() {
+    let directory = directory("2p");
+    let sums = exchange_cluster(&directory, None, 10, 1000);
+    assert_eq!(sums.iter().sum::<u64>(), 10 * (0 .. 1000).sum::<u64>());
+    // Sockets and rings are removed once processes have connected.
+    assert_eq!(::std::fs::read_dir(&directory).unwrap().count(), 0);
+}
+
+#[test]
+fn shared_memory_small_rings() {
+    // Rings much smaller than the data exchanged wrap around, and their writers wait for space.
+    let directory = directory("small");
+    let sums = exchange_cluster(&directory, Some(4096), 20, 10000);
+    assert_eq!(sums.iter().sum::<u64>(), 20 * (0 .. 10000).sum::<u64>());
+    assert_eq!(::std::fs::read_dir(&directory).unwrap().count(), 0);
+}
+
+fn directory(name: &str) -> PathBuf {
+    let directory = ::std::env::temp_dir().join(format!("timely-shared-memory-{}-{}", name, ::std::process::id()));
+    let _ = ::std::fs::remove_dir_all(&directory);
+    ::std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+// Runs two processes of two workers each, with rings of `capacity` bytes if set, exchanging
+// `rounds` rounds of `records` records, and returns the sum of the records each worker received.
+fn exchange_cluster(directory: &PathBuf, capacity: Option<usize>, rounds: u64, records: u64) -> Vec<u64> {
+    let processes = (0 .. 2).map(|process| {
+        let mut config = Config::new().threads(2).process(process).processes(2).shared_memory(directory.clone());
+        if let Some(capacity) = capacity { config = config.ring_capacity(capacity); }
+        ::std::thread::spawn(move || {
+            timely::execute(config, move |worker| {
+                let sum = Rc::new(RefCell::new(0));
+                let sum2 = sum.clone();
+                let mut input = InputHandle::new();
+                let probe = worker.dataflow::<u64,_,_>(|scope| {
+                    scope.input_from(&mut input)
+                         .exchange(|x: &u64| *x)
+                         .inspect(move |x| *sum2.borrow_mut() += *x)
+                         .probe()
+                });
+                let peers = worker.peers() as u64;
+                for round in 0 .. rounds {
+                    for record in (0 .. records).filter(|record| record % peers == worker.index() as u64) {
+                        input.send(record);
+                    }
+                    input.advance_to(round + 1);
+                    while probe.less_than(&RootTimestamp::new(round + 1)) { worker.step(); }
+                }
+                let sum = *sum.borrow();
+                sum
+            }).unwrap().join().into_iter().map(|result| result.unwrap()).collect::<Vec<u64>>()
+        })
+    }).collect::<Vec<_>>();
+    processes.into_iter().flat_map(|process| process.join().unwrap()).collect()
+}
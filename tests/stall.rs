@@ -0,0 +1,65 @@
+extern crate timely;
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use timely::Config;
+use timely::dataflow::InputHandle;
+use timely::dataflow::channels::pact::Pipeline;
+use timely::dataflow::operators::{Input, Probe};
+use timely::dataflow::operators::generic::operator::Operator;
+use timely::logging::TimelyEvent;
+
+// Checks that an operator retaining a capability in a `HashMap` is reported, once, when its
+// dataflow stalls.
+#[test]
+fn stall_reported() {
+    timely::execute(Config::new(), |worker| {
+
+        let stalls = Rc::new(RefCell::new(Vec::new()));
+        let stalls2 = stalls.clone();
+        worker.log_register().insert::<TimelyEvent,_>("timely", move |_time, data| {
+            for &(_, _, ref event) in data.iter() {
+                if let TimelyEvent::Stall(ref stall) = *event {
+                    stalls2.borrow_mut().push(stall.clone());
+                }
+            }
+        });
+        worker.set_stall_timeout(Some(Duration::from_millis(50)));
+
+        let mut input = InputHandle::<u64, u64>::new();
+        let (index, probe) = worker.dataflow_indexed::<u64,_,_>(|scope| {
+            scope.input_from(&mut input)
+                 .unary::<u64,_,_,_>(Pipeline, "Stash", |_capability, _info| {
+                     let mut stash = HashMap::new();
+                     move |input, _output| {
+                         input.for_each(|time, _data| {
+                             stash.entry(time.time().clone()).or_insert(time.retain());
+                         });
+                     }
+                 })
+                 .probe()
+        });
+
+        input.send(0);
+        input.close();
+
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(300) {
+            worker.step_or_park(None);
+        }
+
+        assert!(!probe.done());
+        let stalls = stalls.borrow();
+        assert_eq!(stalls.len(), 1);
+        assert_eq!(stalls[0].operators.len(), 1);
+        assert_eq!(stalls[0].operators[0].1, "Stash");
+        assert_eq!(stalls[0].operators[0].2, vec!["(Root, 0)".to_owned()]);
+
+        // the capability is never released.
+        worker.drop_dataflow(index);
+
+    }).unwrap();
+}
@@ -0,0 +1,45 @@
+extern crate timely;
+
+mod common;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use timely::Config;
+use timely::communication::{Allocate, WorkerError};
+
+// Runs process `process` of two, whose worker allocates channel 0 with type `T` and then moves
+// data until it stops, or until a generous deadline passes.
+fn allocate_as<T: timely::communication::Data>(addresses: Vec<String>, process: usize) -> Vec<Result<(), WorkerError>> {
+    let config = Config::new().process(process).addresses(addresses).type_checks(true);
+    timely::communication::initialize(config, |mut allocator| {
+        let (_pushers, _puller) = allocator.allocate::<T>(0);
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while Instant::now() < deadline {
+            allocator.pre_work();
+            allocator.post_work();
+            thread::sleep(Duration::from_millis(1));
+        }
+    }).unwrap().join()
+}
+
+#[test]
+fn type_mismatch_between_processes() {
+    let addresses = common::free_addresses(2);
+    let addresses1 = addresses.clone();
+    let process0 = thread::spawn(move || allocate_as::<u64>(addresses, 0));
+    let process1 = thread::spawn(move || allocate_as::<String>(addresses1, 1));
+    let mut results = process0.join().unwrap();
+    results.extend(process1.join().unwrap());
+
+    // A worker that observes the failure of the other process first reports that instead.
+    let messages: Vec<String> = results.into_iter().map(|result| match result {
+        Err(WorkerError::Panic(message)) => message,
+        Err(WorkerError::PeerFailure(_)) => String::new(),
+        Ok(()) => panic!("worker completed despite a type mismatch"),
+    }).collect();
+    assert!(messages.iter().any(|message| message.contains("type mismatch on channel 0")), "{:?}", messages);
+    for message in messages.iter().filter(|message| !message.is_empty()) {
+        assert!(message.contains("u64") && message.contains("String"), "{}", message);
+    }
+}
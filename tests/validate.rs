@@ -0,0 +1,89 @@
+extern crate timely;
+
+use timely::Config;
+use timely::dataflow::{InputHandle, Scope};
+use timely::dataflow::operators::{Input, LoopVariable, ConnectLoop, ToStream, Concat, Map, Inspect, Probe};
+
+// Constructs a dataflow at a single worker, returning the error that prevented its construction.
+fn build_error<F: Fn(&mut timely::worker::Worker<timely::communication::allocator::Generic>)->Result<(), String>+Send+Sync+'static>(build: F) -> String {
+    let mut results = timely::execute(Config::new(), move |worker| {
+        let error = build(worker).err().expect("dataflow constructed without error");
+        assert!(worker.installed_dataflows().is_empty());
+        error
+    }).unwrap().join();
+    results.pop().unwrap().unwrap()
+}
+
+#[test]
+fn unconnected_loop_variable() {
+    let error = build_error(|worker| {
+        worker.try_dataflow::<u64,_,_>(|scope| {
+            let (_handle, cycle) = scope.loop_variable::<u64>(10, 1);
+            (0..10).to_stream(scope)
+                   .concat(&cycle)
+                   .inspect(|x| println!("seen: {:?}", x));
+        })
+    });
+    assert!(error.contains("loop variable Feedback at [0, 0, 1] is never passed to `connect_loop`"), "{}", error);
+    // the output that was likely meant for the loop variable is listed too.
+    assert!(error.contains("output 0 of operator InspectBatch at [0, 0, 4] is never connected"), "{}", error);
+}
+
+#[test]
+fn unused_loop_variable() {
+    let error = build_error(|worker| {
+        worker.try_dataflow::<u64,_,_>(|scope| {
+            let (handle, _cycle) = scope.loop_variable::<u64>(10, 1);
+            (0..10).to_stream(scope)
+                   .connect_loop(handle);
+        })
+    });
+    assert!(error.contains("the stream of loop variable Feedback at [0, 0, 1] is never used"), "{}", error);
+}
+
+#[test]
+fn cycle_without_increment() {
+    let error = build_error(|worker| {
+        worker.try_dataflow::<u64,_,_>(|scope| {
+            scope.scoped::<u64,_,_>(|inner| {
+                let (handle, cycle) = inner.loop_variable::<u64>(10, 0);
+                cycle.map(|x: u64| x + 1)
+                     .connect_loop(handle);
+            });
+        })
+    });
+    assert!(error.contains("invalid dataflow graph in scope Subgraph at [0, 0, 1]"), "{}", error);
+    assert!(error.contains("a cycle through input 0 of operator Map at [0, 0, 1, 2] does not advance timestamps"), "{}", error);
+}
+
+// Checks that constructing an invalid dataflow panics, and that a worker goes on to construct and
+// complete other dataflows after one it could not construct.
+#[test]
+fn invalid_then_valid() {
+    let mut results = timely::execute(Config::new(), |worker| {
+        worker.dataflow::<u64,_,_>(|scope| {
+            let (_handle, cycle) = scope.loop_variable::<u64>(10, 1);
+            cycle.inspect(|x| println!("seen: {:?}", x));
+        });
+    }).unwrap().join();
+    let error = results.pop().unwrap().err().expect("invalid dataflow constructed").to_string();
+    assert!(error.contains("is never passed to `connect_loop`"), "{}", error);
+
+    timely::execute(Config::new().threads(2), |worker| {
+        let error = worker.try_dataflow::<u64,_,_>(|scope| {
+            let (_handle, cycle) = scope.loop_variable::<u64>(10, 1);
+            cycle.inspect(|x| println!("seen: {:?}", x));
+        });
+        assert!(error.is_err());
+
+        let mut input = InputHandle::new();
+        let probe = worker.dataflow::<u64,_,_>(|scope| {
+            scope.input_from(&mut input)
+                 .map(|x: u64| x + 1)
+                 .probe()
+        });
+        for value in 0 .. 10 { input.send(value); }
+        input.close();
+        while !probe.done() { worker.step(); }
+    }).unwrap().join().into_iter().for_each(|result| result.unwrap());
+}